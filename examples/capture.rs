@@ -0,0 +1,39 @@
+use std::time::Duration;
+
+use win_hotkeys::events::KeyboardInputEvent;
+use win_hotkeys::{CaptureSession, Hotkey, HotkeyManager, VKey};
+
+fn main() {
+    let hkm = HotkeyManager::current();
+    let event_loop_thread = HotkeyManager::start_keyboard_capturing().unwrap();
+
+    println!("Press the shortcut to bind, ENTER to confirm or wait 10 seconds to cancel");
+    let capture = hkm.start_capture(
+        CaptureSession::new()
+            .exit_chord([VKey::Return])
+            .timeout(Duration::from_secs(10))
+            .pass_through([VKey::VolumeUp, VKey::VolumeDown, VKey::VolumeMute]),
+    );
+
+    // the last chord pressed before ENTER is the shortcut, the iteration ends with the session
+    let mut chord = Vec::new();
+    for event in &capture {
+        if let KeyboardInputEvent::KeyDown { state, .. } = event {
+            chord = state.pressing.clone();
+        }
+    }
+    println!("Capture ended: {:?}", capture.end_reason());
+
+    if chord.is_empty() {
+        println!("No shortcut was pressed");
+        HotkeyManager::stop_keyboard_capturing();
+    } else {
+        println!("Binding {chord:?}");
+        hkm.register_hotkey(
+            Hotkey::from_keys(&chord).action(|| println!("Bound shortcut pressed")),
+        )
+        .unwrap();
+    }
+
+    event_loop_thread.join().unwrap();
+}
@@ -0,0 +1,32 @@
+use std::time::Duration;
+
+use win_hotkeys::{Hotkey, HotkeyManager, VKey};
+
+fn main() {
+    let hkm = HotkeyManager::current();
+
+    // Double-tap left SHIFT, single taps still reach other applications
+    hkm.register_hotkey(
+        Hotkey::new(VKey::LShift, [], || {
+            println!("LSHIFT double-tapped, opening launcher");
+        })
+        .taps(2)
+        .pass_through_taps(),
+    )
+    .unwrap();
+
+    // Press CTRL + Q three times within 500ms of each other
+    hkm.register_hotkey(
+        Hotkey::new(VKey::Q, [VKey::Control], || {
+            println!("CTRL + Q pressed three times, quitting");
+            HotkeyManager::stop_keyboard_capturing();
+        })
+        .taps(3)
+        .tap_window(Duration::from_millis(500)),
+    )
+    .unwrap();
+
+    let event_loop_thread = HotkeyManager::start_keyboard_capturing().unwrap();
+    // Block until the event loop thread exits
+    event_loop_thread.join().unwrap();
+}
@@ -0,0 +1,35 @@
+use std::time::Duration;
+use win_hotkeys::{DualRoleKey, HotkeyManager, VKey};
+
+fn main() {
+    let hkm = HotkeyManager::current();
+
+    // CapsLock prints a message when tapped, and acts as CTRL when held
+    hkm.register_dual_role(
+        DualRoleKey::new(VKey::Capital)
+            .on_tap(|| {
+                println!("CapsLock tapped");
+            })
+            .hold_as(VKey::LControl)
+            // CapsLock + C is CTRL + C even if released before the tapping term
+            .permissive_hold(),
+    )
+    .unwrap();
+
+    // Space is still a space when tapped, and a layer-like key when held
+    hkm.register_dual_role(
+        DualRoleKey::new(VKey::Space)
+            .tapping_term(Duration::from_millis(300))
+            .on_hold(|| {
+                println!("Space held");
+            })
+            .on_hold_release(|| {
+                println!("Space released");
+            }),
+    )
+    .unwrap();
+
+    let event_loop_thread = HotkeyManager::start_keyboard_capturing().unwrap();
+    // Block until the event loop thread exits
+    event_loop_thread.join().unwrap();
+}
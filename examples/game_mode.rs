@@ -0,0 +1,35 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use win_hotkeys::{FilterResult, Hotkey, HotkeyManager, VKey};
+
+fn main() {
+    let hkm = HotkeyManager::current();
+    let game_mode = Arc::new(AtomicBool::new(false));
+
+    // While game mode is on the Windows Key is disabled, the other keys are untouched
+    let game_mode_filter = game_mode.clone();
+    hkm.add_filter(0, move |event| {
+        let key = VKey::from(event.vk_code());
+        if key.is_windows_key() && game_mode_filter.load(Ordering::SeqCst) {
+            FilterResult::Block
+        } else {
+            FilterResult::Pass
+        }
+    });
+
+    // CTRL + ALT + G toggles game mode
+    hkm.register_hotkey(Hotkey::new(
+        VKey::G,
+        [VKey::Control, VKey::Menu],
+        move || {
+            let enabled = !game_mode.fetch_xor(true, Ordering::SeqCst);
+            println!("Game mode {}", if enabled { "on" } else { "off" });
+        },
+    ))
+    .unwrap();
+
+    let event_loop_thread = HotkeyManager::start_keyboard_capturing().unwrap();
+    // Block until the event loop thread exits
+    event_loop_thread.join().unwrap();
+}
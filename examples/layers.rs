@@ -0,0 +1,49 @@
+use win_hotkeys::{Hotkey, HotkeyManager, KeyOutput, LayerAction, VKey};
+
+fn main() {
+    let hkm = HotkeyManager::current();
+
+    // "nav" layer, active while CAPSLOCK is held
+    hkm.register_hotkey(
+        Hotkey::new(VKey::Capital, [], || {}).layer_action(LayerAction::Momentary("nav".into())),
+    )
+    .unwrap();
+    for (key, arrow) in [
+        (VKey::H, VKey::Left),
+        (VKey::J, VKey::Down),
+        (VKey::K, VKey::Up),
+        (VKey::L, VKey::Right),
+    ] {
+        hkm.register_layer_hotkey(
+            "nav",
+            Hotkey::new(key, [], || {}).emit(KeyOutput::chord([arrow])),
+        )
+        .unwrap();
+    }
+
+    // "vim-normal" layer, entered with CTRL + ALT + V and left with I
+    hkm.register_hotkey(
+        Hotkey::new(VKey::V, [VKey::Control, VKey::Menu], || {
+            println!("-- NORMAL --");
+        })
+        .layer_action(LayerAction::Push("vim-normal".into())),
+    )
+    .unwrap();
+    hkm.register_layer_hotkey(
+        "vim-normal",
+        Hotkey::new(VKey::I, [], || {
+            println!("-- INSERT --");
+        })
+        .layer_action(LayerAction::Pop),
+    )
+    .unwrap();
+    hkm.register_layer_hotkey(
+        "vim-normal",
+        Hotkey::new(VKey::X, [], || {}).emit(KeyOutput::chord([VKey::Delete])),
+    )
+    .unwrap();
+
+    let event_loop_thread = HotkeyManager::start_keyboard_capturing().unwrap();
+    // Block until the event loop thread exits
+    event_loop_thread.join().unwrap();
+}
@@ -1,8 +1,5 @@
 use std::{thread, time};
 use win_hotkeys::{Hotkey, HotkeyManager, VKey};
-use windows::core::PCWSTR;
-use windows::Win32::Foundation::HWND;
-use windows::Win32::UI::WindowsAndMessaging::{MessageBoxW, MB_OK};
 
 fn main() {
     let hkm = HotkeyManager::current();
@@ -25,7 +22,12 @@ fn main() {
     event_loop_thread.join().unwrap(); // Block until the event loop thread exits
 }
 
+#[cfg(windows)]
 fn show_popup(title: &str, message: &str) {
+    use windows::core::PCWSTR;
+    use windows::Win32::Foundation::HWND;
+    use windows::Win32::UI::WindowsAndMessaging::{MessageBoxW, MB_OK};
+
     unsafe {
         MessageBoxW(
             Some(HWND(std::ptr::null_mut())),
@@ -36,6 +38,12 @@ fn show_popup(title: &str, message: &str) {
     }
 }
 
+#[cfg(not(windows))]
+fn show_popup(title: &str, message: &str) {
+    println!("[{title}] {message}");
+}
+
+#[cfg(windows)]
 fn to_wide_string(value: &str) -> Vec<u16> {
     value.encode_utf16().chain(std::iter::once(0)).collect()
 }
@@ -0,0 +1,29 @@
+use win_hotkeys::{Hotkey, HotkeyManager, TriggerOn, VKey};
+
+fn main() {
+    let hkm = HotkeyManager::current();
+
+    // Callback on press and `on_release` callback when the trigger key is released
+    hkm.register_hotkey(
+        Hotkey::new(VKey::Space, [VKey::Control], || {
+            println!("CTRL + SPACE pressed, talking...");
+        })
+        .on_release(|| {
+            println!("SPACE released, muted");
+        }),
+    )
+    .unwrap();
+
+    // Callback only when the trigger key is released
+    hkm.register_hotkey(
+        Hotkey::new(VKey::O, [VKey::Control, VKey::Shift], || {
+            println!("CTRL + SHIFT + O released");
+        })
+        .trigger_on(TriggerOn::Release),
+    )
+    .unwrap();
+
+    let event_loop_thread = HotkeyManager::start_keyboard_capturing().unwrap();
+    // Block until the event loop thread exits
+    event_loop_thread.join().unwrap();
+}
@@ -0,0 +1,35 @@
+use std::time::Duration;
+
+use win_hotkeys::{HotkeyManager, RecordOptions, RecordedShortcut, VKey};
+
+fn main() {
+    let hkm = HotkeyManager::current();
+    let event_loop_thread = HotkeyManager::start_keyboard_capturing().unwrap();
+
+    println!("Press the shortcut to bind, ESC to cancel");
+    let recorded = hkm.record_shortcut(
+        RecordOptions::new()
+            .timeout(Duration::from_secs(30))
+            .reserve([VKey::Control, VKey::Q]),
+    );
+
+    for shortcut in recorded {
+        match shortcut {
+            RecordedShortcut::Rejected { keys, reason } => {
+                println!("{keys:?} can't be used ({reason:?}), try another one");
+            }
+            RecordedShortcut::Chord { .. } => {
+                println!("Binding {shortcut:?}");
+                let hotkey = shortcut.to_hotkey().unwrap();
+                hkm.register_hotkey(hotkey.action(|| println!("Recorded shortcut pressed")))
+                    .unwrap();
+            }
+            RecordedShortcut::Cancelled => {
+                println!("Recording cancelled");
+                HotkeyManager::stop_keyboard_capturing();
+            }
+        }
+    }
+
+    event_loop_thread.join().unwrap();
+}
@@ -0,0 +1,43 @@
+use win_hotkeys::{Hotkey, HotkeyManager, KeyOutput, KeySender, Remap, VKey};
+
+fn main() {
+    let hkm = HotkeyManager::current();
+
+    // Use CAPSLOCK as a layer key, blocking its own effect
+    hkm.register_hotkey(Hotkey::new(VKey::Capital, [], || {}))
+        .unwrap();
+
+    // CAPSLOCK + HJKL to arrow keys
+    for (key, arrow) in [
+        (VKey::H, VKey::Left),
+        (VKey::J, VKey::Down),
+        (VKey::K, VKey::Up),
+        (VKey::L, VKey::Right),
+    ] {
+        hkm.register_hotkey(
+            Hotkey::from_keys([VKey::Capital, key]).emit(KeyOutput::chord([arrow])),
+        )
+        .unwrap();
+    }
+
+    // Right ALT acts as right CTRL, for as long as it is held
+    hkm.register_remap(Remap::new(VKey::RMenu, VKey::RControl))
+        .unwrap();
+
+    // Type text, independently of the keyboard layout
+    hkm.register_hotkey(
+        Hotkey::from_keys([VKey::Control, VKey::Menu, VKey::E])
+            .emit(KeyOutput::text("¯\\_(ツ)_/¯")),
+    )
+    .unwrap();
+
+    // Send keys from a callback
+    hkm.register_hotkey(Hotkey::new(VKey::S, [VKey::Control, VKey::Shift], || {
+        KeySender::new().send_sequence([[VKey::Control, VKey::S], [VKey::Control, VKey::W]]);
+    }))
+    .unwrap();
+
+    let event_loop_thread = HotkeyManager::start_keyboard_capturing().unwrap();
+    // Block until the event loop thread exits
+    event_loop_thread.join().unwrap();
+}
@@ -0,0 +1,36 @@
+use win_hotkeys::{Hotkey, HotkeyManager, Scope, VKey};
+
+fn main() {
+    let hkm = HotkeyManager::current();
+
+    // CTRL + SHIFT + P only inside Visual Studio Code, other applications receive it as usual
+    hkm.register_hotkey(
+        Hotkey::new(VKey::P, [VKey::Control, VKey::Shift], || {
+            println!("CTRL + SHIFT + P pressed in Visual Studio Code");
+        })
+        .scope(Scope::processes(["code.exe"])),
+    )
+    .unwrap();
+
+    // ALT + M everywhere except on games
+    hkm.register_hotkey(
+        Hotkey::new(VKey::M, [VKey::Menu], || {
+            println!("ALT + M pressed, muting microphone");
+        })
+        .scope(Scope::except_processes(["eldenring.exe", "cs2.exe"])),
+    )
+    .unwrap();
+
+    // F1 on windows titled "Untitled", ex: a new document
+    hkm.register_hotkey(
+        Hotkey::new(VKey::F1, [], || {
+            println!("F1 pressed on an untitled window")
+        })
+        .scope(Scope::custom(|window| window.title.starts_with("Untitled"))),
+    )
+    .unwrap();
+
+    let event_loop_thread = HotkeyManager::start_keyboard_capturing().unwrap();
+    // Block until the event loop thread exits
+    event_loop_thread.join().unwrap();
+}
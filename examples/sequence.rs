@@ -0,0 +1,38 @@
+use std::time::Duration;
+use win_hotkeys::{Chord, HotkeyManager, HotkeySequence, VKey};
+
+fn main() {
+    let hkm = HotkeyManager::current();
+
+    // Register CTRL + K, CTRL + C like code editors do. CTRL + K is swallowed while
+    // waiting for the next chord, and replayed if another key is pressed instead.
+    hkm.register_sequence(HotkeySequence::new(
+        [
+            Chord::new(VKey::K, [VKey::Control]),
+            Chord::new(VKey::C, [VKey::Control]),
+        ],
+        || {
+            println!("Sequence CTRL + K, CTRL + C was pressed");
+        },
+    ))
+    .unwrap();
+
+    // Sequences can share a prefix and use a custom time limit between chords
+    hkm.register_sequence(
+        HotkeySequence::new(
+            [
+                Chord::new(VKey::K, [VKey::Control]),
+                Chord::new(VKey::U, [VKey::Control]),
+            ],
+            || {
+                println!("Sequence CTRL + K, CTRL + U was pressed");
+            },
+        )
+        .timeout(Duration::from_millis(500)),
+    )
+    .unwrap();
+
+    let event_loop_thread = HotkeyManager::start_keyboard_capturing().unwrap();
+    // Block until the event loop thread exits
+    event_loop_thread.join().unwrap();
+}
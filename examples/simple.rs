@@ -37,7 +37,7 @@ fn main() {
         .unwrap();
 
     // Unregister hotkey with ID
-    let _ = hkm.unregister_hotkey(hotkey_id);
+    let _ = hkm.unregister_hotkey(&hotkey_id);
 
     // Run the event handler in a blocking loop. This will block forever and execute the set
     // callbacks when the registered hotkeys are detected
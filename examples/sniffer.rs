@@ -24,10 +24,11 @@ fn main() {
             if let KeyboardInputEvent::KeyDown {
                 vk_code,
                 state: keyboard_state,
+                info,
             } = event
             {
                 let key = VKey::from(vk_code);
-                println!("Global key down: {key:?}");
+                println!("Global key down: {key:?} (scan code: {})", info.scan_code);
 
                 if key == VKey::Return {
                     println!("Enter pressed, stealing mode finished");
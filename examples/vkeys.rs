@@ -1,5 +1,4 @@
 use win_hotkeys::{Hotkey, HotkeyManager, VKey};
-use windows::Win32::UI::Input::KeyboardAndMouse::VK_A;
 
 fn main() {
     // Create HotkeyManager
@@ -8,16 +7,22 @@ fn main() {
     let vk_a1 = VKey::A;
     let vk_a2 = VKey::from_keyname("a").unwrap();
     let vk_a3 = VKey::from_vk_code(0x41);
-    let vk_a4 = VKey::from_vk_code(VK_A.0);
 
     // do not use this variant, instead use `from_vk_code`, btw this will be equivalent
     let vk_a5 = VKey::UnknownOrReserved(0x41);
 
     assert_eq!(vk_a1, vk_a2);
     assert_eq!(vk_a1, vk_a3);
-    assert_eq!(vk_a1, vk_a4);
     assert_eq!(vk_a1, vk_a5);
 
+    // the windows crate constants can be used too
+    #[cfg(windows)]
+    {
+        use windows::Win32::UI::Input::KeyboardAndMouse::VK_A;
+        let vk_a4 = VKey::from_vk_code(VK_A.0);
+        assert_eq!(vk_a1, vk_a4);
+    }
+
     // NOTE
     // When matching `UnknownOrReserved` you must include a guard if statement
     match vk_a5 {
@@ -0,0 +1,540 @@
+//! Defines the [`InputBackend`] trait, the boundary between the hotkey engine and
+//! the source of keyboard input. The Windows `WH_KEYBOARD_LL` hook
+//! ([`crate::hook::HookBackend`]) is the default implementation, while
+//! [`ScriptedBackend`] is an in-memory implementation that allows driving the whole
+//! matching pipeline without a desktop session, e.g. on CI machines.
+
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+use arc_swap::ArcSwapOption;
+
+use crate::error::Result;
+use crate::events::{EventLoopEvent, KeyAction, KeyEventInfo, KeyboardInputEvent};
+use crate::manager::HotkeyManager;
+use crate::scope::WindowContext;
+use crate::state::KEYBOARD_STATE;
+use crate::{log_on_dev, VKey};
+
+/// Timeout for blocking key events, measured in milliseconds.
+pub(crate) const TIMEOUT: Duration = Duration::from_millis(250);
+
+/// Time left to the event loop to send its response before the backend stops waiting.
+pub(crate) const RESPONSE_MARGIN: Duration = Duration::from_millis(25);
+
+/// Unassigned virtual key code, sent to suppress the Windows Key events. Backends must ignore it.
+pub(crate) const SILENT_VK_CODE: u16 = 0xE8;
+
+/// id of the next keyboard event waiting for a response
+static NEXT_EVENT_ID: AtomicU64 = AtomicU64::new(0);
+
+/// the backend feeding the event loop, if capturing is running
+static BACKEND: ArcSwapOption<Box<dyn InputBackend>> = ArcSwapOption::const_empty();
+
+/// A synthetic key event sent to the system by the engine,
+/// e.g. to replay keys that were swallowed while waiting for a [`crate::HotkeySequence`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyInput {
+    Down(VKey),
+    Up(VKey),
+    /// Types a character independently of the keyboard layout, press and release included
+    Char(char),
+}
+
+/// A source of keyboard events for the hotkey engine.
+///
+/// Implementors observe key presses/releases and forward them to the engine, which updates
+/// the shared keyboard state, runs the matching pipeline and answers how the key must be
+/// handled.
+pub trait InputBackend: Send + Sync + 'static {
+    /// Starts observing keyboard input.
+    fn start(&self) -> Result<()>;
+
+    /// Stops observing keyboard input.
+    fn stop(&self);
+
+    /// Returns whether a key is currently pressed according to the input source,
+    /// this is used to resynchronize the tracked keyboard state.
+    fn is_key_down(&self, vk_code: u16) -> bool;
+
+    /// Returns whether a lock key is on, ex: [CAPSLOCK].
+    /// Backends that don't track the lock keys return `false`.
+    fn is_key_toggled(&self, _vk_code: u16) -> bool {
+        false
+    }
+
+    /// Sends synthetic key events to the system. These events must not be
+    /// delivered back to the engine, or they could trigger hotkeys again.
+    fn send_input(&self, inputs: &[KeyInput]);
+
+    /// Returns the foreground window, used to evaluate the scoped hotkeys.
+    /// Backends without a notion of windows return `None`.
+    fn foreground_window(&self) -> Option<WindowContext> {
+        None
+    }
+}
+
+/// Delivers a key press to the engine and waits for the decision on how to handle it.
+/// If the engine doesn't answer in time the key is allowed.
+pub(crate) fn key_down(vk_code: u16, info: KeyEventInfo) -> KeyAction {
+    key_down_at(vk_code, info, Instant::now())
+}
+
+/// Same as [`key_down`] for a press received at `received`, the engine has to answer
+/// within [`TIMEOUT`] of it.
+fn key_down_at(vk_code: u16, info: KeyEventInfo, received: Instant) -> KeyAction {
+    let state = {
+        let mut state = KEYBOARD_STATE.lock().unwrap();
+        state.keydown(vk_code);
+        state.clone()
+    };
+    log_on_dev!("{state:?}");
+    dispatch(
+        KeyboardInputEvent::KeyDown {
+            vk_code,
+            state,
+            info,
+        },
+        received + TIMEOUT,
+    )
+}
+
+/// Delivers a key release to the engine. Unless `wait` is set, the release is allowed
+/// without waiting when the engine can't handle it differently, ex: to end a dual-role key.
+/// If the engine doesn't answer in time the key is allowed.
+pub(crate) fn key_up(vk_code: u16, info: KeyEventInfo, wait: bool) -> KeyAction {
+    let state = {
+        let mut state = KEYBOARD_STATE.lock().unwrap();
+        state.keyup(vk_code);
+        state.clone()
+    };
+    log_on_dev!("{state:?}");
+    let event = KeyboardInputEvent::KeyUp {
+        vk_code,
+        state,
+        info,
+    };
+    if !wait && !HotkeyManager::decides_release(vk_code) {
+        EventLoopEvent::Notify(event).send();
+        return KeyAction::Allow;
+    }
+    dispatch(event, Instant::now() + TIMEOUT)
+}
+
+/// Sends the event to the event loop and waits until `deadline` for the response
+/// on how to handle it.
+fn dispatch(event: KeyboardInputEvent, deadline: Instant) -> KeyAction {
+    let id = NEXT_EVENT_ID.fetch_add(1, Ordering::Relaxed);
+    EventLoopEvent::Keyboard(event, id, deadline).send();
+
+    // the late responses to the previous events are dropped
+    let response_rx = KeyAction::reciever();
+    while let Ok((event_id, action)) = response_rx.recv_deadline(deadline) {
+        if event_id == id {
+            return action;
+        }
+    }
+    KeyAction::Allow
+}
+
+/// Starts the backend and makes it the active one.
+pub(crate) fn install<B: InputBackend>(backend: B) -> Result<()> {
+    backend.start()?;
+    BACKEND.store(Some(Arc::new(Box::new(backend))));
+    Ok(())
+}
+
+/// Stops and removes the active backend, if any.
+pub(crate) fn uninstall() {
+    if let Some(backend) = BACKEND.swap(None) {
+        backend.stop();
+    }
+}
+
+/// Returns whether a key is pressed according to the active backend,
+/// falling back to the OS state when capturing is not running.
+pub(crate) fn async_is_key_down(vk_code: u16) -> bool {
+    if let Some(backend) = BACKEND.load().as_ref() {
+        return backend.is_key_down(vk_code);
+    }
+
+    #[cfg(windows)]
+    return crate::hook::async_is_key_down(vk_code);
+    #[cfg(not(windows))]
+    return false;
+}
+
+/// Returns whether a lock key is on according to the active backend,
+/// falling back to the OS state when capturing is not running.
+pub(crate) fn is_key_toggled(vk_code: u16) -> bool {
+    match BACKEND.load().as_ref() {
+        Some(backend) => backend.is_key_toggled(vk_code),
+        #[cfg(windows)]
+        None => crate::hook::is_key_toggled(vk_code),
+        #[cfg(not(windows))]
+        None => false,
+    }
+}
+
+/// Returns the foreground window according to the active backend,
+/// falling back to the OS when capturing is not running.
+pub(crate) fn foreground_window() -> Option<WindowContext> {
+    match BACKEND.load().as_ref() {
+        Some(backend) => backend.foreground_window(),
+        #[cfg(windows)]
+        None => crate::hook::foreground_window(),
+        #[cfg(not(windows))]
+        None => None,
+    }
+}
+
+/// Sends synthetic key events through the active backend,
+/// falling back to the OS when capturing is not running.
+pub(crate) fn send_input(inputs: &[KeyInput]) {
+    if inputs.is_empty() {
+        return;
+    }
+    match BACKEND.load().as_ref() {
+        Some(backend) => backend.send_input(inputs),
+        #[cfg(windows)]
+        None => crate::hook::send_input(inputs),
+        #[cfg(not(windows))]
+        None => {}
+    }
+}
+
+/// Returns the backend used by [`crate::HotkeyManager::start_keyboard_capturing`].
+#[cfg(windows)]
+pub(crate) fn default_backend() -> Result<crate::hook::HookBackend> {
+    Ok(crate::hook::HookBackend)
+}
+
+/// Returns the backend used by [`crate::HotkeyManager::start_keyboard_capturing`].
+#[cfg(not(windows))]
+pub(crate) fn default_backend() -> Result<ScriptedBackend> {
+    Err(crate::error::WHKError::NoDefaultBackend)
+}
+
+/// In-memory backend where key presses are scripted by the caller instead of
+/// coming from a real keyboard.
+///
+/// Cloned instances share the same simulated keyboard, so a clone can be handed
+/// to [`crate::HotkeyManager::start_keyboard_capturing_with`] while the original drives it.
+#[derive(Debug, Default, Clone)]
+pub struct ScriptedBackend {
+    /// keys physically held on the simulated keyboard
+    pressed: Arc<Mutex<HashSet<u16>>>,
+    /// lock keys that are on, these switch when pressed
+    toggled: Arc<Mutex<HashSet<u16>>>,
+    /// synthetic events sent by the engine
+    sent: Arc<Mutex<Vec<KeyInput>>>,
+    /// notified when the engine sends synthetic events
+    sent_changed: Arc<Condvar>,
+    /// simulated clock in milliseconds, used as timestamp of the key events
+    clock: Arc<AtomicU32>,
+    /// simulated foreground window
+    foreground: Arc<Mutex<Option<WindowContext>>>,
+}
+
+impl ScriptedBackend {
+    /// Creates a new `ScriptedBackend` with all keys released.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Simulates pressing a key, returns how the engine decided to handle it.
+    pub fn press(&self, key: VKey) -> KeyAction {
+        self.press_with(key, self.event_info())
+    }
+
+    /// Simulates releasing a key, returns how the engine decided to handle it.
+    /// Unlike the hook, the releases always wait for the engine, so their effects
+    /// are visible when this returns.
+    pub fn release(&self, key: VKey) -> KeyAction {
+        self.release_with(key, self.event_info())
+    }
+
+    /// Same as [`ScriptedBackend::press`] but with custom hardware information,
+    /// ex: to simulate injected events.
+    pub fn press_with(&self, key: VKey, info: KeyEventInfo) -> KeyAction {
+        let vk_code = key.to_vk_code();
+        let is_repeat = !self.pressed.lock().unwrap().insert(vk_code);
+        if !is_repeat && matches!(key, VKey::Capital | VKey::Numlock | VKey::Scroll) {
+            let mut toggled = self.toggled.lock().unwrap();
+            if !toggled.remove(&vk_code) {
+                toggled.insert(vk_code);
+            }
+        }
+        key_down(vk_code, info)
+    }
+
+    /// Same as [`ScriptedBackend::release`] but with custom hardware information.
+    pub fn release_with(&self, key: VKey, info: KeyEventInfo) -> KeyAction {
+        let vk_code = key.to_vk_code();
+        self.pressed.lock().unwrap().remove(&vk_code);
+        key_up(vk_code, info, true)
+    }
+
+    /// Returns the information of a key event made on the simulated keyboard at the current time.
+    pub fn event_info(&self) -> KeyEventInfo {
+        KeyEventInfo {
+            time: self.clock.load(Ordering::SeqCst),
+            ..Default::default()
+        }
+    }
+
+    /// Presses all keys in order and releases them in reverse order.
+    /// Returns the actions decided for each key press.
+    pub fn chord<T: AsRef<[VKey]>>(&self, keys: T) -> Vec<KeyAction> {
+        let keys = keys.as_ref();
+        let actions = keys.iter().map(|key| self.press(*key)).collect();
+        for key in keys.iter().rev() {
+            self.release(*key);
+        }
+        actions
+    }
+
+    /// Moves the simulated clock forward. The clock only moves when this is called,
+    /// so key events scripted without advancing it happen at the same time.
+    pub fn advance(&self, duration: Duration) {
+        self.clock
+            .fetch_add(duration.as_millis() as u32, Ordering::SeqCst);
+    }
+
+    /// Simulates a change of the foreground window, `None` behaves like a desktop-less session.
+    pub fn set_foreground_window(&self, window: Option<WindowContext>) {
+        *self.foreground.lock().unwrap() = window;
+    }
+
+    /// Returns the synthetic events sent by the engine since the last call.
+    pub fn take_sent(&self) -> Vec<KeyInput> {
+        std::mem::take(&mut *self.sent.lock().unwrap())
+    }
+
+    /// Same as [`ScriptedBackend::take_sent`] but waits up to `timeout` for the engine
+    /// to send events, ex: the ones sent by a callback on the executor thread.
+    pub fn wait_sent(&self, timeout: Duration) -> Vec<KeyInput> {
+        let sent = self.sent.lock().unwrap();
+        let (mut sent, _) = self
+            .sent_changed
+            .wait_timeout_while(sent, timeout, |sent| sent.is_empty())
+            .unwrap();
+        std::mem::take(&mut *sent)
+    }
+}
+
+impl InputBackend for ScriptedBackend {
+    fn start(&self) -> Result<()> {
+        self.pressed.lock()?.clear();
+        self.toggled.lock()?.clear();
+        self.sent.lock()?.clear();
+        Ok(())
+    }
+
+    fn stop(&self) {}
+
+    fn is_key_down(&self, vk_code: u16) -> bool {
+        self.pressed.lock().unwrap().contains(&vk_code)
+    }
+
+    fn send_input(&self, inputs: &[KeyInput]) {
+        self.sent.lock().unwrap().extend_from_slice(inputs);
+        self.sent_changed.notify_all();
+    }
+
+    fn is_key_toggled(&self, vk_code: u16) -> bool {
+        self.toggled.lock().unwrap().contains(&vk_code)
+    }
+
+    fn foreground_window(&self) -> Option<WindowContext> {
+        self.foreground.lock().unwrap().clone()
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod testing {
+    use std::ops::Deref;
+    use std::sync::{Mutex, MutexGuard};
+    use std::thread::JoinHandle;
+    use std::time::{Duration, Instant};
+
+    use super::ScriptedBackend;
+    use crate::events::KeyAction;
+    use crate::{HotkeyManager, VKey};
+
+    /// The engine is process-wide, so tests driving it must not overlap.
+    static ENGINE_LOCK: Mutex<()> = Mutex::new(());
+
+    /// Runs the engine on a [`ScriptedBackend`] for the duration of a test.
+    pub(crate) struct ScriptedEngine {
+        backend: ScriptedBackend,
+        event_loop: Option<JoinHandle<()>>,
+        _guard: MutexGuard<'static, ()>,
+    }
+
+    impl ScriptedEngine {
+        pub(crate) fn start() -> Self {
+            let guard = ENGINE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+            let backend = ScriptedBackend::new();
+            let event_loop = HotkeyManager::start_keyboard_capturing_with(backend.clone()).unwrap();
+            Self {
+                backend,
+                event_loop: Some(event_loop),
+                _guard: guard,
+            }
+        }
+
+        /// Simulates a key press that waited `queued` before reaching the engine,
+        /// ex: behind a slow event, so it has less time left to be decided.
+        pub(crate) fn press_queued(&self, key: VKey, queued: Duration) -> KeyAction {
+            let vk_code = key.to_vk_code();
+            self.backend.pressed.lock().unwrap().insert(vk_code);
+            let info = self.backend.event_info();
+            super::key_down_at(vk_code, info, Instant::now() - queued)
+        }
+    }
+
+    impl Deref for ScriptedEngine {
+        type Target = ScriptedBackend;
+
+        fn deref(&self) -> &Self::Target {
+            &self.backend
+        }
+    }
+
+    impl Drop for ScriptedEngine {
+        fn drop(&mut self) {
+            HotkeyManager::stop_keyboard_capturing();
+            if let Some(event_loop) = self.event_loop.take() {
+                let _ = event_loop.join();
+            }
+            let mut manager = HotkeyManager::current();
+            let _ = manager.unregister_all();
+            manager.remove_global_keyboard_listener();
+            manager.free_keyboard();
+            manager.pause_handler().set(false);
+            manager.set_injected_policy(Default::default());
+            manager.set_conflict_policy(Default::default());
+            manager.set_priority(0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::testing::ScriptedEngine;
+    use crate::events::{KeyAction, KeyboardInputEvent};
+    use crate::{Hotkey, HotkeyManager, TriggerBehavior, VKey};
+
+    const WAIT: Duration = Duration::from_secs(1);
+
+    #[test]
+    fn test_hotkey_blocks_and_runs_callback() {
+        let engine = ScriptedEngine::start();
+        let (tx, rx) = crossbeam_channel::unbounded();
+        HotkeyManager::current()
+            .register_hotkey(Hotkey::new(VKey::A, [VKey::Control], move || {
+                tx.send(()).unwrap();
+            }))
+            .unwrap();
+
+        assert_eq!(engine.press(VKey::LControl), KeyAction::Allow);
+        assert_eq!(engine.press(VKey::A), KeyAction::Block);
+        engine.release(VKey::A);
+        engine.release(VKey::LControl);
+        assert!(rx.recv_timeout(WAIT).is_ok(), "Callback should be executed");
+
+        // without the modifier the key must pass
+        assert_eq!(engine.chord([VKey::A]), vec![KeyAction::Allow]);
+        assert!(rx.recv_timeout(Duration::from_millis(50)).is_err());
+    }
+
+    #[test]
+    fn test_pass_through_and_win_replace() {
+        let engine = ScriptedEngine::start();
+        let manager = HotkeyManager::current();
+        manager
+            .register_hotkey(
+                Hotkey::new(VKey::B, [VKey::Shift], || {}).behavior(TriggerBehavior::PassThrough),
+            )
+            .unwrap();
+        manager
+            .register_hotkey(Hotkey::new(VKey::C, [VKey::LWin], || {}))
+            .unwrap();
+
+        assert_eq!(
+            engine.chord([VKey::RShift, VKey::B]),
+            vec![KeyAction::Allow, KeyAction::Allow]
+        );
+        assert_eq!(
+            engine.chord([VKey::LWin, VKey::C]),
+            vec![KeyAction::Allow, KeyAction::Replace]
+        );
+    }
+
+    #[test]
+    fn test_paused_manager_ignores_hotkeys() {
+        let engine = ScriptedEngine::start();
+        let manager = HotkeyManager::current();
+        manager
+            .register_hotkey(Hotkey::new(VKey::D, [VKey::Control], || {}))
+            .unwrap();
+        manager
+            .register_hotkey(Hotkey::new(VKey::E, [VKey::Control], || {}).bypass_pause())
+            .unwrap();
+
+        manager.pause_handler().set(true);
+        assert_eq!(
+            engine.chord([VKey::LControl, VKey::D]),
+            vec![KeyAction::Allow, KeyAction::Allow]
+        );
+        assert_eq!(
+            engine.chord([VKey::LControl, VKey::E]),
+            vec![KeyAction::Allow, KeyAction::Block]
+        );
+    }
+
+    #[test]
+    fn test_global_listener_receives_events() {
+        let engine = ScriptedEngine::start();
+        let (tx, rx) = crossbeam_channel::unbounded();
+        HotkeyManager::current().set_global_keyboard_listener(move |event| {
+            tx.send(event).unwrap();
+        });
+
+        engine.press(VKey::F);
+        engine.advance(Duration::from_millis(40));
+        engine.release(VKey::F);
+
+        let KeyboardInputEvent::KeyDown {
+            vk_code,
+            state,
+            info,
+        } = rx.recv_timeout(WAIT).unwrap()
+        else {
+            panic!("Expected a key down event");
+        };
+        assert_eq!(VKey::from(vk_code), VKey::F);
+        assert!(state.is_down(VKey::F));
+        let up = rx.recv_timeout(WAIT).unwrap();
+        assert!(matches!(up, KeyboardInputEvent::KeyUp { .. }));
+        assert_eq!(up.vk_code(), vk_code);
+        assert_eq!(up.info().time, info.time + 40);
+    }
+
+    #[test]
+    fn test_stealing_mode_ends_on_escape() {
+        let engine = ScriptedEngine::start();
+        let manager = HotkeyManager::current();
+        manager.steal_keyboard(|| {});
+
+        assert_eq!(engine.chord([VKey::Q]), vec![KeyAction::Block]);
+        assert_eq!(engine.chord([VKey::Escape]), vec![KeyAction::Block]);
+        assert!(!manager.is_stealing_mode());
+        assert_eq!(engine.chord([VKey::Q]), vec![KeyAction::Allow]);
+    }
+}
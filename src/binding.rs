@@ -0,0 +1,329 @@
+//! This module defines the `HotkeyBinding`, the keys of a hotkey without its callback,
+//! which can be parsed from and displayed as an accelerator string, ex: `"Ctrl+Shift+A"`.
+//!
+//! # Grammar
+//!
+//! ```text
+//! binding   = key *( separator key )
+//! key       = name | separator
+//! separator = "+"                      ; or the ones set in the `BindingFormat`
+//! ```
+//!
+//! - The last key is the trigger key, the ones before it are the modifiers.
+//! - Key names are the ones accepted by [`VKey::from_keyname`], ignoring case: the names
+//!   of the virtual-key codes with or without their `VK_` prefix, their aliases like
+//!   `Ctrl`, `Alt`, `Win` or `Esc`, and hexadecimal codes like `0x41`.
+//! - A separator character is read as a key name when a key is expected and no key name
+//!   follows it, ex: `Ctrl++` is [CTRL] + [+], while `Ctrl++A` misses a key.
+//! - Whitespace around the key names is ignored.
+//!
+//! When displayed, the modifiers come in a canonical order: [CTRL], [ALT], [SHIFT] and [WIN],
+//! then the other keys by virtual-key code. Each key is shown by [`VKey::display_name`].
+
+use crate::error::{Result, WHKError};
+use crate::hotkey::Hotkey;
+use crate::VKey;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::str::FromStr;
+
+/// Why an accelerator string can't be parsed, see [`WHKError::InvalidBinding`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BindingErrorKind {
+    /// A key name is missing, ex: in an empty string or after a trailing separator
+    MissingKey,
+    /// The key name is not known
+    UnknownKey(String),
+    /// The key appears more than once
+    DuplicateKey(VKey),
+    /// The key is placed before the trigger key but is not a modifier
+    ExpectedModifier(VKey),
+    /// The construct is valid in the source notation but has no equivalent in this crate
+    Unsupported(String),
+}
+
+impl fmt::Display for BindingErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BindingErrorKind::MissingKey => f.write_str("expected a key name"),
+            BindingErrorKind::UnknownKey(name) => write!(f, "unknown key name `{name}`"),
+            BindingErrorKind::DuplicateKey(key) => {
+                write!(f, "duplicate key `{}`", key.display_name())
+            }
+            BindingErrorKind::ExpectedModifier(key) => {
+                write!(f, "expected a modifier, found `{}`", key.display_name())
+            }
+            BindingErrorKind::Unsupported(construct) => write!(f, "unsupported {construct}"),
+        }
+    }
+}
+
+/// Separators used to parse and display a [`HotkeyBinding`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BindingFormat {
+    /// characters accepted between the keys when parsing
+    pub separators: Vec<char>,
+    /// text written between the keys when displaying
+    pub display_separator: String,
+}
+
+impl Default for BindingFormat {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BindingFormat {
+    /// Creates a new `BindingFormat` separating the keys with `+`.
+    pub fn new() -> Self {
+        Self {
+            separators: vec!['+'],
+            display_separator: "+".to_string(),
+        }
+    }
+
+    /// Sets the characters accepted between the keys when parsing, ex: `['+', '-']`.
+    pub fn separators<T: AsRef<[char]>>(mut self, separators: T) -> Self {
+        self.separators = separators.as_ref().to_vec();
+        self
+    }
+
+    /// Sets the text written between the keys when displaying, ex: `" + "`.
+    pub fn display_separator<S: Into<String>>(mut self, separator: S) -> Self {
+        self.display_separator = separator.into();
+        self
+    }
+}
+
+/// The keys of a hotkey, without its callback.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct HotkeyBinding {
+    /// key that must be pressed to trigger the hotkey
+    pub trigger_key: VKey,
+    /// keys that must be pressed before the trigger key
+    pub modifiers: BTreeSet<VKey>,
+}
+
+impl HotkeyBinding {
+    /// Creates a new `HotkeyBinding`.
+    pub fn new<T: AsRef<[VKey]>>(trigger_key: VKey, modifiers: T) -> Self {
+        Self {
+            trigger_key,
+            modifiers: modifiers.as_ref().iter().cloned().collect(),
+        }
+    }
+
+    /// Parses an accelerator string with the given separators, see the [module
+    /// documentation](self) for the grammar.
+    pub fn parse_with(input: &str, format: &BindingFormat) -> Result<Self> {
+        let keys = parse_keys(input, &format.separators, |name| {
+            VKey::from_keyname(name).map_err(|_| BindingErrorKind::UnknownKey(name.to_string()))
+        })?;
+        Ok(Self::from_keys(keys.into_iter().map(|(_, key)| key)))
+    }
+
+    /// Creates a `HotkeyBinding` from keys in order, the last one being the trigger key.
+    pub(crate) fn from_keys<I: IntoIterator<Item = VKey>>(keys: I) -> Self {
+        let mut modifiers: Vec<VKey> = keys.into_iter().collect();
+        let trigger_key = modifiers.pop().unwrap_or(VKey::None);
+        Self::new(trigger_key, modifiers)
+    }
+
+    /// Returns the accelerator string of the binding with the given separator.
+    pub fn display_with(&self, format: &BindingFormat) -> String {
+        let mut modifiers: Vec<VKey> = self.modifiers.iter().cloned().collect();
+        modifiers.sort_by_key(|key| (modifier_rank(*key), *key));
+        modifiers
+            .iter()
+            .chain([&self.trigger_key])
+            .map(VKey::display_name)
+            .collect::<Vec<_>>()
+            .join(&format.display_separator)
+    }
+
+    /// Creates a hotkey with these keys, executing the given callback.
+    pub fn to_hotkey<F>(&self, callback: F) -> Hotkey
+    where
+        F: Fn() + Send + Sync + 'static,
+    {
+        let modifiers: Vec<VKey> = self.modifiers.iter().cloned().collect();
+        Hotkey::new(self.trigger_key, modifiers, callback)
+    }
+}
+
+/// Splits the input in key names with the given separators, resolving each one to a key.
+/// Returns the keys in order, along with their position in the input.
+pub(crate) fn parse_keys<F>(
+    input: &str,
+    separators: &[char],
+    mut resolve: F,
+) -> Result<Vec<(usize, VKey)>>
+where
+    F: FnMut(&str) -> std::result::Result<VKey, BindingErrorKind>,
+{
+    let error = |position, kind| WHKError::InvalidBinding {
+        input: input.to_string(),
+        position,
+        kind,
+    };
+
+    let mut keys: Vec<(usize, VKey)> = Vec::new();
+    let mut rest = input;
+    loop {
+        let offset = input.len() - rest.len();
+        let (position, name, after) = next_key(rest, separators);
+        let position = offset + position;
+        if name.is_empty() {
+            return Err(error(position, BindingErrorKind::MissingKey));
+        }
+        let key = resolve(name).map_err(|kind| error(position, kind))?;
+        if keys.iter().any(|(_, other)| *other == key) {
+            return Err(error(position, BindingErrorKind::DuplicateKey(key)));
+        }
+        keys.push((position, key));
+
+        let mut chars = after.chars();
+        match chars.next() {
+            None => return Ok(keys),
+            Some(_) => rest = chars.as_str(),
+        }
+    }
+}
+
+/// Splits the next key name from the input, returns the position of the name, the name
+/// and the input after it, which is empty or starts with a separator.
+fn next_key<'a>(input: &'a str, separators: &[char]) -> (usize, &'a str, &'a str) {
+    let is_blank = |c: char| c.is_whitespace() && !separators.contains(&c);
+    let trimmed = input.trim_start_matches(is_blank);
+    let start = input.len() - trimmed.len();
+
+    // a separator where a key is expected is the key itself, unless a key name follows
+    let mut chars = trimmed.chars();
+    if let Some(c) = chars.next().filter(|c| separators.contains(c)) {
+        let after = chars.as_str().trim_start_matches(is_blank);
+        if after.is_empty() || after.starts_with(separators) {
+            return (start, &trimmed[..c.len_utf8()], after);
+        }
+    }
+    let end = trimmed.find(separators).unwrap_or(trimmed.len());
+    let (name, after) = trimmed.split_at(end);
+    (start, name.trim_end_matches(is_blank), after)
+}
+
+/// Returns the position of a key in the canonical order of the modifiers.
+fn modifier_rank(key: VKey) -> u8 {
+    if key.is_control_key() {
+        0
+    } else if key.is_menu_key() {
+        1
+    } else if key.is_shift_key() {
+        2
+    } else if key.is_windows_key() {
+        3
+    } else {
+        4
+    }
+}
+
+impl FromStr for HotkeyBinding {
+    type Err = WHKError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        HotkeyBinding::parse_with(s, &BindingFormat::new())
+    }
+}
+
+impl fmt::Display for HotkeyBinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.display_with(&BindingFormat::new()))
+    }
+}
+
+/// Serialized as an accelerator string, ex: `"Ctrl+Shift+A"`.
+#[cfg(feature = "serde")]
+impl serde::Serialize for HotkeyBinding {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for HotkeyBinding {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let input = String::deserialize(deserializer)?;
+        input.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+impl From<&Hotkey> for HotkeyBinding {
+    fn from(hotkey: &Hotkey) -> Self {
+        Self {
+            trigger_key: hotkey.trigger_key,
+            modifiers: hotkey.modifiers.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error_at(input: &str) -> (usize, BindingErrorKind) {
+        match input.parse::<HotkeyBinding>() {
+            Err(WHKError::InvalidBinding { position, kind, .. }) => (position, kind),
+            other => panic!("unexpected result for `{input}`: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_parse_and_display() {
+        let binding: HotkeyBinding = "shift + ctrl+A".parse().unwrap();
+        assert_eq!(
+            binding,
+            HotkeyBinding::new(VKey::A, [VKey::Control, VKey::Shift])
+        );
+        assert_eq!(binding.to_string(), "Ctrl+Shift+A");
+
+        let binding: HotkeyBinding = "Win+LAlt+VK_RCONTROL+0x41+esc".parse().unwrap();
+        assert_eq!(binding.to_string(), "RCtrl+LAlt+Win+A+Esc");
+        assert_eq!(
+            binding.to_string().parse::<HotkeyBinding>().unwrap(),
+            binding
+        );
+
+        let binding: HotkeyBinding = "Ctrl++".parse().unwrap();
+        assert_eq!(binding, HotkeyBinding::new(VKey::OemPlus, [VKey::Control]));
+        assert_eq!(binding.to_string(), "Ctrl++");
+
+        let format = BindingFormat::new()
+            .separators(['-', ' '])
+            .display_separator(" + ");
+        let binding = HotkeyBinding::parse_with("Alt-F4", &format).unwrap();
+        assert_eq!(binding.display_with(&format), "Alt + F4");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(error_at(""), (0, BindingErrorKind::MissingKey));
+        assert_eq!(error_at("Ctrl++A"), (5, BindingErrorKind::MissingKey));
+        assert_eq!(error_at("Ctrl+ "), (6, BindingErrorKind::MissingKey));
+        assert_eq!(
+            error_at("Ctrl + Foo + A"),
+            (7, BindingErrorKind::UnknownKey("Foo".to_string()))
+        );
+        assert_eq!(
+            error_at("Ctrl+A+ctrl"),
+            (7, BindingErrorKind::DuplicateKey(VKey::Control))
+        );
+        assert_eq!(
+            "Ctrl+Foo".parse::<HotkeyBinding>().unwrap_err().to_string(),
+            "Invalid hotkey binding `Ctrl+Foo` at position 5: unknown key name `Foo`"
+        );
+    }
+}
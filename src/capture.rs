@@ -0,0 +1,295 @@
+//! This module defines the `CaptureSession`, which takes over the keyboard of a
+//! [`crate::HotkeyManager`]: key presses are blocked and delivered to a [`CaptureHandle`]
+//! instead of triggering hotkeys, ex: to let the user press the shortcut to bind.
+//!
+//! The session ends when one of its exit chords is pressed, when its timeout expires,
+//! or when it is stopped from the handle.
+
+use crate::client_executor::run_on_executor_thread;
+use crate::events::{KeyAction, KeyboardInputEvent};
+use crate::hotkey::{self, TriggerBehavior};
+use crate::manager::HotkeyManager;
+use crate::state::KeyboardState;
+use crate::VKey;
+use crossbeam_channel::{Receiver, Sender};
+use std::collections::{BTreeSet, HashSet};
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+static NEXT_CAPTURE_ID: AtomicU64 = AtomicU64::new(0);
+
+type CaptureEndCallback = Arc<dyn Fn(CaptureEnd) + Send + Sync + 'static>;
+
+/// Why a capture session ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaptureEnd {
+    /// One of the exit chords was pressed
+    ExitChord,
+    /// The timeout of the session expired
+    Timeout,
+    /// The session was stopped by the client, or replaced by a new one
+    Stopped,
+}
+
+/// Configuration of a keyboard capture, started with [`HotkeyManager::start_capture`].
+#[derive(Clone)]
+pub struct CaptureSession {
+    /// chords ending the session, the last key of each one is the trigger key
+    pub exit_chords: Vec<Vec<VKey>>,
+    /// time after which the session ends on its own
+    pub timeout: Option<Duration>,
+    /// keys that are not captured, these reach the hotkeys and other applications
+    pub pass_through: HashSet<VKey>,
+    /// callback executed when the session ends
+    pub on_end: Option<CaptureEndCallback>,
+}
+
+impl Default for CaptureSession {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CaptureSession {
+    /// Creates a new `CaptureSession` ending when [ESC] is pressed, without timeout.
+    pub fn new() -> Self {
+        Self {
+            exit_chords: vec![vec![VKey::Escape]],
+            timeout: None,
+            pass_through: HashSet::new(),
+            on_end: None,
+        }
+    }
+
+    /// Sets the chord ending the session, replacing the previous ones.
+    pub fn exit_chord<T: AsRef<[VKey]>>(self, keys: T) -> Self {
+        self.exit_chords([keys])
+    }
+
+    /// Sets the chords ending the session, replacing the previous ones. Without exit
+    /// chords the session only ends on timeout or when stopped from the handle.
+    pub fn exit_chords<C, T>(mut self, chords: C) -> Self
+    where
+        C: IntoIterator<Item = T>,
+        T: AsRef<[VKey]>,
+    {
+        self.exit_chords = chords
+            .into_iter()
+            .map(|keys| keys.as_ref().to_vec())
+            .filter(|keys| !keys.is_empty())
+            .collect();
+        self
+    }
+
+    /// Makes the session end on its own after the given time.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Lets the given keys through, ex: the media keys.
+    pub fn pass_through<T: AsRef<[VKey]>>(mut self, keys: T) -> Self {
+        self.pass_through.extend(keys.as_ref());
+        self
+    }
+
+    /// Sets a callback executed when the session ends.
+    pub fn on_end<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(CaptureEnd) + Send + Sync + 'static,
+    {
+        self.on_end = Some(Arc::new(callback));
+        self
+    }
+
+    /// Returns whether the pressed keys complete one of the exit chords.
+    fn is_exit_state(&self, state: &KeyboardState) -> bool {
+        self.exit_chords.iter().any(|keys| {
+            let (trigger_key, modifiers) = keys.split_last().unwrap();
+            let modifiers: BTreeSet<VKey> = modifiers.iter().cloned().collect();
+            hotkey::is_trigger_state(*trigger_key, &modifiers, state)
+        })
+    }
+}
+
+impl fmt::Debug for CaptureSession {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CaptureSession")
+            .field("exit_chords", &self.exit_chords)
+            .field("timeout", &self.timeout)
+            .field("pass_through", &self.pass_through)
+            .field("on_end", &self.on_end.as_ref().map(|_| "<callback>"))
+            .finish()
+    }
+}
+
+/// Handle of a running capture session, yields the captured key events.
+///
+/// The events channel is disconnected when the session ends, so iterating the
+/// handle blocks until then. Dropping the handle doesn't end the session.
+#[derive(Debug)]
+pub struct CaptureHandle {
+    id: u64,
+    events: Receiver<KeyboardInputEvent>,
+    end: Arc<Mutex<Option<CaptureEnd>>>,
+    manager: HotkeyManager,
+}
+
+impl CaptureHandle {
+    /// Returns the channel receiving the captured key events.
+    pub fn events(&self) -> &Receiver<KeyboardInputEvent> {
+        &self.events
+    }
+
+    /// Returns a blocking iterator over the captured key events, until the session ends.
+    pub fn iter(&self) -> crossbeam_channel::Iter<'_, KeyboardInputEvent> {
+        self.events.iter()
+    }
+
+    /// Returns why the session ended, `None` while it is running.
+    pub fn end_reason(&self) -> Option<CaptureEnd> {
+        *self.end.lock().unwrap()
+    }
+
+    /// Returns whether the session is still running.
+    pub fn is_active(&self) -> bool {
+        self.end_reason().is_none()
+    }
+
+    /// Ends the session, if still running.
+    pub fn stop(&self) {
+        self.manager.end_capture(Some(self.id), CaptureEnd::Stopped);
+    }
+}
+
+impl IntoIterator for CaptureHandle {
+    type Item = KeyboardInputEvent;
+    type IntoIter = crossbeam_channel::IntoIter<KeyboardInputEvent>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.events.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a CaptureHandle {
+    type Item = KeyboardInputEvent;
+    type IntoIter = crossbeam_channel::Iter<'a, KeyboardInputEvent>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Decision of a running capture session on a key event.
+pub(crate) enum CaptureStep {
+    /// The key is not captured and must be processed as usual
+    Ignored,
+    /// The key was captured and must be handled with the given action
+    Captured(KeyAction),
+    /// The session ended, the key must be handled with the given action if any
+    Ended(CaptureEnd, Option<KeyAction>),
+}
+
+/// The capture session running on a manager.
+#[derive(Debug)]
+pub(crate) struct ActiveCapture {
+    pub id: u64,
+    session: CaptureSession,
+    deadline: Option<Instant>,
+    events: Sender<KeyboardInputEvent>,
+    end: Arc<Mutex<Option<CaptureEnd>>>,
+}
+
+impl ActiveCapture {
+    /// Starts a session, returns it along with its handle.
+    pub fn start(session: CaptureSession, manager: HotkeyManager) -> (Self, CaptureHandle) {
+        let id = NEXT_CAPTURE_ID.fetch_add(1, Ordering::Relaxed);
+        let (tx, rx) = crossbeam_channel::unbounded();
+        let end = Arc::new(Mutex::new(None));
+        let capture = ActiveCapture {
+            id,
+            deadline: session.timeout.map(|timeout| Instant::now() + timeout),
+            session,
+            events: tx,
+            end: end.clone(),
+        };
+        let handle = CaptureHandle {
+            id,
+            events: rx,
+            end,
+            manager,
+        };
+        (capture, handle)
+    }
+
+    /// Returns when the session ends on its own, if it has a timeout.
+    pub fn deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    /// Feeds a key event to the session.
+    pub fn on_event(&self, event: &KeyboardInputEvent) -> CaptureStep {
+        if self
+            .deadline
+            .is_some_and(|deadline| deadline <= Instant::now())
+        {
+            return CaptureStep::Ended(CaptureEnd::Timeout, None);
+        }
+        if self
+            .session
+            .pass_through
+            .contains(&VKey::from(event.vk_code()))
+        {
+            return CaptureStep::Ignored;
+        }
+
+        match event {
+            KeyboardInputEvent::KeyDown { state, .. } => {
+                // on exit chord press we end the session, but still block the key
+                let action = TriggerBehavior::StopPropagation.key_action(state);
+                if self.session.is_exit_state(state) {
+                    return CaptureStep::Ended(CaptureEnd::ExitChord, Some(action));
+                }
+                let _ = self.events.send(event.clone());
+                CaptureStep::Captured(action)
+            }
+            // releases are processed as usual, so no key stays pressed
+            KeyboardInputEvent::KeyUp { .. } => {
+                let _ = self.events.send(event.clone());
+                CaptureStep::Ignored
+            }
+        }
+    }
+
+    /// Ends the session, disconnecting its handle.
+    pub fn finish(self, reason: CaptureEnd) {
+        *self.end.lock().unwrap() = Some(reason);
+        if let Some(on_end) = self.session.on_end {
+            run_on_executor_thread(Arc::new(move || on_end(reason)));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exit_chords() {
+        let session = CaptureSession::new().exit_chords([vec![VKey::Control, VKey::Q], vec![]]);
+        assert_eq!(session.exit_chords, vec![vec![VKey::Control, VKey::Q]]);
+
+        let mut state = KeyboardState::new();
+        state.keydown(VKey::Q);
+        assert!(!session.is_exit_state(&state));
+        state.keyup(VKey::Q);
+        state.keydown(VKey::LControl);
+        state.keydown(VKey::Q);
+        assert!(session.is_exit_state(&state));
+        assert!(!CaptureSession::new()
+            .exit_chords::<_, [VKey; 0]>([])
+            .is_exit_state(&state));
+    }
+}
@@ -1,11 +1,13 @@
-use std::sync::{Arc, LazyLock};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
-use crossbeam_channel::{Receiver, Sender};
+use crossbeam_channel::Sender;
 
 use crate::log_on_dev;
 
-static CLIENT_CHANNEL: LazyLock<(Sender<ClientAction>, Receiver<ClientAction>)> =
-    LazyLock::new(crossbeam_channel::unbounded);
+/// Channel to the running executor thread, each run of the executor
+/// owns its channel so a stopped thread never steals actions from a new one.
+static CLIENT_CHANNEL: Mutex<Option<Sender<ClientAction>>> = Mutex::new(None);
 
 /// Enum representing client actions, which need to run
 /// on a separated thead to avoid deadlocks.
@@ -16,19 +18,18 @@ enum ClientAction {
 
 impl ClientAction {
     fn emit(self) {
-        if CLIENT_CHANNEL.0.send(self).is_err() {
+        let channel = CLIENT_CHANNEL.lock().unwrap();
+        if channel.as_ref().is_none_or(|tx| tx.send(self).is_err()) {
             log_on_dev!("Failed to send client action");
         }
     }
-
-    fn reciever() -> Receiver<ClientAction> {
-        CLIENT_CHANNEL.1.clone()
-    }
 }
 
 pub(crate) fn start_executor_thread() {
-    std::thread::spawn(|| {
-        while let Ok(event) = ClientAction::reciever().recv() {
+    let (tx, rx) = crossbeam_channel::unbounded();
+    *CLIENT_CHANNEL.lock().unwrap() = Some(tx);
+    std::thread::spawn(move || {
+        while let Ok(event) = rx.recv() {
             match event {
                 ClientAction::Call(cb) => cb(),
                 ClientAction::Stop => break,
@@ -41,6 +42,21 @@ pub(crate) fn run_on_executor_thread<F: Fn() + Send + Sync + 'static>(cb: Arc<F>
     ClientAction::Call(cb).emit();
 }
 
+/// Runs a callback on the executor thread and waits for its result up to `timeout`,
+/// returns `None` if it didn't answer in time.
+pub(crate) fn call_on_executor_thread<T, F>(cb: Arc<F>, timeout: Duration) -> Option<T>
+where
+    T: Send + 'static,
+    F: Fn() -> T + Send + Sync + ?Sized + 'static,
+{
+    let (tx, rx) = crossbeam_channel::bounded(1);
+    run_on_executor_thread(Arc::new(move || {
+        let _ = tx.try_send(cb());
+    }));
+    rx.recv_timeout(timeout).ok()
+}
+
 pub(crate) fn stop_executor_thread() {
     ClientAction::Stop.emit();
+    CLIENT_CHANNEL.lock().unwrap().take();
 }
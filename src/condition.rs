@@ -0,0 +1,113 @@
+//! This module defines the `HotkeyContext` received by the conditions of a hotkey,
+//! registered with [`crate::Hotkey::when`], which decide whether the hotkey is eligible
+//! when its keys are pressed, ex: only while [CAPSLOCK] is on.
+//!
+//! A hotkey whose conditions are not met is skipped, so its key event is handled as if
+//! the hotkey was not registered.
+
+use crate::backend;
+use crate::layer::{Layers, DEFAULT_LAYER};
+use crate::state::KeyboardState;
+use crate::VKey;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+pub(crate) type HotkeyCondition = Arc<dyn Fn(&HotkeyContext) -> bool + Send + Sync + 'static>;
+
+/// The state of the engine when the keys of a hotkey are pressed.
+pub struct HotkeyContext<'a> {
+    state: &'a KeyboardState,
+    layers: &'a Layers,
+    paused: bool,
+    flags: &'a HashSet<String>,
+}
+
+impl<'a> HotkeyContext<'a> {
+    pub(crate) fn new(
+        state: &'a KeyboardState,
+        layers: &'a Layers,
+        paused: bool,
+        flags: &'a HashSet<String>,
+    ) -> Self {
+        Self {
+            state,
+            layers,
+            paused,
+            flags,
+        }
+    }
+
+    /// Returns the keys being pressed, trigger key included.
+    pub fn state(&self) -> &KeyboardState {
+        self.state
+    }
+
+    /// Returns the name of the top-most active layer, [`DEFAULT_LAYER`] if no other is active.
+    pub fn top_layer(&self) -> &str {
+        self.layers
+            .stack
+            .last()
+            .map_or(DEFAULT_LAYER, String::as_str)
+    }
+
+    /// Returns whether the layer is active, the [`DEFAULT_LAYER`] always is.
+    pub fn is_layer_active(&self, layer: &str) -> bool {
+        layer == DEFAULT_LAYER || self.layers.stack.iter().any(|active| active == layer)
+    }
+
+    /// Returns whether the hotkey manager is paused, only meaningful for hotkeys
+    /// that bypass the pause.
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Returns whether a user-defined flag is set, see [`crate::HotkeyManager::set_flag`].
+    pub fn flag(&self, name: &str) -> bool {
+        self.flags.contains(name)
+    }
+
+    /// Returns whether a lock key is on, ex: [`VKey::Capital`] for [CAPSLOCK].
+    pub fn is_toggled(&self, key: VKey) -> bool {
+        backend::is_key_toggled(key.to_vk_code())
+    }
+
+    /// Returns whether [CAPSLOCK] is on.
+    pub fn is_caps_lock_on(&self) -> bool {
+        self.is_toggled(VKey::Capital)
+    }
+
+    /// Returns whether [NUMLOCK] is on.
+    pub fn is_num_lock_on(&self) -> bool {
+        self.is_toggled(VKey::Numlock)
+    }
+
+    /// Returns whether [SCROLLLOCK] is on.
+    pub fn is_scroll_lock_on(&self) -> bool {
+        self.is_toggled(VKey::Scroll)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_context_layers_and_flags() {
+        let state = KeyboardState::new();
+        let mut layers = Layers::default();
+        let flags = HashSet::from(["recording".to_string()]);
+
+        let context = HotkeyContext::new(&state, &layers, false, &flags);
+        assert_eq!(context.top_layer(), DEFAULT_LAYER);
+        assert!(context.is_layer_active(DEFAULT_LAYER));
+        assert!(!context.is_layer_active("nav"));
+        assert!(context.flag("recording"));
+        assert!(!context.flag("muted"));
+
+        layers.push("nav");
+        let context = HotkeyContext::new(&state, &layers, true, &flags);
+        assert_eq!(context.top_layer(), "nav");
+        assert!(context.is_layer_active("nav"));
+        assert!(context.is_paused());
+    }
+}
@@ -0,0 +1,247 @@
+//! This module defines the conflict analysis of the registered hotkeys, returned by
+//! [`crate::HotkeyManager::conflicts`], which finds the hotkeys triggered by the same keys,
+//! ex: [CTRL] + [A] and [LCTRL] + [A], since the modifiers are matched regardless of their side.
+//!
+//! A [`ConflictPolicy`] can also be set on the manager to reject or report the ambiguous
+//! hotkeys when they are registered.
+
+use crate::binding::HotkeyBinding;
+use crate::hotkey::{Hotkey, HotkeyId};
+use crate::layer::DEFAULT_LAYER;
+use crate::VKey;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::sync::Arc;
+
+type ConflictCallback = Arc<dyn Fn(&HotkeyConflict) + Send + Sync + 'static>;
+
+/// How two hotkeys triggered by the same keys interact.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ConflictKind {
+    /// Both hotkeys are on the same layer with the same precedence, without scope nor
+    /// conditions, only the first one registered triggers
+    Ambiguous,
+    /// Both hotkeys are on the same layer with the same precedence and a scope or
+    /// conditions, the conflict only happens when both are eligible
+    Overlapping,
+    /// The first hotkey takes precedence over the second one when eligible, because it
+    /// is on a layer above it, or it has a higher priority, a scope or conditions, or more
+    /// specific modifiers, see [`crate::Hotkey::priority`]. Between two layers other than
+    /// the [`DEFAULT_LAYER`], the top-most active one takes precedence, regardless of the
+    /// order reported.
+    Shadowed,
+}
+
+/// A hotkey involved in a conflict.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConflictingHotkey {
+    /// id of the hotkey
+    pub id: HotkeyId,
+    /// layer the hotkey is registered on
+    pub layer: String,
+    /// key that triggers the hotkey
+    pub trigger_key: VKey,
+    /// keys that must be pressed before the trigger key
+    pub modifiers: BTreeSet<VKey>,
+}
+
+impl ConflictingHotkey {
+    fn new(layer: &str, hotkey: &Hotkey) -> Self {
+        Self {
+            id: hotkey.id.clone(),
+            layer: layer.to_string(),
+            trigger_key: hotkey.trigger_key,
+            modifiers: hotkey.modifiers.clone(),
+        }
+    }
+}
+
+impl fmt::Display for ConflictingHotkey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let binding = HotkeyBinding {
+            trigger_key: self.trigger_key,
+            modifiers: self.modifiers.clone(),
+        };
+        write!(f, "{binding} on layer `{}`", self.layer)
+    }
+}
+
+/// Two registered hotkeys triggered by the same keys.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HotkeyConflict {
+    pub kind: ConflictKind,
+    pub first: ConflictingHotkey,
+    pub second: ConflictingHotkey,
+}
+
+impl fmt::Display for HotkeyConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            ConflictKind::Ambiguous => {
+                write!(f, "{} is ambiguous with {}", self.first, self.second)
+            }
+            ConflictKind::Overlapping => write!(f, "{} overlaps {}", self.first, self.second),
+            ConflictKind::Shadowed => write!(f, "{} shadows {}", self.first, self.second),
+        }
+    }
+}
+
+/// Defines how the `HotkeyManager` handles a hotkey being registered with the same keys
+/// and precedence as a hotkey of the same layer, when neither of them has a scope or
+/// conditions, see [`ConflictKind::Ambiguous`].
+#[derive(Clone, Default)]
+pub enum ConflictPolicy {
+    /// Register the hotkey anyway
+    #[default]
+    Allow,
+    /// Register the hotkey and execute the callback for each conflict found
+    Warn(ConflictCallback),
+    /// Refuse the hotkey with [`crate::error::WHKError::HotkeyConflict`]
+    Reject,
+}
+
+impl ConflictPolicy {
+    /// Creates a `ConflictPolicy` registering the hotkey and executing the callback
+    /// for each conflict found.
+    pub fn warn<F>(callback: F) -> Self
+    where
+        F: Fn(&HotkeyConflict) + Send + Sync + 'static,
+    {
+        ConflictPolicy::Warn(Arc::new(callback))
+    }
+}
+
+impl fmt::Debug for ConflictPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConflictPolicy::Allow => f.write_str("Allow"),
+            ConflictPolicy::Warn(_) => f.debug_tuple("Warn").field(&"<callback>").finish(),
+            ConflictPolicy::Reject => f.write_str("Reject"),
+        }
+    }
+}
+
+/// Returns the conflict between two hotkeys, if they are triggered by the same keys.
+pub(crate) fn conflict_between(
+    (layer_a, a): (&str, &Hotkey),
+    (layer_b, b): (&str, &Hotkey),
+) -> Option<HotkeyConflict> {
+    if !a.overlaps(b) {
+        return None;
+    }
+
+    let ordered = |kind, first: (&str, &Hotkey), second: (&str, &Hotkey)| HotkeyConflict {
+        kind,
+        first: ConflictingHotkey::new(first.0, first.1),
+        second: ConflictingHotkey::new(second.0, second.1),
+    };
+    if layer_a != layer_b {
+        // the hotkey of the default layer is always the shadowed one
+        return Some(if layer_a == DEFAULT_LAYER {
+            ordered(ConflictKind::Shadowed, (layer_b, b), (layer_a, a))
+        } else {
+            ordered(ConflictKind::Shadowed, (layer_a, a), (layer_b, b))
+        });
+    }
+
+    let (first, second) = if b.precedence() < a.precedence() {
+        (b, a)
+    } else {
+        (a, b)
+    };
+    let kind = if first.precedence() < second.precedence() {
+        ConflictKind::Shadowed
+    } else if first.is_guarded() {
+        ConflictKind::Overlapping
+    } else {
+        ConflictKind::Ambiguous
+    };
+    Some(ordered(kind, (layer_a, first), (layer_b, second)))
+}
+
+/// Returns the conflicts between the given hotkeys, sorted by kind, then by layer
+/// and trigger key of the first hotkey.
+pub(crate) fn find_conflicts<'a, I>(hotkeys: I) -> Vec<HotkeyConflict>
+where
+    I: IntoIterator<Item = (&'a str, &'a Hotkey)>,
+{
+    let hotkeys: Vec<_> = hotkeys.into_iter().collect();
+    let mut conflicts: Vec<HotkeyConflict> = hotkeys
+        .iter()
+        .enumerate()
+        .flat_map(|(i, a)| {
+            hotkeys[i + 1..]
+                .iter()
+                .filter_map(move |b| conflict_between(*a, *b))
+        })
+        .collect();
+    // the hotkeys come in no particular order, so the symmetric conflicts are normalized
+    for conflict in &mut conflicts {
+        if conflict.kind != ConflictKind::Shadowed && conflict.first.id > conflict.second.id {
+            std::mem::swap(&mut conflict.first, &mut conflict.second);
+        }
+    }
+    conflicts.sort_by_key(|conflict| {
+        (
+            conflict.kind,
+            conflict.first.layer.clone(),
+            conflict.first.trigger_key,
+            conflict.first.id.clone(),
+            conflict.second.id.clone(),
+        )
+    });
+    conflicts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scope::Scope;
+
+    #[test]
+    fn test_conflict_between() {
+        let ctrl_a = Hotkey::new(VKey::A, [VKey::Control], || {});
+        let lctrl_a = Hotkey::new(VKey::A, [VKey::LControl], || {});
+        let ctrl_shift_a = Hotkey::new(VKey::A, [VKey::Control, VKey::Shift], || {});
+        let double_ctrl_a = Hotkey::new(VKey::A, [VKey::Control], || {}).taps(2);
+        let scoped =
+            Hotkey::new(VKey::A, [VKey::RControl], || {}).scope(Scope::processes(["a.exe"]));
+
+        let rctrl_a = Hotkey::new(VKey::A, [VKey::RControl], || {});
+        let conflict =
+            conflict_between((DEFAULT_LAYER, &lctrl_a), (DEFAULT_LAYER, &rctrl_a)).unwrap();
+        assert_eq!(conflict.kind, ConflictKind::Ambiguous);
+        assert_eq!(conflict.first.id, lctrl_a.id);
+
+        // the most specific modifiers take precedence, unless the priority says otherwise
+        let conflict =
+            conflict_between((DEFAULT_LAYER, &ctrl_a), (DEFAULT_LAYER, &lctrl_a)).unwrap();
+        assert_eq!(conflict.kind, ConflictKind::Shadowed);
+        assert_eq!(conflict.first.id, lctrl_a.id);
+        let urgent_ctrl_a = Hotkey::new(VKey::A, [VKey::Control], || {}).priority(1);
+        let conflict =
+            conflict_between((DEFAULT_LAYER, &lctrl_a), (DEFAULT_LAYER, &urgent_ctrl_a)).unwrap();
+        assert_eq!(conflict.kind, ConflictKind::Shadowed);
+        assert_eq!(conflict.first.id, urgent_ctrl_a.id);
+        assert!(
+            conflict_between((DEFAULT_LAYER, &ctrl_a), (DEFAULT_LAYER, &ctrl_shift_a)).is_none()
+        );
+        assert!(
+            conflict_between((DEFAULT_LAYER, &ctrl_a), (DEFAULT_LAYER, &double_ctrl_a)).is_none()
+        );
+
+        let conflict =
+            conflict_between((DEFAULT_LAYER, &ctrl_a), (DEFAULT_LAYER, &scoped)).unwrap();
+        assert_eq!(conflict.kind, ConflictKind::Shadowed);
+        assert_eq!(conflict.first.id, scoped.id);
+
+        let conflict = conflict_between((DEFAULT_LAYER, &ctrl_a), ("nav", &lctrl_a)).unwrap();
+        assert_eq!(conflict.kind, ConflictKind::Shadowed);
+        assert_eq!(conflict.first.layer, "nav");
+        assert_eq!(conflict.second.layer, DEFAULT_LAYER);
+        assert_eq!(
+            conflict.to_string(),
+            "LCtrl+A on layer `nav` shadows Ctrl+A on layer `default`"
+        );
+    }
+}
@@ -0,0 +1,458 @@
+//! This module defines the `DualRoleKey` struct, a key that does one thing when tapped
+//! and another when held, like the mod-tap keys of QMK keyboards (ex: CapsLock that is
+//! Escape on tap and Control on hold).
+//!
+//! When a dual-role key is pressed it is swallowed until the engine can decide between tap
+//! and hold. Keys pressed meanwhile are buffered and replayed once the decision is made.
+
+use crate::state::KeyboardState;
+use crate::VKey;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Time a dual-role key must be held to be considered a hold, if not configured.
+pub const DEFAULT_TAPPING_TERM: Duration = Duration::from_millis(200);
+
+type DualRoleCallback = Arc<Box<dyn Fn() + Send + Sync + 'static>>;
+
+/// A key that acts as one thing when tapped and as another when held.
+#[derive(Clone)]
+pub struct DualRoleKey {
+    /// the physical key with two roles
+    pub key: VKey,
+    /// time the key must be held to be considered a hold
+    pub tapping_term: Duration,
+    /// decide hold when another key is pressed and released while this key is held,
+    /// even before the tapping term ends
+    pub permissive_hold: bool,
+    /// decide hold as soon as another key is pressed while this key is held
+    pub hold_on_other_key_press: bool,
+    /// will ignore the `paused` global state
+    pub bypass_pause: bool,
+    /// callback to execute on tap, if none the key itself is sent
+    pub on_tap: Option<DualRoleCallback>,
+    /// modifier key that is held while this key is held
+    pub hold_modifier: Option<VKey>,
+    /// callback to execute when the hold is decided
+    pub on_hold: Option<DualRoleCallback>,
+    /// callback to execute when the key is released after a hold
+    pub on_hold_release: Option<DualRoleCallback>,
+}
+
+impl DualRoleKey {
+    /// Creates a new `DualRoleKey`, that by default sends itself on tap and does nothing on hold.
+    pub fn new(key: VKey) -> Self {
+        Self {
+            key,
+            tapping_term: DEFAULT_TAPPING_TERM,
+            permissive_hold: false,
+            hold_on_other_key_press: false,
+            bypass_pause: false,
+            on_tap: None,
+            hold_modifier: None,
+            on_hold: None,
+            on_hold_release: None,
+        }
+    }
+
+    /// Sets the time the key must be held to be considered a hold
+    pub fn tapping_term(mut self, tapping_term: Duration) -> Self {
+        self.tapping_term = tapping_term;
+        self
+    }
+
+    /// Decide hold when another key is tapped while this key is held
+    pub fn permissive_hold(mut self) -> Self {
+        self.permissive_hold = true;
+        self
+    }
+
+    /// Decide hold as soon as another key is pressed while this key is held
+    pub fn hold_on_other_key_press(mut self) -> Self {
+        self.hold_on_other_key_press = true;
+        self
+    }
+
+    /// Makes the key work even when global hotkeys are paused
+    pub fn bypass_pause(mut self) -> Self {
+        self.bypass_pause = true;
+        self
+    }
+
+    /// Sets the callback to execute on tap, instead of sending the key
+    pub fn on_tap<F>(mut self, action: F) -> Self
+    where
+        F: Fn() + Send + Sync + 'static,
+    {
+        self.on_tap = Some(Arc::new(Box::new(action)));
+        self
+    }
+
+    /// Makes the key act as the given modifier while held
+    pub fn hold_as(mut self, modifier: VKey) -> Self {
+        self.hold_modifier = Some(modifier);
+        self
+    }
+
+    /// Sets the callback to execute when the hold is decided
+    pub fn on_hold<F>(mut self, action: F) -> Self
+    where
+        F: Fn() + Send + Sync + 'static,
+    {
+        self.on_hold = Some(Arc::new(Box::new(action)));
+        self
+    }
+
+    /// Sets the callback to execute when the key is released after a hold
+    pub fn on_hold_release<F>(mut self, action: F) -> Self
+    where
+        F: Fn() + Send + Sync + 'static,
+    {
+        self.on_hold_release = Some(Arc::new(Box::new(action)));
+        self
+    }
+}
+
+impl fmt::Debug for DualRoleKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DualRoleKey")
+            .field("key", &self.key)
+            .field("tapping_term", &self.tapping_term)
+            .field("permissive_hold", &self.permissive_hold)
+            .field("hold_on_other_key_press", &self.hold_on_other_key_press)
+            .field("hold_modifier", &self.hold_modifier)
+            .finish()
+    }
+}
+
+/// A key pressed while the dual-role key was undecided.
+#[derive(Debug, Clone)]
+pub(crate) struct BufferedKey {
+    pub vk_code: u16,
+    /// keyboard state when the key was pressed
+    pub state: KeyboardState,
+    /// time of the key press, in milliseconds
+    pub time: u32,
+    /// whether the key was already released
+    pub released: bool,
+}
+
+/// Decision taken for a dual-role key.
+#[derive(Debug)]
+pub(crate) enum Resolution {
+    /// the key was tapped, the buffered keys must be replayed after the tap
+    Tap(DualRoleKey, Vec<BufferedKey>),
+    /// the key is held, the buffered keys must be replayed with the hold active
+    Hold(DualRoleKey, Vec<BufferedKey>),
+    /// the key was released after a hold
+    HoldEnd(DualRoleKey),
+}
+
+/// Outcome of feeding a key event to the dual-role matcher.
+#[derive(Debug)]
+pub(crate) enum DualRoleStep {
+    /// the key is not related to a dual-role key
+    Unrelated,
+    /// the key was swallowed
+    Swallowed,
+    /// the key was swallowed and caused a decision
+    Decided(Resolution),
+}
+
+#[derive(Debug)]
+enum ActiveDualRole {
+    Undecided {
+        key: DualRoleKey,
+        deadline: Instant,
+        buffered: Vec<BufferedKey>,
+    },
+    Holding(DualRoleKey),
+}
+
+/// Tracks the dual-role key being pressed for the dual-role keys of a [`crate::HotkeyManager`].
+#[derive(Debug, Default)]
+pub(crate) struct DualRoleMatcher {
+    active: Mutex<Option<ActiveDualRole>>,
+}
+
+impl DualRoleMatcher {
+    /// Feeds a key press to the dual-role matcher.
+    pub(crate) fn on_key_down(
+        &self,
+        keys: &HashMap<VKey, DualRoleKey>,
+        vk_code: u16,
+        state: &KeyboardState,
+        time: u32,
+        paused: bool,
+    ) -> DualRoleStep {
+        let mut active = self.active.lock().unwrap();
+        let pressed = VKey::from(vk_code);
+        match active.as_mut() {
+            None => {
+                let Some(key) = keys.get(&pressed) else {
+                    return DualRoleStep::Unrelated;
+                };
+                if paused && !key.bypass_pause {
+                    return DualRoleStep::Unrelated;
+                }
+                *active = Some(ActiveDualRole::Undecided {
+                    key: key.clone(),
+                    deadline: Instant::now() + key.tapping_term,
+                    buffered: Vec::new(),
+                });
+                DualRoleStep::Swallowed
+            }
+            // autorepeat of the dual-role key
+            Some(ActiveDualRole::Undecided { key, .. }) | Some(ActiveDualRole::Holding(key))
+                if key.key == pressed =>
+            {
+                DualRoleStep::Swallowed
+            }
+            Some(ActiveDualRole::Holding(_)) => DualRoleStep::Unrelated,
+            Some(ActiveDualRole::Undecided { key, buffered, .. }) => {
+                buffered.retain(|b| b.vk_code != vk_code || b.released);
+                buffered.push(BufferedKey {
+                    vk_code,
+                    state: state.clone(),
+                    time,
+                    released: false,
+                });
+                if !key.hold_on_other_key_press {
+                    return DualRoleStep::Swallowed;
+                }
+                let key = key.clone();
+                let buffered = std::mem::take(buffered);
+                *active = Some(ActiveDualRole::Holding(key.clone()));
+                DualRoleStep::Decided(Resolution::Hold(key, buffered))
+            }
+        }
+    }
+
+    /// Feeds a key release to the dual-role matcher.
+    pub(crate) fn on_key_up(&self, vk_code: u16) -> DualRoleStep {
+        let mut active = self.active.lock().unwrap();
+        let released = VKey::from(vk_code);
+        let Some(current) = active.as_mut() else {
+            return DualRoleStep::Unrelated;
+        };
+        match current {
+            ActiveDualRole::Holding(key) if key.key == released => {
+                let key = key.clone();
+                *active = None;
+                DualRoleStep::Decided(Resolution::HoldEnd(key))
+            }
+            ActiveDualRole::Holding(_) => DualRoleStep::Unrelated,
+            ActiveDualRole::Undecided { key, buffered, .. } if key.key == released => {
+                let resolution = Resolution::Tap(key.clone(), std::mem::take(buffered));
+                *active = None;
+                DualRoleStep::Decided(resolution)
+            }
+            ActiveDualRole::Undecided { key, buffered, .. } => {
+                let Some(buffered_key) = buffered
+                    .iter_mut()
+                    .find(|b| b.vk_code == vk_code && !b.released)
+                else {
+                    return DualRoleStep::Unrelated;
+                };
+                buffered_key.released = true;
+                if !key.permissive_hold {
+                    return DualRoleStep::Swallowed;
+                }
+                let key = key.clone();
+                let buffered = std::mem::take(buffered);
+                *active = Some(ActiveDualRole::Holding(key.clone()));
+                DualRoleStep::Decided(Resolution::Hold(key, buffered))
+            }
+        }
+    }
+
+    /// Returns whether a dual-role key is pressed, its release or the release of the
+    /// buffered keys are swallowed.
+    pub(crate) fn is_active(&self) -> bool {
+        self.active.lock().unwrap().is_some()
+    }
+
+    /// Returns when the undecided dual-role key becomes a hold, if any.
+    pub(crate) fn pending_deadline(&self) -> Option<Instant> {
+        match self.active.lock().unwrap().as_ref()? {
+            ActiveDualRole::Undecided { deadline, .. } => Some(*deadline),
+            ActiveDualRole::Holding(_) => None,
+        }
+    }
+
+    /// Decides hold for the undecided dual-role key if its tapping term ended.
+    pub(crate) fn expire(&self) -> Option<Resolution> {
+        let mut active = self.active.lock().unwrap();
+        match active.as_mut()? {
+            ActiveDualRole::Undecided {
+                key,
+                deadline,
+                buffered,
+            } if *deadline <= Instant::now() => {
+                let key = key.clone();
+                let buffered = std::mem::take(buffered);
+                *active = Some(ActiveDualRole::Holding(key.clone()));
+                Some(Resolution::Hold(key, buffered))
+            }
+            _ => None,
+        }
+    }
+
+    /// Forgets the active dual-role key.
+    pub(crate) fn cancel(&self) {
+        self.active.lock().unwrap().take();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::backend::testing::ScriptedEngine;
+    use crate::backend::KeyInput;
+    use crate::events::KeyAction;
+    use crate::{Hotkey, HotkeyManager};
+
+    const WAIT: Duration = Duration::from_secs(1);
+
+    #[test]
+    fn test_tap_sends_key() {
+        let engine = ScriptedEngine::start();
+        HotkeyManager::current()
+            .register_dual_role(DualRoleKey::new(VKey::Space).hold_as(VKey::LControl))
+            .unwrap();
+
+        assert_eq!(engine.chord([VKey::Space]), vec![KeyAction::Block]);
+        assert_eq!(
+            engine.take_sent(),
+            vec![KeyInput::Down(VKey::Space), KeyInput::Up(VKey::Space)]
+        );
+    }
+
+    #[test]
+    fn test_releases_wait_while_pressed() {
+        let engine = ScriptedEngine::start();
+        HotkeyManager::current()
+            .register_dual_role(DualRoleKey::new(VKey::Space).hold_as(VKey::LControl))
+            .unwrap();
+
+        let other = VKey::A.to_vk_code();
+        assert!(!HotkeyManager::decides_release(other));
+        engine.press(VKey::Space);
+        assert!(HotkeyManager::decides_release(other));
+        engine.release(VKey::Space);
+        assert!(!HotkeyManager::decides_release(other));
+    }
+
+    #[test]
+    fn test_tap_callback() {
+        let engine = ScriptedEngine::start();
+        let (tx, rx) = crossbeam_channel::unbounded();
+        HotkeyManager::current()
+            .register_dual_role(DualRoleKey::new(VKey::Capital).on_tap(move || {
+                tx.send(()).unwrap();
+            }))
+            .unwrap();
+
+        assert_eq!(engine.chord([VKey::Capital]), vec![KeyAction::Block]);
+        assert!(rx.recv_timeout(WAIT).is_ok(), "Tap should be executed");
+        assert!(engine.take_sent().is_empty());
+    }
+
+    #[test]
+    fn test_hold_after_tapping_term() {
+        let engine = ScriptedEngine::start();
+        HotkeyManager::current()
+            .register_dual_role(
+                DualRoleKey::new(VKey::Capital)
+                    .hold_as(VKey::LControl)
+                    .tapping_term(Duration::from_millis(50)),
+            )
+            .unwrap();
+
+        assert_eq!(engine.press(VKey::Capital), KeyAction::Block);
+        assert_eq!(engine.wait_sent(WAIT), vec![KeyInput::Down(VKey::LControl)]);
+
+        // keys pressed while holding are not affected
+        assert_eq!(engine.press(VKey::C), KeyAction::Allow);
+        engine.release(VKey::C);
+        engine.release(VKey::Capital);
+        assert_eq!(engine.take_sent(), vec![KeyInput::Up(VKey::LControl)]);
+    }
+
+    #[test]
+    fn test_hold_triggers_hotkeys() {
+        let engine = ScriptedEngine::start();
+        let (tx, rx) = crossbeam_channel::unbounded();
+        let manager = HotkeyManager::current();
+        manager
+            .register_dual_role(
+                DualRoleKey::new(VKey::Capital)
+                    .hold_as(VKey::LControl)
+                    .hold_on_other_key_press(),
+            )
+            .unwrap();
+        manager
+            .register_hotkey(Hotkey::new(VKey::S, [VKey::Control], move || {
+                tx.send(()).unwrap();
+            }))
+            .unwrap();
+
+        engine.press(VKey::Capital);
+        assert_eq!(engine.press(VKey::S), KeyAction::Block);
+        assert!(rx.recv_timeout(WAIT).is_ok(), "Hotkey should be executed");
+        // the key that triggered the hotkey is not replayed
+        assert_eq!(engine.take_sent(), vec![KeyInput::Down(VKey::LControl)]);
+    }
+
+    #[test]
+    fn test_tap_replays_interrupting_keys() {
+        let engine = ScriptedEngine::start();
+        HotkeyManager::current()
+            .register_dual_role(DualRoleKey::new(VKey::Space).hold_as(VKey::LShift))
+            .unwrap();
+
+        engine.press(VKey::Space);
+        assert_eq!(engine.press(VKey::A), KeyAction::Block);
+        engine.release(VKey::A);
+        engine.release(VKey::Space);
+        assert_eq!(
+            engine.take_sent(),
+            vec![
+                KeyInput::Down(VKey::Space),
+                KeyInput::Up(VKey::Space),
+                KeyInput::Down(VKey::A),
+                KeyInput::Up(VKey::A),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_permissive_hold() {
+        let engine = ScriptedEngine::start();
+        HotkeyManager::current()
+            .register_dual_role(
+                DualRoleKey::new(VKey::Space)
+                    .hold_as(VKey::LShift)
+                    .permissive_hold(),
+            )
+            .unwrap();
+
+        engine.press(VKey::Space);
+        assert_eq!(engine.press(VKey::A), KeyAction::Block);
+        engine.release(VKey::A);
+        engine.release(VKey::Space);
+        assert_eq!(
+            engine.take_sent(),
+            vec![
+                KeyInput::Down(VKey::LShift),
+                KeyInput::Down(VKey::A),
+                KeyInput::Up(VKey::A),
+                KeyInput::Up(VKey::LShift),
+            ]
+        );
+    }
+}
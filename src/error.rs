@@ -4,7 +4,7 @@
 
 use thiserror::Error;
 
-use crate::VKey;
+use crate::{BindingErrorKind, HotkeyConflict, HotkeyId, VKey};
 
 /// An enumeration of errors that may occur while using the crate.
 #[derive(Error, Debug)]
@@ -13,12 +13,39 @@ pub enum WHKError {
     AlreadyStarted,
     #[error("Failed to start hook thread.")]
     StartupFailed,
+    #[error("There is no default input backend for this platform.")]
+    NoDefaultBackend,
     #[error("Hotkey registration failed. Hotkey is already in use.")]
     HotKeyAlreadyRegistered,
+    #[error("Hotkey registration failed. {0}")]
+    HotkeyConflict(Box<HotkeyConflict>),
+    #[error("Hotkey registration failed. A synchronous action must be triggered on press.")]
+    SyncActionNotOnPress,
+    #[error("Hotkey registration failed. Id `{0}` is already in use.")]
+    HotkeyIdInUse(HotkeyId),
+    #[error("No hotkey registered with id `{0}`")]
+    UnknownHotkey(HotkeyId),
+    #[error("No action registered with name `{0}`")]
+    UnknownAction(String),
+    #[error("No keymap source is set.")]
+    NoKeymapSource,
+    #[error("Failed to read the keymap. {0}")]
+    KeymapSource(String),
+    #[error("Hotkey sequence has no chords.")]
+    EmptySequence,
+    #[error("Hotkey sequence registration failed. It is the start of a registered sequence, or starts with one.")]
+    SequencePrefixConflict,
     #[error("Invalid trigger key `{0:?}`")]
     HotkeyInvalidTriggerKey(VKey),
     #[error("Invalid key name `{0}`")]
     InvalidKey(String),
+    #[error("Invalid hotkey binding `{input}` at position {position}: {kind}")]
+    InvalidBinding {
+        input: String,
+        /// byte offset of the error in the input
+        position: usize,
+        kind: BindingErrorKind,
+    },
     // crossbeam
     #[error("Sending event failed")]
     SendFailed,
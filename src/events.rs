@@ -1,19 +1,29 @@
 use std::sync::LazyLock;
+use std::time::Instant;
 
 use crossbeam_channel::{Receiver, Sender};
 
-use crate::{log_on_dev, state::KeyboardState};
+use crate::{log_on_dev, state::KeyboardState, VKey};
 
 static EVENT_LOOP_CHANNEL: LazyLock<(Sender<EventLoopEvent>, Receiver<EventLoopEvent>)> =
     LazyLock::new(crossbeam_channel::unbounded);
 
-static ACTION_CHANNEL: LazyLock<(Sender<KeyAction>, Receiver<KeyAction>)> =
+/// an answer of the event loop, with the id of the keyboard event it answers
+type KeyActionResponse = (u64, KeyAction);
+
+static ACTION_CHANNEL: LazyLock<(Sender<KeyActionResponse>, Receiver<KeyActionResponse>)> =
     LazyLock::new(crossbeam_channel::unbounded);
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum EventLoopEvent {
     Stop,
-    Keyboard(KeyboardInputEvent),
+    /// A keyboard event the backend waits the response for, with its id and when the
+    /// backend stops waiting
+    Keyboard(KeyboardInputEvent, u64, Instant),
+    /// A keyboard event the backend doesn't wait the response for
+    Notify(KeyboardInputEvent),
+    /// Makes the event loop recompute its deadlines, ex: after a capture session with timeout starts
+    Wake,
 }
 
 impl EventLoopEvent {
@@ -33,37 +43,141 @@ impl EventLoopEvent {
 /// **note**: This doesn't represent the real hardware event, as hooks on high priority
 /// can override the pressed keys.
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum KeyboardInputEvent {
     KeyDown {
         /// The virtual key code of the key.
         vk_code: u16,
         /// The updated keyboard state due to this event.
         state: KeyboardState,
+        /// Hardware information of the event.
+        info: KeyEventInfo,
     },
     KeyUp {
         /// The virtual key code of the key.
         vk_code: u16,
         /// The updated keyboard state due to this event.
         state: KeyboardState,
+        /// Hardware information of the event.
+        info: KeyEventInfo,
     },
 }
 
+impl KeyboardInputEvent {
+    /// Returns the virtual key code of the key.
+    pub fn vk_code(&self) -> u16 {
+        match self {
+            KeyboardInputEvent::KeyDown { vk_code, .. }
+            | KeyboardInputEvent::KeyUp { vk_code, .. } => *vk_code,
+        }
+    }
+
+    /// Returns the hardware information of the event.
+    pub fn info(&self) -> &KeyEventInfo {
+        match self {
+            KeyboardInputEvent::KeyDown { info, .. } | KeyboardInputEvent::KeyUp { info, .. } => {
+                info
+            }
+        }
+    }
+}
+
+/// Hardware information of a key event, as reported by the low-level keyboard hook.
+///
+/// # See Also
+/// - [KBDLLHOOKSTRUCT](https://learn.microsoft.com/en-us/windows/win32/api/winuser/ns-winuser-kbdllhookstruct)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KeyEventInfo {
+    /// The hardware scan code of the key.
+    pub scan_code: u32,
+    /// The key is an extended key, ex: the navigation keys that are not on the numeric keypad.
+    pub extended: bool,
+    /// The event was injected by software, ex: using `SendInput`, instead of a keyboard.
+    pub injected: bool,
+    /// The ALT key was down when the event was generated.
+    pub alt_down: bool,
+    /// Value of `dwExtraInfo` on the event, software that injects input can use it to tag
+    /// its events, see [`crate::InjectedPolicy::ProcessOnlyFrom`].
+    pub extra_info: usize,
+    /// Time of the event in milliseconds, only the difference between two events is meaningful
+    /// as the value may wrap around.
+    pub time: u32,
+}
+
 /// Enum representing how to handle keypress.
 #[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum KeyAction {
     Allow,
     Block,
     Replace,
+    /// Block the key and send the given key instead, with the same press/release state
+    Substitute(VKey),
 }
 
 impl KeyAction {
-    pub(crate) fn send(self) {
-        if ACTION_CHANNEL.0.send(self).is_err() {
+    /// Answers the keyboard event with the given id.
+    pub(crate) fn send(self, event_id: u64) {
+        if ACTION_CHANNEL.0.send((event_id, self)).is_err() {
             log_on_dev!("Failed to send key action");
         }
     }
 
-    pub(crate) fn reciever() -> Receiver<KeyAction> {
+    pub(crate) fn reciever() -> Receiver<KeyActionResponse> {
         ACTION_CHANNEL.1.clone()
     }
 }
+
+#[cfg(all(test, feature = "serde"))]
+mod serde_tests {
+    use super::*;
+    use serde_test::{assert_tokens, Token};
+
+    #[test]
+    fn test_serialize_deserialize_key_action() {
+        assert_tokens(
+            &KeyAction::Block,
+            &[Token::UnitVariant {
+                name: "KeyAction",
+                variant: "Block",
+            }],
+        );
+        assert_tokens(
+            &KeyAction::Substitute(VKey::Escape),
+            &[
+                Token::NewtypeVariant {
+                    name: "KeyAction",
+                    variant: "Substitute",
+                },
+                Token::Str("Escape"),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_keyboard_input_event_round_trip() {
+        let mut state = KeyboardState::new();
+        state.keydown(VKey::LControl);
+        state.keydown(VKey::A);
+        let event = KeyboardInputEvent::KeyDown {
+            vk_code: VKey::A.to_vk_code(),
+            state,
+            info: KeyEventInfo {
+                scan_code: 0x1E,
+                injected: true,
+                time: 1234,
+                ..Default::default()
+            },
+        };
+        let json = serde_json::to_string(&event).unwrap();
+        assert_eq!(
+            json,
+            r#"{"KeyDown":{"vk_code":65,"state":{"pressing":["LControl","A"]},"info":{"scan_code":30,"extended":false,"injected":true,"alt_down":false,"extra_info":0,"time":1234}}}"#
+        );
+        assert_eq!(
+            serde_json::from_str::<KeyboardInputEvent>(&json).unwrap(),
+            event
+        );
+    }
+}
@@ -0,0 +1,108 @@
+//! This module defines the keyboard filters, evaluated on every key event before the
+//! hotkeys are matched, which can block or replace any key, ex: disable the Windows key
+//! while gaming, without entering stealing mode.
+
+use crate::events::KeyboardInputEvent;
+use crate::VKey;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+static NEXT_FILTER_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Decision of a filter on a key event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterResult {
+    /// Let the next filters and the hotkeys handle the event
+    Pass,
+    /// Consume the event, it reaches neither the hotkeys nor other applications
+    Block,
+    /// Send the given key instead. When a key press is replaced, its release is
+    /// replaced too, without evaluating the filters
+    Replace(VKey),
+}
+
+type FilterCallback = Arc<dyn Fn(&KeyboardInputEvent) -> FilterResult + Send + Sync + 'static>;
+
+/// A filter registered on a [`crate::HotkeyManager`].
+#[derive(Clone)]
+pub(crate) struct KeyFilter {
+    pub id: u64,
+    pub priority: i32,
+    callback: FilterCallback,
+}
+
+impl KeyFilter {
+    pub fn new<F>(priority: i32, callback: F) -> Self
+    where
+        F: Fn(&KeyboardInputEvent) -> FilterResult + Send + Sync + 'static,
+    {
+        Self {
+            id: NEXT_FILTER_ID.fetch_add(1, Ordering::Relaxed),
+            priority,
+            callback: Arc::new(callback),
+        }
+    }
+}
+
+impl fmt::Debug for KeyFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeyFilter")
+            .field("id", &self.id)
+            .field("priority", &self.priority)
+            .field("callback", &"<callback>")
+            .finish()
+    }
+}
+
+/// Inserts a filter after the ones with the same or higher priority.
+pub(crate) fn insert(filters: &mut Vec<KeyFilter>, filter: KeyFilter) {
+    let index = filters.partition_point(|current| current.priority >= filter.priority);
+    filters.insert(index, filter);
+}
+
+/// Evaluates the filters in order, returns the result of the first one not passing the event.
+pub(crate) fn evaluate(filters: &[KeyFilter], event: &KeyboardInputEvent) -> FilterResult {
+    filters
+        .iter()
+        .map(|filter| (filter.callback)(event))
+        .find(|result| *result != FilterResult::Pass)
+        .unwrap_or(FilterResult::Pass)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::KeyEventInfo;
+    use crate::state::KeyboardState;
+
+    #[test]
+    fn test_filters_in_order_of_priority() {
+        let mut filters = Vec::new();
+        insert(&mut filters, KeyFilter::new(0, |_| FilterResult::Block));
+        insert(&mut filters, KeyFilter::new(10, |_| FilterResult::Pass));
+        insert(
+            &mut filters,
+            KeyFilter::new(10, |event| match event.vk_code() {
+                0x41 => FilterResult::Replace(VKey::B),
+                _ => FilterResult::Pass,
+            }),
+        );
+        let priorities: Vec<i32> = filters.iter().map(|filter| filter.priority).collect();
+        assert_eq!(priorities, vec![10, 10, 0]);
+        // same priority keeps the insertion order
+        assert!(filters[0].id < filters[1].id);
+
+        let event = |vk_code| KeyboardInputEvent::KeyDown {
+            vk_code,
+            state: KeyboardState::new(),
+            info: KeyEventInfo::default(),
+        };
+        assert_eq!(
+            evaluate(&filters, &event(0x41)),
+            FilterResult::Replace(VKey::B)
+        );
+        assert_eq!(evaluate(&filters, &event(0x43)), FilterResult::Block);
+        assert_eq!(evaluate(&[], &event(0x43)), FilterResult::Pass);
+    }
+}
@@ -0,0 +1,85 @@
+//! This module defines the `HotkeyHandle`, returned by
+//! [`HotkeyManager::register_hotkey_handle`], which controls a registered hotkey and
+//! unregisters it when dropped, ex: for the shortcuts of a window that can be closed.
+
+use crate::error::Result;
+use crate::hotkey::HotkeyId;
+use crate::manager::HotkeyManager;
+use crate::VKey;
+
+/// Handle of a registered hotkey.
+///
+/// The hotkey is unregistered when the handle is dropped, unless [`HotkeyHandle::keep`]
+/// is called.
+#[derive(Debug)]
+pub struct HotkeyHandle {
+    id: HotkeyId,
+    /// the registration of the hotkey, its id can be reused once it is unregistered
+    registration: u64,
+    manager: HotkeyManager,
+    /// whether the hotkey is unregistered on drop
+    owned: bool,
+}
+
+impl HotkeyHandle {
+    pub(crate) fn new(id: HotkeyId, registration: u64, manager: HotkeyManager) -> Self {
+        Self {
+            id,
+            registration,
+            manager,
+            owned: true,
+        }
+    }
+
+    /// Returns the id of the hotkey.
+    pub fn id(&self) -> &HotkeyId {
+        &self.id
+    }
+
+    /// Makes the hotkey match its keys again.
+    pub fn enable(&self) -> Result<()> {
+        self.manager
+            .set_registration_enabled(&self.id, Some(self.registration), true)
+    }
+
+    /// Stops matching the hotkey, it stays registered.
+    pub fn disable(&self) -> Result<()> {
+        self.manager
+            .set_registration_enabled(&self.id, Some(self.registration), false)
+    }
+
+    /// Returns whether the hotkey is enabled.
+    pub fn is_enabled(&self) -> Result<bool> {
+        self.manager
+            .is_registration_enabled(&self.id, Some(self.registration))
+    }
+
+    /// Changes the keys of the hotkey, see [`HotkeyManager::rebind_hotkey`].
+    pub fn rebind<T: AsRef<[VKey]>>(&self, trigger_key: VKey, modifiers: T) -> Result<()> {
+        self.manager
+            .rebind_registration(&self.id, Some(self.registration), trigger_key, modifiers)
+    }
+
+    /// Unregisters the hotkey now.
+    pub fn unregister(mut self) -> Result<()> {
+        self.owned = false;
+        self.manager
+            .unregister_registration(&self.id, Some(self.registration))
+    }
+
+    /// Keeps the hotkey registered after the handle is dropped, returns its id.
+    pub fn keep(mut self) -> HotkeyId {
+        self.owned = false;
+        self.id.clone()
+    }
+}
+
+impl Drop for HotkeyHandle {
+    fn drop(&mut self) {
+        if self.owned {
+            let _ = self
+                .manager
+                .unregister_registration(&self.id, Some(self.registration));
+        }
+    }
+}
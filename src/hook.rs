@@ -3,47 +3,107 @@
 //! and releases, tracks the state of modifier keys, and communicates events
 //! via channels to the rest of the application.
 
+use crate::backend::{self, InputBackend, KeyInput, SILENT_VK_CODE};
 use crate::error::{Result, WHKError};
-use crate::events::{EventLoopEvent, KeyAction, KeyboardInputEvent};
+use crate::events::{KeyAction, KeyEventInfo};
 use crate::log_on_dev;
+use crate::scope::WindowContext;
 use crate::state::KEYBOARD_STATE;
+use crate::VKey;
 use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 use std::thread;
-use std::time::Duration;
-use windows::Win32::Foundation::{HANDLE, LPARAM, LRESULT, WPARAM};
+use windows::core::PWSTR;
+use windows::Win32::Foundation::{CloseHandle, HANDLE, LPARAM, LRESULT, WPARAM};
 use windows::Win32::System::Power::{
     RegisterSuspendResumeNotification, DEVICE_NOTIFY_SUBSCRIBE_PARAMETERS,
 };
-use windows::Win32::System::Threading::GetCurrentThreadId;
+use windows::Win32::System::Threading::{
+    GetCurrentThreadId, OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_WIN32,
+    PROCESS_QUERY_LIMITED_INFORMATION,
+};
 use windows::Win32::UI::Input::KeyboardAndMouse::{
-    SendInput, INPUT, INPUT_0, INPUT_KEYBOARD, KEYBDINPUT, KEYBD_EVENT_FLAGS, KEYEVENTF_KEYUP,
-    VIRTUAL_KEY,
+    GetAsyncKeyState, GetKeyState, SendInput, INPUT, INPUT_0, INPUT_KEYBOARD, KEYBDINPUT,
+    KEYBD_EVENT_FLAGS, KEYEVENTF_EXTENDEDKEY, KEYEVENTF_KEYUP, KEYEVENTF_UNICODE, VIRTUAL_KEY,
 };
 use windows::Win32::UI::WindowsAndMessaging::{
-    CallNextHookEx, DispatchMessageW, GetMessageW, PostThreadMessageW, SetWindowsHookExW,
-    TranslateMessage, DEVICE_NOTIFY_CALLBACK, KBDLLHOOKSTRUCT, MSG, PBT_APMRESUMEAUTOMATIC,
-    PBT_APMRESUMESUSPEND, WH_KEYBOARD_LL, WM_KEYDOWN, WM_KEYUP, WM_QUIT, WM_SYSKEYDOWN,
-    WM_SYSKEYUP,
+    CallNextHookEx, DispatchMessageW, GetClassNameW, GetForegroundWindow, GetMessageW,
+    GetWindowTextW, GetWindowThreadProcessId, PostThreadMessageW, SetWindowsHookExW,
+    TranslateMessage, DEVICE_NOTIFY_CALLBACK, KBDLLHOOKSTRUCT, LLKHF_ALTDOWN, LLKHF_EXTENDED,
+    LLKHF_INJECTED, MSG, PBT_APMRESUMEAUTOMATIC, PBT_APMRESUMESUSPEND, WH_KEYBOARD_LL, WM_KEYDOWN,
+    WM_KEYUP, WM_QUIT, WM_SYSKEYDOWN, WM_SYSKEYUP,
 };
 
-/// Timeout for blocking key events, measured in milliseconds.
-const TIMEOUT: Duration = Duration::from_millis(250);
-
 /// Unassigned Virtual Key code used to suppress Windows Key events.
-const SILENT_KEY: VIRTUAL_KEY = VIRTUAL_KEY(0xE8);
+const SILENT_KEY: VIRTUAL_KEY = VIRTUAL_KEY(SILENT_VK_CODE);
+
+/// Value of `dwExtraInfo` on the events sent by this crate, used to skip them on the hook.
+const INJECTED_TAG: usize = 0x5748_4B53;
 
 static STARTED: AtomicBool = AtomicBool::new(false);
 static HOOK_THREAD_ID: AtomicU32 = AtomicU32::new(0);
 
+/// [`InputBackend`] backed by the `WH_KEYBOARD_LL` low-level keyboard hook.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct HookBackend;
+
+impl InputBackend for HookBackend {
+    fn start(&self) -> Result<()> {
+        start()
+    }
+
+    fn stop(&self) {
+        stop()
+    }
+
+    fn is_key_down(&self, vk_code: u16) -> bool {
+        async_is_key_down(vk_code)
+    }
+
+    fn is_key_toggled(&self, vk_code: u16) -> bool {
+        is_key_toggled(vk_code)
+    }
+
+    fn send_input(&self, inputs: &[KeyInput]) {
+        send_input(inputs)
+    }
+
+    fn foreground_window(&self) -> Option<WindowContext> {
+        foreground_window()
+    }
+}
+
+/// Sends synthetic key events to the system using `SendInput`.
+pub(crate) fn send_input(inputs: &[KeyInput]) {
+    let mut events = Vec::with_capacity(inputs.len());
+    for input in inputs {
+        let (key, mut flags) = match input {
+            KeyInput::Down(key) => (key, KEYBD_EVENT_FLAGS(0)),
+            KeyInput::Up(key) => (key, KEYEVENTF_KEYUP),
+            KeyInput::Char(c) => {
+                // characters outside the BMP are sent as two UTF-16 surrogates
+                for unit in c.encode_utf16(&mut [0; 2]) {
+                    events.push(unicode_input(*unit, KEYEVENTF_UNICODE));
+                    events.push(unicode_input(*unit, KEYEVENTF_UNICODE | KEYEVENTF_KEYUP));
+                }
+                continue;
+            }
+        };
+        if is_extended_key(*key) {
+            flags |= KEYEVENTF_EXTENDEDKEY;
+        }
+        events.push(keyboard_input(VIRTUAL_KEY(key.to_vk_code()), flags));
+    }
+    unsafe {
+        SendInput(&events, size_of::<INPUT>() as i32);
+    }
+}
+
 /// Starts the keyboard hook thread.
 pub fn start() -> Result<()> {
     if STARTED.load(Ordering::Relaxed) {
         return Err(WHKError::AlreadyStarted);
     }
 
-    // Create/clear keyboard state
-    KEYBOARD_STATE.lock().unwrap().clear();
-
     let (tx, rx) = crossbeam_channel::unbounded::<bool>();
     thread::spawn(move || unsafe {
         let Ok(_keyborad_handle) =
@@ -91,6 +151,8 @@ pub fn stop() {
     unsafe {
         let _ = PostThreadMessageW(thread_id, WM_QUIT, WPARAM::default(), LPARAM::default());
     }
+    HOOK_THREAD_ID.store(0, Ordering::Relaxed);
+    STARTED.store(false, Ordering::Relaxed);
 }
 
 /// https://learn.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-registersuspendresumenotification
@@ -120,82 +182,173 @@ unsafe extern "system" fn keyboard_hook_proc(code: i32, wparam: WPARAM, lparam:
         };
 
         let vk_code = event_data.vkCode as u16;
-        if vk_code == SILENT_KEY.0 {
+        if vk_code == SILENT_KEY.0 || event_data.dwExtraInfo == INJECTED_TAG {
             return CallNextHookEx(None, code, wparam, lparam);
         }
 
+        let info = event_info(event_data);
         match event_type {
-            // We only care about key down events
-            WM_KEYDOWN | WM_SYSKEYDOWN => {
-                let state = {
-                    let mut state = KEYBOARD_STATE.lock().unwrap();
-                    state.keydown(vk_code);
-                    state.clone()
-                };
-                log_on_dev!("{state:?}");
-
-                // Clear the actions channel of any previous action
-                let response_rx = KeyAction::reciever();
-                while response_rx.try_recv().is_ok() {}
-
-                EventLoopEvent::Keyboard(KeyboardInputEvent::KeyDown { vk_code, state }).send();
-
-                // Wait for response on how to handle event
-                if let Ok(action) = response_rx.recv_timeout(TIMEOUT) {
-                    match action {
-                        KeyAction::Block => {
-                            return LRESULT(1);
-                        }
-                        KeyAction::Replace => {
-                            send_silent_key();
-                            return LRESULT(1);
-                        }
-                        KeyAction::Allow => {}
-                    }
+            WM_KEYDOWN | WM_SYSKEYDOWN => match backend::key_down(vk_code, info) {
+                KeyAction::Block => {
+                    return LRESULT(1);
                 }
-            }
-            WM_KEYUP | WM_SYSKEYUP => {
-                let state = {
-                    let mut state = KEYBOARD_STATE.lock().unwrap();
-                    state.keyup(vk_code);
-                    state.clone()
-                };
-                log_on_dev!("{state:?}");
-                EventLoopEvent::Keyboard(KeyboardInputEvent::KeyUp { vk_code, state }).send();
-            }
+                KeyAction::Replace => {
+                    send_silent_key();
+                    return LRESULT(1);
+                }
+                KeyAction::Substitute(key) => {
+                    send_input(&[KeyInput::Down(key)]);
+                    return LRESULT(1);
+                }
+                KeyAction::Allow => {}
+            },
+            WM_KEYUP | WM_SYSKEYUP => match backend::key_up(vk_code, info, false) {
+                KeyAction::Block | KeyAction::Replace => {
+                    return LRESULT(1);
+                }
+                KeyAction::Substitute(key) => {
+                    send_input(&[KeyInput::Up(key)]);
+                    return LRESULT(1);
+                }
+                KeyAction::Allow => {}
+            },
             _ => {}
         };
     }
     CallNextHookEx(None, code, wparam, lparam)
 }
 
+/// Extracts the hardware information of a hook event.
+fn event_info(event_data: &KBDLLHOOKSTRUCT) -> KeyEventInfo {
+    KeyEventInfo {
+        scan_code: event_data.scanCode,
+        extended: event_data.flags.contains(LLKHF_EXTENDED),
+        injected: event_data.flags.contains(LLKHF_INJECTED),
+        alt_down: event_data.flags.contains(LLKHF_ALTDOWN),
+        extra_info: event_data.dwExtraInfo,
+        time: event_data.time,
+    }
+}
+
+/// Returns whether a key is currently pressed according to the OS.
+pub(crate) fn async_is_key_down(vk_code: u16) -> bool {
+    let state: i16 = unsafe { GetAsyncKeyState(vk_code.into()) };
+    // Check if the high-order bit is set (on intergers this bit is set if the value is negative)
+    state < 0
+}
+
+/// Returns whether a lock key is on according to the OS.
+pub(crate) fn is_key_toggled(vk_code: u16) -> bool {
+    let state: i16 = unsafe { GetKeyState(vk_code.into()) };
+    // the low-order bit is set if the key is toggled
+    state & 1 != 0
+}
+
+/// Returns the process, class and title of the foreground window.
+pub(crate) fn foreground_window() -> Option<WindowContext> {
+    unsafe {
+        let hwnd = GetForegroundWindow();
+        if hwnd.is_invalid() {
+            return None;
+        }
+
+        let mut buffer = [0u16; 512];
+        let len = GetClassNameW(hwnd, &mut buffer).max(0) as usize;
+        let window_class = String::from_utf16_lossy(&buffer[..len]);
+        let len = GetWindowTextW(hwnd, &mut buffer).max(0) as usize;
+        let title = String::from_utf16_lossy(&buffer[..len]);
+
+        let mut process_id = 0;
+        GetWindowThreadProcessId(hwnd, Some(&mut process_id));
+        Some(WindowContext {
+            process_name: process_name(process_id).unwrap_or_default(),
+            window_class,
+            title,
+        })
+    }
+}
+
+/// Returns the executable file name of a process, ex: `code.exe`.
+unsafe fn process_name(process_id: u32) -> Option<String> {
+    let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, process_id).ok()?;
+    let mut buffer = [0u16; 1024];
+    let mut len = buffer.len() as u32;
+    let result = QueryFullProcessImageNameW(
+        process,
+        PROCESS_NAME_WIN32,
+        PWSTR(buffer.as_mut_ptr()),
+        &mut len,
+    );
+    let _ = CloseHandle(process);
+    result.ok()?;
+
+    let path = String::from_utf16_lossy(&buffer[..len as usize]);
+    path.rsplit('\\').next().map(str::to_string)
+}
+
 /// Sends a keydown and keyup event for Unassigned Virtual Key 0xE8.
 unsafe fn send_silent_key() {
     let inputs = [
-        INPUT {
-            r#type: INPUT_KEYBOARD,
-            Anonymous: INPUT_0 {
-                ki: KEYBDINPUT {
-                    wVk: SILENT_KEY,
-                    wScan: 0,
-                    dwFlags: KEYBD_EVENT_FLAGS(0),
-                    time: 0,
-                    dwExtraInfo: 0,
-                },
+        keyboard_input(SILENT_KEY, KEYBD_EVENT_FLAGS(0)),
+        keyboard_input(SILENT_KEY, KEYEVENTF_KEYUP),
+    ];
+    SendInput(&inputs, size_of::<INPUT>() as i32);
+}
+
+fn keyboard_input(vk: VIRTUAL_KEY, flags: KEYBD_EVENT_FLAGS) -> INPUT {
+    INPUT {
+        r#type: INPUT_KEYBOARD,
+        Anonymous: INPUT_0 {
+            ki: KEYBDINPUT {
+                wVk: vk,
+                wScan: 0,
+                dwFlags: flags,
+                time: 0,
+                dwExtraInfo: INJECTED_TAG,
             },
         },
-        INPUT {
-            r#type: INPUT_KEYBOARD,
-            Anonymous: INPUT_0 {
-                ki: KEYBDINPUT {
-                    wVk: SILENT_KEY,
-                    wScan: 0,
-                    dwFlags: KEYEVENTF_KEYUP,
-                    time: 0,
-                    dwExtraInfo: 0,
-                },
+    }
+}
+
+/// Input typing a UTF-16 code unit, independently of the keyboard layout.
+/// https://learn.microsoft.com/en-us/windows/win32/api/winuser/ns-winuser-keybdinput
+fn unicode_input(unit: u16, flags: KEYBD_EVENT_FLAGS) -> INPUT {
+    INPUT {
+        r#type: INPUT_KEYBOARD,
+        Anonymous: INPUT_0 {
+            ki: KEYBDINPUT {
+                wVk: VIRTUAL_KEY(0),
+                wScan: unit,
+                dwFlags: flags,
+                time: 0,
+                dwExtraInfo: INJECTED_TAG,
             },
         },
-    ];
-    SendInput(&inputs, size_of::<INPUT>() as i32);
+    }
+}
+
+/// Keys whose scan code is prefixed by `0xE0`, these need
+/// `KEYEVENTF_EXTENDEDKEY` to not be confused with their numpad twins.
+fn is_extended_key(key: VKey) -> bool {
+    matches!(
+        key,
+        VKey::RControl
+            | VKey::RMenu
+            | VKey::LWin
+            | VKey::RWin
+            | VKey::Apps
+            | VKey::Insert
+            | VKey::Delete
+            | VKey::Home
+            | VKey::End
+            | VKey::Prior
+            | VKey::Next
+            | VKey::Left
+            | VKey::Up
+            | VKey::Right
+            | VKey::Down
+            | VKey::Divide
+            | VKey::Numlock
+            | VKey::Snapshot
+    )
 }
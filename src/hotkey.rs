@@ -2,24 +2,188 @@
 //! A hotkey is composed of a trigger key, one or more modifier keys, and a callback function
 //! that is executed when the hotkey is triggered.
 
+use crate::binding::HotkeyBinding;
+use crate::client_executor::call_on_executor_thread;
+use crate::condition::{HotkeyCondition, HotkeyContext};
+use crate::events::KeyAction;
+use crate::layer::LayerAction;
+use crate::scope::Scope;
+use crate::sender::{KeyOutput, KeySender};
 use crate::state::KeyboardState;
 use crate::VKey;
+use std::cmp::Reverse;
 use std::collections::BTreeSet;
 use std::fmt;
 use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+static NEXT_HOTKEY_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Default maximum time between the presses of a multi-tap hotkey.
+pub const DEFAULT_TAP_WINDOW: Duration = Duration::from_millis(300);
+
+/// Default time a synchronous hotkey callback has to decide the [`Propagation`].
+pub const DEFAULT_SYNC_BUDGET: Duration = Duration::from_millis(50);
+
+/// Maximum time a synchronous hotkey callback can have to decide the [`Propagation`],
+/// it must stay below the time the keyboard hook waits for an answer.
+pub const MAX_SYNC_BUDGET: Duration = Duration::from_millis(200);
 
 /// Defines what should happen with the key event after hotkey triggers
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TriggerBehavior {
     /// Allow the key event to propagate to other applications
     PassThrough,
     /// Consume the key event and prevent further processing
+    #[default]
     StopPropagation,
 }
 
+/// Defines on which key event the hotkey callback is executed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TriggerOn {
+    /// Execute the callback when the trigger key is pressed
+    #[default]
+    Press,
+    /// Execute the callback when the trigger key is released, after the hotkey was pressed
+    Release,
+    /// Execute the callback on both press and release
+    Both,
+}
+
+/// Decision of a synchronous hotkey callback on the key event that triggered it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Propagation {
+    /// Consume the key event and prevent further processing
+    Block,
+    /// Allow the key event to propagate to other applications
+    Allow,
+}
+
+impl Propagation {
+    /// Returns how the key event that triggered a hotkey must be handled.
+    pub(crate) fn key_action(&self, state: &KeyboardState) -> KeyAction {
+        match self {
+            Propagation::Block => TriggerBehavior::StopPropagation.key_action(state),
+            Propagation::Allow => TriggerBehavior::PassThrough.key_action(state),
+        }
+    }
+}
+
+/// Identifies a registered hotkey. Each hotkey gets a unique id when created,
+/// which can be replaced by a name chosen by the user with [`Hotkey::id`].
+///
+/// With the `serde` feature, named ids are serialized as strings. Allocated ids can't
+/// be serialized, as they are only unique within the process that allocated them.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct HotkeyId(IdKind);
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+enum IdKind {
+    Allocated(u64),
+    Named(String),
+}
+
+impl HotkeyId {
+    /// Allocates a new unique id.
+    pub(crate) fn next() -> Self {
+        HotkeyId(IdKind::Allocated(
+            NEXT_HOTKEY_ID.fetch_add(1, Ordering::Relaxed),
+        ))
+    }
+
+    /// Creates an id from a name chosen by the user, ex: `"editor.save"`.
+    pub fn named<S: Into<String>>(name: S) -> Self {
+        HotkeyId(IdKind::Named(name.into()))
+    }
+
+    /// Returns the name of the id, `None` if it was allocated.
+    pub fn name(&self) -> Option<&str> {
+        match &self.0 {
+            IdKind::Allocated(_) => None,
+            IdKind::Named(name) => Some(name),
+        }
+    }
+}
+
+impl fmt::Display for HotkeyId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.0 {
+            IdKind::Allocated(id) => write!(f, "#{id}"),
+            IdKind::Named(name) => f.write_str(name),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for HotkeyId {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match &self.0 {
+            IdKind::Allocated(_) => Err(serde::ser::Error::custom(format!(
+                "the allocated hotkey id `{self}` can't be serialized, only named ones"
+            ))),
+            IdKind::Named(name) => serializer.serialize_str(name),
+        }
+    }
+}
+
+/// Deserialized from a string, as a named id.
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for HotkeyId {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        String::deserialize(deserializer).map(HotkeyId::named)
+    }
+}
+
+impl From<&str> for HotkeyId {
+    fn from(name: &str) -> Self {
+        HotkeyId::named(name)
+    }
+}
+
+impl From<String> for HotkeyId {
+    fn from(name: String) -> Self {
+        HotkeyId::named(name)
+    }
+}
+
+pub(crate) type HotkeyCallback = Arc<Box<dyn Fn() + Send + Sync + 'static>>;
+type SyncHotkeyCallback = Arc<dyn Fn() -> Propagation + Send + Sync + 'static>;
+
+/// Synchronous callback of a triggered hotkey, waiting to decide the propagation.
+pub(crate) struct SyncDecision {
+    /// id of the triggered hotkey
+    pub id: HotkeyId,
+    callback: SyncHotkeyCallback,
+    budget: Duration,
+    fallback: Propagation,
+}
+
+impl SyncDecision {
+    /// Executes the callback on the executor thread and waits for its decision,
+    /// up to the budget or the deadline of the key event, whichever comes first.
+    pub fn decide(self, deadline: Instant) -> Propagation {
+        let budget = self
+            .budget
+            .min(deadline.saturating_duration_since(Instant::now()));
+        call_on_executor_thread(self.callback, budget).unwrap_or(self.fallback)
+    }
+}
+
 /// Represents a keyboard shortcut that triggers an action
 pub struct Hotkey {
+    /// identifies the hotkey once registered
+    pub id: HotkeyId,
     /// key that must be pressed to trigger this hotkey
     pub trigger_key: VKey,
     /// keys that must be pressed before the trigger key ex: [CTRL] + [A]
@@ -28,18 +192,62 @@ pub struct Hotkey {
     pub behaviour: TriggerBehavior,
     /// will ignore the `paused` global state
     pub bypass_pause: bool,
+    /// key event(s) on which the callback is executed
+    pub trigger_on: TriggerOn,
     /// callback function to execute when this hotkey is triggered
-    pub callback: Arc<Box<dyn Fn() + Send + Sync + 'static>>,
+    pub callback: HotkeyCallback,
+    /// callback function to execute when the trigger key is released, after the hotkey was pressed
+    pub on_release: Option<HotkeyCallback>,
+    /// times the hotkey must be pressed in a row to trigger, ex: 2 for a double-tap
+    pub taps: u8,
+    /// maximum time between two consecutive taps
+    pub tap_window: Duration,
+    /// allow the key events of the taps that don't complete the hotkey, instead of
+    /// replaying them once the hotkey can no longer complete
+    pub pass_through_taps: bool,
+    /// how the active layers change when this hotkey is triggered
+    pub layer_action: Option<LayerAction>,
+    /// foreground windows on which the hotkey is enabled, all of them if not set
+    pub scope: Option<Scope>,
+    /// conditions that must all be met for the hotkey to be eligible
+    pub conditions: Vec<HotkeyCondition>,
+    /// callback deciding the propagation of the key event, executed on press
+    pub sync_callback: Option<SyncHotkeyCallback>,
+    /// time the synchronous callback has to decide
+    pub sync_budget: Duration,
+    /// propagation used when the synchronous callback exceeds its budget
+    pub sync_fallback: Propagation,
+    /// hotkeys with higher priority are matched first among the ones of the same layer
+    pub priority: i32,
+    /// disabled hotkeys stay registered but are never matched
+    pub enabled: bool,
+    /// identifies the registration, the id can be reused once the hotkey is unregistered
+    pub(crate) registration: u64,
 }
 
 impl Hotkey {
     fn base() -> Hotkey {
         Hotkey {
+            id: HotkeyId::next(),
             trigger_key: VKey::None,
             modifiers: BTreeSet::new(),
             behaviour: TriggerBehavior::StopPropagation,
             bypass_pause: false,
+            trigger_on: TriggerOn::Press,
             callback: Arc::new(Box::new(|| {})),
+            on_release: None,
+            taps: 1,
+            tap_window: DEFAULT_TAP_WINDOW,
+            pass_through_taps: false,
+            layer_action: None,
+            scope: None,
+            conditions: Vec::new(),
+            sync_callback: None,
+            sync_budget: DEFAULT_SYNC_BUDGET,
+            sync_fallback: Propagation::Allow,
+            priority: 0,
+            enabled: true,
+            registration: 0,
         }
     }
 
@@ -50,11 +258,26 @@ impl Hotkey {
         F: Fn() + Send + Sync + 'static,
     {
         Self {
+            id: HotkeyId::next(),
             trigger_key,
             behaviour: TriggerBehavior::StopPropagation,
             bypass_pause: false,
             modifiers: modifiers.as_ref().iter().cloned().collect(),
+            trigger_on: TriggerOn::Press,
             callback: Arc::new(Box::new(callback)),
+            on_release: None,
+            taps: 1,
+            tap_window: DEFAULT_TAP_WINDOW,
+            pass_through_taps: false,
+            layer_action: None,
+            scope: None,
+            conditions: Vec::new(),
+            sync_callback: None,
+            sync_budget: DEFAULT_SYNC_BUDGET,
+            sync_fallback: Propagation::Allow,
+            priority: 0,
+            enabled: true,
+            registration: 0,
         }
     }
 
@@ -98,50 +321,218 @@ impl Hotkey {
         self
     }
 
+    /// Makes the hotkey send the given keys when triggered, replacing its callback,
+    /// ex: remap [CAPSLOCK] + [H] to the left arrow
+    pub fn emit(mut self, output: KeyOutput) -> Self {
+        self.callback = Arc::new(Box::new(move || KeySender.send(&output)));
+        self
+    }
+
+    /// Replaces the callback with one that decides at runtime whether the key event
+    /// is blocked, ex: only consume [F5] when something was refreshed.
+    ///
+    /// It runs synchronously when the hotkey is pressed, after the previously queued
+    /// callbacks, and must answer within [`Hotkey::sync_budget`]. Otherwise the key event is
+    /// handled with the fallback propagation, while the callback still runs to completion.
+    /// The hotkey must be triggered on [`TriggerOn::Press`], or its registration fails.
+    pub fn sync_action<F>(mut self, action: F) -> Self
+    where
+        F: Fn() -> Propagation + Send + Sync + 'static,
+    {
+        self.callback = Arc::new(Box::new(|| {}));
+        self.sync_callback = Some(Arc::new(action));
+        self
+    }
+
+    /// Sets the time the synchronous callback has to decide, up to [`MAX_SYNC_BUDGET`],
+    /// and the propagation used when it doesn't answer in time.
+    pub fn sync_budget(mut self, budget: Duration, fallback: Propagation) -> Self {
+        self.sync_budget = budget.min(MAX_SYNC_BUDGET);
+        self.sync_fallback = fallback;
+        self
+    }
+
+    /// Sets on which key event(s) the callback is executed
+    pub fn trigger_on(mut self, trigger_on: TriggerOn) -> Self {
+        self.trigger_on = trigger_on;
+        self
+    }
+
+    /// Sets a callback to execute when the trigger key is released after the hotkey was pressed,
+    /// ex: stop recording on a push-to-talk hotkey
+    pub fn on_release<F>(mut self, action: F) -> Self
+    where
+        F: Fn() + Send + Sync + 'static,
+    {
+        self.on_release = Some(Arc::new(Box::new(action)));
+        self
+    }
+
+    /// Makes the hotkey trigger only when pressed `taps` times in a row,
+    /// ex: double-tap [SHIFT] to open a launcher. Values below 1 are treated as 1.
+    pub fn taps(mut self, taps: u8) -> Self {
+        self.taps = taps.max(1);
+        self
+    }
+
+    /// Sets the maximum time between two consecutive taps of a multi-tap hotkey
+    pub fn tap_window(mut self, window: Duration) -> Self {
+        self.tap_window = window;
+        self
+    }
+
+    /// Allows the taps that don't complete a multi-tap hotkey to reach other applications
+    /// right away, by default they are blocked and replayed once the hotkey can no longer
+    /// complete, i.e. when another key is pressed or the tap window ends
+    pub fn pass_through_taps(mut self) -> Self {
+        self.pass_through_taps = true;
+        self
+    }
+
+    /// Makes the hotkey change the active layers when triggered, ex: activate
+    /// a "nav" layer while [CAPSLOCK] is held
+    pub fn layer_action(mut self, action: LayerAction) -> Self {
+        self.layer_action = Some(action);
+        self
+    }
+
+    /// Restricts the hotkey to the foreground windows in the given scope, ex: only in
+    /// `code.exe`. Scoped hotkeys take precedence over the unscoped ones with the same keys.
+    pub fn scope(mut self, scope: Scope) -> Self {
+        self.scope = Some(scope);
+        self
+    }
+
+    /// Adds a condition that must be met for the hotkey to be eligible, ex: only while
+    /// [CAPSLOCK] is on. When not met the key event is handled as if the hotkey was not
+    /// registered. Hotkeys with conditions take precedence over the ones without them.
+    ///
+    /// Conditions are evaluated on the event loop thread while the registrations are
+    /// locked, so they must be fast and must not call the [`crate::HotkeyManager`].
+    pub fn when<F>(mut self, condition: F) -> Self
+    where
+        F: Fn(&HotkeyContext) -> bool + Send + Sync + 'static,
+    {
+        self.conditions.push(Arc::new(condition));
+        self
+    }
+
+    /// Replaces the id of the hotkey by a name chosen by the user, ex: to unregister it
+    /// later without keeping the id returned on registration.
+    pub fn id<I: Into<HotkeyId>>(mut self, id: I) -> Self {
+        self.id = id.into();
+        self
+    }
+
+    /// Sets the priority of the hotkey. When several hotkeys of the same layer are
+    /// triggered by the same keys, only the one with the highest priority is executed.
+    pub fn priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Returns the order in which the hotkeys of a layer are matched: by priority, then
+    /// the ones with a scope or conditions, then the ones with more modifiers and more
+    /// side-specific modifiers, ex: [LCTRL] + [A] before [CTRL] + [A], then the ones
+    /// with more taps.
+    pub(crate) fn precedence(
+        &self,
+    ) -> (
+        Reverse<i32>,
+        bool,
+        Reverse<usize>,
+        Reverse<usize>,
+        Reverse<u8>,
+    ) {
+        let side_specific = self
+            .modifiers
+            .iter()
+            .filter(|key| {
+                key.is_modifier_key() && !matches!(key, VKey::Control | VKey::Shift | VKey::Menu)
+            })
+            .count();
+        (
+            Reverse(self.priority),
+            !self.is_guarded(),
+            Reverse(self.modifiers.len()),
+            Reverse(side_specific),
+            Reverse(self.taps),
+        )
+    }
+
+    /// Returns whether the hotkey is restricted by a scope or conditions.
+    pub(crate) fn is_guarded(&self) -> bool {
+        self.scope.is_some() || !self.conditions.is_empty()
+    }
+
+    /// Returns whether both hotkeys are triggered by the same keys, ex: [CTRL] + [A] and
+    /// [LCTRL] + [A], since the modifiers are matched regardless of their side.
+    pub(crate) fn overlaps(&self, other: &Hotkey) -> bool {
+        let a = self.generate_expected_keyboard_state();
+        let b = other.generate_expected_keyboard_state();
+        let other_keys = |state: &KeyboardState| -> BTreeSet<VKey> {
+            state
+                .pressing
+                .iter()
+                .filter(|key| !key.is_modifier_key())
+                .cloned()
+                .collect()
+        };
+        self.trigger_key == other.trigger_key
+            && self.taps == other.taps
+            && a.is_win_pressed() == b.is_win_pressed()
+            && a.is_menu_pressed() == b.is_menu_pressed()
+            && a.is_shift_pressed() == b.is_shift_pressed()
+            && a.is_control_pressed() == b.is_control_pressed()
+            && other_keys(&a) == other_keys(&b)
+    }
+
+    /// Returns whether this hotkey does something when its trigger key is released.
+    /// Autorepeated presses of these hotkeys don't execute the callback again.
+    pub fn tracks_release(&self) -> bool {
+        self.trigger_on != TriggerOn::Press
+            || self.on_release.is_some()
+            || self
+                .layer_action
+                .as_ref()
+                .is_some_and(LayerAction::tracks_release)
+    }
+
     /// Executes the callback associated with the hotkey, in a separate thread.
     pub fn execute(&self) {
         (self.callback)()
     }
 
+    /// Returns the pending decision of the synchronous callback, if any.
+    pub(crate) fn sync_decision(&self) -> Option<SyncDecision> {
+        Some(SyncDecision {
+            id: self.id.clone(),
+            callback: self.sync_callback.clone()?,
+            budget: self.sync_budget,
+            fallback: self.sync_fallback,
+        })
+    }
+
     /// Checks if current keyboard state should trigger hotkey callback.
     /// This should only be called if the most recent keypress is the
     /// trigger key for the hotkey.
     pub fn is_trigger_state(&self, state: &KeyboardState) -> bool {
-        // For non-modifier keys, verify the last pressed key matches
-        if !self.trigger_key.is_modifier_key() {
-            let Some(last_pressed) = state.pressing.last() else {
-                return false;
-            };
-
-            if *last_pressed != self.trigger_key {
-                return false;
-            }
-        }
-
-        let expected_state = self.generate_expected_keyboard_state();
-
-        // Verify all required non-modifier keys are pressed
-        for key in &expected_state.pressing {
-            if !key.is_modifier_key() && !state.is_down(*key) {
-                return false;
-            }
-        }
-
-        // Verify modifier key states match exactly
-        expected_state.is_win_pressed() == state.is_win_pressed()
-            && expected_state.is_menu_pressed() == state.is_menu_pressed()
-            && expected_state.is_shift_pressed() == state.is_shift_pressed()
-            && expected_state.is_control_pressed() == state.is_control_pressed()
+        is_trigger_state(self.trigger_key, &self.modifiers, state)
     }
 
     /// Generates a `KeyboardState` representing the hotkey.
     pub fn generate_expected_keyboard_state(&self) -> KeyboardState {
-        let mut keyboard_state = KeyboardState::new();
-        keyboard_state.keydown(self.trigger_key);
-        for key in &self.modifiers {
-            keyboard_state.keydown(*key);
-        }
-        keyboard_state
+        expected_keyboard_state(self.trigger_key, &self.modifiers)
+    }
+
+    /// Returns the keys of the hotkey, without its callback.
+    pub fn binding(&self) -> HotkeyBinding {
+        HotkeyBinding::from(self)
+    }
+
+    /// Returns the settings of the hotkey, without its callbacks.
+    pub fn description(&self) -> HotkeyDescription {
+        HotkeyDescription::from(self)
     }
 
     /// Returns a hash representing the hotkey combination
@@ -152,21 +543,121 @@ impl Hotkey {
     }
 }
 
+impl TriggerBehavior {
+    /// Returns how the key event that triggered a hotkey must be handled.
+    pub(crate) fn key_action(&self, state: &KeyboardState) -> KeyAction {
+        match self {
+            TriggerBehavior::PassThrough => KeyAction::Allow,
+            TriggerBehavior::StopPropagation => {
+                if state.is_down(VKey::LWin) {
+                    KeyAction::Replace
+                } else {
+                    KeyAction::Block
+                }
+            }
+        }
+    }
+}
+
+/// Inserts a hotkey after the ones matched before or with the same precedence,
+/// so ties are matched in registration order.
+pub(crate) fn insert(hotkeys: &mut Vec<Hotkey>, hotkey: Hotkey) {
+    let precedence = hotkey.precedence();
+    let index = hotkeys.partition_point(|current| current.precedence() <= precedence);
+    hotkeys.insert(index, hotkey);
+}
+
+/// Checks if a keyboard state matches the given key combination.
+/// This should only be called if the most recent keypress is the trigger key.
+pub(crate) fn is_trigger_state(
+    trigger_key: VKey,
+    modifiers: &BTreeSet<VKey>,
+    state: &KeyboardState,
+) -> bool {
+    // For non-modifier keys, verify the last pressed key matches
+    if !trigger_key.is_modifier_key() {
+        let Some(last_pressed) = state.pressing.last() else {
+            return false;
+        };
+
+        if *last_pressed != trigger_key {
+            return false;
+        }
+    }
+
+    let expected_state = expected_keyboard_state(trigger_key, modifiers);
+
+    // Verify all required non-modifier keys are pressed
+    for key in &expected_state.pressing {
+        if !key.is_modifier_key() && !state.is_down(*key) {
+            return false;
+        }
+    }
+
+    // Verify modifier key states match exactly
+    expected_state.is_win_pressed() == state.is_win_pressed()
+        && expected_state.is_menu_pressed() == state.is_menu_pressed()
+        && expected_state.is_shift_pressed() == state.is_shift_pressed()
+        && expected_state.is_control_pressed() == state.is_control_pressed()
+}
+
+/// Generates a `KeyboardState` representing the given key combination.
+pub(crate) fn expected_keyboard_state(
+    trigger_key: VKey,
+    modifiers: &BTreeSet<VKey>,
+) -> KeyboardState {
+    let mut keyboard_state = KeyboardState::new();
+    keyboard_state.keydown(trigger_key);
+    for key in modifiers {
+        keyboard_state.keydown(*key);
+    }
+    keyboard_state
+}
+
 impl fmt::Debug for Hotkey {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("Hotkey")
+            .field("id", &self.id)
             .field("trigger_key", &self.trigger_key)
             .field("trigger_action", &self.behaviour)
+            .field("trigger_on", &self.trigger_on)
+            .field("taps", &self.taps)
+            .field("priority", &self.priority)
+            .field("enabled", &self.enabled)
+            .field("layer_action", &self.layer_action)
+            .field("scope", &self.scope)
+            .field("conditions", &self.conditions.len())
+            .field(
+                "sync_callback",
+                &self.sync_callback.as_ref().map(|_| "<callback>"),
+            )
             .field("modifiers", &self.modifiers)
             .field("callback", &"<callback>")
             .finish()
     }
 }
 
+/// Displays the keys of the hotkey as an accelerator string, ex: `Ctrl+Shift+A`.
+impl fmt::Display for Hotkey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.binding().fmt(f)
+    }
+}
+
 impl Eq for Hotkey {}
 impl PartialEq for Hotkey {
     fn eq(&self, other: &Self) -> bool {
-        self.trigger_key == other.trigger_key && self.modifiers == other.modifiers
+        self.trigger_key == other.trigger_key
+            && self.modifiers == other.modifiers
+            && self.taps == other.taps
+            && self.scope == other.scope
+            // conditions are only equal to themselves
+            && self.conditions.len() == other.conditions.len()
+            && self
+                .conditions
+                .iter()
+                .zip(&other.conditions)
+                .all(|(a, b)| Arc::ptr_eq(a, b))
     }
 }
 
@@ -174,5 +665,126 @@ impl Hash for Hotkey {
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.trigger_key.hash(state);
         self.modifiers.hash(state);
+        self.taps.hash(state);
+        self.scope.hash(state);
+        for condition in &self.conditions {
+            (Arc::as_ptr(condition) as *const () as usize).hash(state);
+        }
+    }
+}
+
+/// The settings of a hotkey without its callbacks, ex: to persist the bindings chosen by
+/// the user or send them to another process with the `serde` feature.
+///
+/// Scopes, conditions and layer actions are not described, and only the descriptions of
+/// the hotkeys with a named id can be serialized.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HotkeyDescription {
+    /// id of the hotkey
+    pub id: HotkeyId,
+    /// keys of the hotkey
+    pub binding: HotkeyBinding,
+    /// what happens with the key event after the hotkey triggers
+    pub behavior: TriggerBehavior,
+    /// key event(s) on which the callback is executed
+    pub trigger_on: TriggerOn,
+    /// will ignore the `paused` global state
+    pub bypass_pause: bool,
+    /// times the hotkey must be pressed in a row to trigger
+    pub taps: u8,
+    /// precedence over the hotkeys with the same keys
+    pub priority: i32,
+    /// whether the hotkey is matched
+    pub enabled: bool,
+}
+
+impl HotkeyDescription {
+    /// Creates a hotkey with these settings, executing the given callback.
+    pub fn to_hotkey<F>(&self, callback: F) -> Hotkey
+    where
+        F: Fn() + Send + Sync + 'static,
+    {
+        let mut hotkey = self
+            .binding
+            .to_hotkey(callback)
+            .id(self.id.clone())
+            .behavior(self.behavior)
+            .trigger_on(self.trigger_on)
+            .taps(self.taps)
+            .priority(self.priority);
+        hotkey.bypass_pause = self.bypass_pause;
+        hotkey.enabled = self.enabled;
+        hotkey
+    }
+}
+
+impl From<&Hotkey> for HotkeyDescription {
+    fn from(hotkey: &Hotkey) -> Self {
+        Self {
+            id: hotkey.id.clone(),
+            binding: hotkey.binding(),
+            behavior: hotkey.behaviour,
+            trigger_on: hotkey.trigger_on,
+            bypass_pause: hotkey.bypass_pause,
+            taps: hotkey.taps,
+            priority: hotkey.priority,
+            enabled: hotkey.enabled,
+        }
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod serde_tests {
+    use super::*;
+    use serde_test::{assert_de_tokens_error, assert_ser_tokens_error, assert_tokens, Token};
+
+    #[test]
+    fn test_serialize_deserialize_settings() {
+        assert_tokens(
+            &TriggerBehavior::PassThrough,
+            &[Token::UnitVariant {
+                name: "TriggerBehavior",
+                variant: "PassThrough",
+            }],
+        );
+        assert_tokens(
+            &TriggerOn::Release,
+            &[Token::UnitVariant {
+                name: "TriggerOn",
+                variant: "Release",
+            }],
+        );
+        assert_tokens(
+            &HotkeyId::named("editor.save"),
+            &[Token::Str("editor.save")],
+        );
+        assert_ser_tokens_error(
+            &HotkeyId(IdKind::Allocated(7)),
+            &[],
+            "the allocated hotkey id `#7` can't be serialized, only named ones",
+        );
+        assert_de_tokens_error::<HotkeyId>(
+            &[Token::U64(7)],
+            "invalid type: integer `7`, expected a string",
+        );
+    }
+
+    #[test]
+    fn test_hotkey_description_round_trip() {
+        let hotkey = Hotkey::new(VKey::S, [VKey::Control, VKey::Shift], || {})
+            .id("editor.save")
+            .behavior(TriggerBehavior::PassThrough)
+            .taps(2)
+            .priority(-1);
+        let description = hotkey.description();
+        let json = serde_json::to_string(&description).unwrap();
+        assert_eq!(
+            json,
+            r#"{"id":"editor.save","binding":"Ctrl+Shift+S","behavior":"PassThrough","trigger_on":"Press","bypass_pause":false,"taps":2,"priority":-1,"enabled":true}"#
+        );
+        let deserialized: HotkeyDescription = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized, description);
+        assert_eq!(deserialized.to_hotkey(|| {}).description(), description);
     }
 }
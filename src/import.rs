@@ -0,0 +1,484 @@
+//! This module defines the parsers importing shortcuts from the notations of other tools
+//! as a [`HotkeyBinding`], ex: to migrate the settings of an application:
+//!
+//! - [`HotkeyBinding::from_electron`] for the Electron and Tauri accelerators,
+//!   ex: `CommandOrControl+Shift+Z`.
+//! - [`HotkeyBinding::from_autohotkey`] for the AutoHotkey hotkeys, ex: `~^!#a`.
+//! - [`HotkeyBinding::from_mod_flags`] for the `MOD_*` flags and virtual-key code pairs
+//!   given to `RegisterHotKey`.
+//!
+//! Punctuation keys are read for the US layout, characters typed with [SHIFT] like `?`
+//! are rejected as they depend on the keyboard layout.
+
+use crate::binding::{self, BindingErrorKind, HotkeyBinding};
+use crate::error::{Result, WHKError};
+use crate::hotkey::{Hotkey, TriggerBehavior, TriggerOn};
+use crate::VKey;
+
+const MOD_ALT: u32 = 0x0001;
+const MOD_CONTROL: u32 = 0x0002;
+const MOD_SHIFT: u32 = 0x0004;
+const MOD_WIN: u32 = 0x0008;
+const MOD_NOREPEAT: u32 = 0x4000;
+
+/// A hotkey imported from the AutoHotkey syntax, along with the behavior set by its
+/// prefix and suffix.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AutoHotkeyBinding {
+    /// keys of the hotkey
+    pub binding: HotkeyBinding,
+    /// `PassThrough` with the `~` prefix
+    pub behavior: TriggerBehavior,
+    /// `Release` with the ` up` suffix
+    pub trigger_on: TriggerOn,
+}
+
+impl AutoHotkeyBinding {
+    /// Creates a hotkey with these keys and behavior, executing the given callback.
+    pub fn to_hotkey<F>(&self, callback: F) -> Hotkey
+    where
+        F: Fn() + Send + Sync + 'static,
+    {
+        self.binding
+            .to_hotkey(callback)
+            .behavior(self.behavior)
+            .trigger_on(self.trigger_on)
+    }
+}
+
+impl HotkeyBinding {
+    /// Parses an Electron or Tauri accelerator, ex: `CommandOrControl+Shift+Z`.
+    ///
+    /// `Command`, `Cmd`, `Super` and `Meta` are [WIN], `CommandOrControl` and `CmdOrCtrl`
+    /// are [CTRL], `Option` is [ALT] and `AltGr` is [RALT]. Only modifiers can be placed
+    /// before the last key.
+    pub fn from_electron(accelerator: &str) -> Result<Self> {
+        let keys = binding::parse_keys(accelerator, &['+'], electron_key)?;
+        let (_, modifiers) = keys.split_last().ok_or(WHKError::InvalidBinding {
+            input: accelerator.to_string(),
+            position: 0,
+            kind: BindingErrorKind::MissingKey,
+        })?;
+        if let Some((position, key)) = modifiers.iter().find(|(_, key)| !key.is_modifier_key()) {
+            return Err(WHKError::InvalidBinding {
+                input: accelerator.to_string(),
+                position: *position,
+                kind: BindingErrorKind::ExpectedModifier(*key),
+            });
+        }
+        Ok(Self::from_keys(keys.into_iter().map(|(_, key)| key)))
+    }
+
+    /// Parses an AutoHotkey hotkey, ex: `^!#a`, `<^>!x` or `Numpad0 & Numpad1`.
+    ///
+    /// The modifier symbols `#`, `!`, `^` and `+` can be restricted to one side with `<`
+    /// and `>`, `~` lets the key event through and `$` is ignored as all the hotkeys use
+    /// the keyboard hook. A trailing ` up` triggers the hotkey on release, and a trailing
+    /// `::` is ignored. The `*` wildcard, scan codes and mouse buttons are not supported.
+    pub fn from_autohotkey(hotkey: &str) -> Result<AutoHotkeyBinding> {
+        let error = |position, kind| WHKError::InvalidBinding {
+            input: hotkey.to_string(),
+            position,
+            kind,
+        };
+
+        let mut text = hotkey.trim_end();
+        text = text.strip_suffix("::").unwrap_or(text).trim_end();
+        let mut trigger_on = TriggerOn::Press;
+        let suffix = text
+            .len()
+            .checked_sub(3)
+            .and_then(|start| text.get(start..));
+        if text.len() > 3 && suffix.is_some_and(|suffix| suffix.eq_ignore_ascii_case(" up")) {
+            trigger_on = TriggerOn::Release;
+            text = text[..text.len() - 3].trim_end();
+        }
+
+        let mut behavior = TriggerBehavior::StopPropagation;
+        let mut modifiers = Vec::new();
+        let mut side = None;
+        let mut position = text.len() - text.trim_start().len();
+        // the last character is always the key, ex: `^=` is [CTRL] + [=]
+        while let Some(c) = text[position..].chars().next() {
+            if text.len() - position == c.len_utf8() {
+                break;
+            }
+            let modifier = match c {
+                '~' => {
+                    behavior = TriggerBehavior::PassThrough;
+                    None
+                }
+                '$' => None,
+                '*' => {
+                    let construct = "wildcard prefix `*`".to_string();
+                    return Err(error(position, BindingErrorKind::Unsupported(construct)));
+                }
+                '<' | '>' if side.is_none() => {
+                    side = Some((position, c));
+                    None
+                }
+                '#' | '!' | '^' | '+' => Some(ahk_modifier(c, side.take().map(|(_, side)| side))),
+                _ => break,
+            };
+            if let Some(key) = modifier {
+                if modifiers.contains(&key) {
+                    return Err(error(position, BindingErrorKind::DuplicateKey(key)));
+                }
+                modifiers.push(key);
+            }
+            position += c.len_utf8();
+        }
+        if let Some((position, side)) = side {
+            let construct = format!("side prefix `{side}` without modifier symbol");
+            return Err(error(position, BindingErrorKind::Unsupported(construct)));
+        }
+
+        let rest = &text[position..];
+        let names = match rest.find('&').filter(|_| rest.trim() != "&") {
+            // custom combination, ex: `Numpad0 & Numpad1`
+            Some(index) => {
+                let construct = if !modifiers.is_empty() {
+                    Some("modifier symbols in a custom combination")
+                } else if rest[index + 1..].contains('&') {
+                    Some("custom combination of more than two keys")
+                } else {
+                    None
+                };
+                if let Some(construct) = construct {
+                    let kind = BindingErrorKind::Unsupported(construct.to_string());
+                    return Err(error(position, kind));
+                }
+                vec![
+                    (position, &rest[..index]),
+                    (position + index + 1, &rest[index + 1..]),
+                ]
+            }
+            None => vec![(position, rest)],
+        };
+        for (position, name) in names {
+            let position = position + name.len() - name.trim_start().len();
+            let name = name.trim();
+            if name.is_empty() {
+                return Err(error(position, BindingErrorKind::MissingKey));
+            }
+            let key = ahk_key(name).map_err(|kind| error(position, kind))?;
+            if modifiers.contains(&key) {
+                return Err(error(position, BindingErrorKind::DuplicateKey(key)));
+            }
+            modifiers.push(key);
+        }
+
+        Ok(AutoHotkeyBinding {
+            binding: HotkeyBinding::from_keys(modifiers),
+            behavior,
+            trigger_on,
+        })
+    }
+
+    /// Creates a `HotkeyBinding` from the `MOD_*` flags and virtual-key code given to
+    /// `RegisterHotKey`, ex: `MOD_CONTROL | MOD_SHIFT` and `0x41` for [CTRL] + [SHIFT] + [A].
+    ///
+    /// `MOD_NOREPEAT` is ignored. The input reported by the errors is `"{modifiers}, {vk}"`
+    /// in hexadecimal.
+    pub fn from_mod_flags(modifiers: u32, vk: u32) -> Result<Self> {
+        let input = format!("{modifiers:#06X}, {vk:#04X}");
+        let vk_position = input.find(", ").unwrap() + 2;
+        let error = |position, kind| WHKError::InvalidBinding {
+            input: input.clone(),
+            position,
+            kind,
+        };
+
+        let unknown = modifiers & !(MOD_ALT | MOD_CONTROL | MOD_SHIFT | MOD_WIN | MOD_NOREPEAT);
+        if unknown != 0 {
+            let construct = format!("modifier flags {unknown:#X}");
+            return Err(error(0, BindingErrorKind::Unsupported(construct)));
+        }
+        let trigger_key = match u16::try_from(vk) {
+            Ok(vk @ 0x01..=0xFE) => VKey::from_vk_code(vk),
+            _ => {
+                let kind = BindingErrorKind::UnknownKey(format!("{vk:#X}"));
+                return Err(error(vk_position, kind));
+            }
+        };
+
+        let flags = [
+            (MOD_CONTROL, VKey::Control),
+            (MOD_ALT, VKey::Menu),
+            (MOD_SHIFT, VKey::Shift),
+            (MOD_WIN, VKey::LWin),
+        ];
+        let keys: Vec<VKey> = flags
+            .iter()
+            .filter(|(flag, _)| modifiers & flag != 0)
+            .map(|(_, key)| *key)
+            .collect();
+        Ok(Self::new(trigger_key, keys))
+    }
+
+    /// Returns the `MOD_*` flags and virtual-key code to give to `RegisterHotKey`,
+    /// `None` if a key before the trigger key is not a modifier.
+    pub fn to_mod_flags(&self) -> Option<(u32, u32)> {
+        let mut flags = 0;
+        for key in &self.modifiers {
+            flags |= if key.is_control_key() {
+                MOD_CONTROL
+            } else if key.is_menu_key() {
+                MOD_ALT
+            } else if key.is_shift_key() {
+                MOD_SHIFT
+            } else if key.is_windows_key() {
+                MOD_WIN
+            } else {
+                return None;
+            };
+        }
+        Some((flags, self.trigger_key.to_vk_code() as u32))
+    }
+}
+
+/// Returns the key of an AutoHotkey modifier symbol, on the given side if any.
+fn ahk_modifier(symbol: char, side: Option<char>) -> VKey {
+    match (symbol, side) {
+        ('#', Some('>')) => VKey::RWin,
+        ('#', _) => VKey::LWin,
+        ('!', Some('<')) => VKey::LMenu,
+        ('!', Some('>')) => VKey::RMenu,
+        ('!', _) => VKey::Menu,
+        ('^', Some('<')) => VKey::LControl,
+        ('^', Some('>')) => VKey::RControl,
+        ('^', _) => VKey::Control,
+        ('+', Some('<')) => VKey::LShift,
+        ('+', Some('>')) => VKey::RShift,
+        _ => VKey::Shift,
+    }
+}
+
+/// Returns the key of an unshifted punctuation character on the US layout.
+fn punctuation_key(name: &str) -> Option<std::result::Result<VKey, BindingErrorKind>> {
+    let mut chars = name.chars();
+    let c = chars.next().filter(|_| chars.next().is_none())?;
+    let key = match c {
+        ';' => VKey::Oem1,
+        '=' => VKey::OemPlus,
+        ',' => VKey::OemComma,
+        '-' => VKey::OemMinus,
+        '.' => VKey::OemPeriod,
+        '/' => VKey::Oem2,
+        '`' => VKey::Oem3,
+        '[' => VKey::Oem4,
+        '\\' => VKey::Oem5,
+        ']' => VKey::Oem6,
+        '\'' => VKey::Oem7,
+        '~' | '!' | '@' | '#' | '$' | '%' | '^' | '&' | '*' | '(' | ')' | '_' | '+' | '{' | '}'
+        | '|' | ':' | '"' | '<' | '>' | '?' => {
+            let construct = format!("shifted character `{c}`");
+            return Some(Err(BindingErrorKind::Unsupported(construct)));
+        }
+        _ => return None,
+    };
+    Some(Ok(key))
+}
+
+/// Returns the key of an Electron key name.
+fn electron_key(name: &str) -> std::result::Result<VKey, BindingErrorKind> {
+    if let Some(key) = punctuation_key(name) {
+        return key;
+    }
+    let key = match name.to_ascii_lowercase().as_str() {
+        "command" | "cmd" | "super" | "meta" => VKey::LWin,
+        "commandorcontrol" | "cmdorctrl" | "control" | "ctrl" => VKey::Control,
+        "alt" | "option" => VKey::Menu,
+        "altgr" => VKey::RMenu,
+        "plus" => VKey::OemPlus,
+        "printscreen" => VKey::Snapshot,
+        "volumeup" => VKey::VolumeUp,
+        "volumedown" => VKey::VolumeDown,
+        "volumemute" => VKey::VolumeMute,
+        "medianexttrack" => VKey::MediaNextTrack,
+        "mediaprevioustrack" => VKey::MediaPrevTrack,
+        "mediastop" => VKey::MediaStop,
+        "mediaplaypause" => VKey::MediaPlayPause,
+        "numdec" => VKey::Decimal,
+        "numadd" => VKey::Add,
+        "numsub" => VKey::Subtract,
+        "nummult" => VKey::Multiply,
+        "numdiv" => VKey::Divide,
+        lower => match lower.strip_prefix("num").and_then(|n| n.parse::<u8>().ok()) {
+            Some(digit @ 0..=9) => VKey::from_vk_code(VKey::Numpad0.to_vk_code() + digit as u16),
+            _ => VKey::from_keyname(name)
+                .map_err(|_| BindingErrorKind::UnknownKey(name.to_string()))?,
+        },
+    };
+    Ok(key)
+}
+
+/// Returns the key of an AutoHotkey key name.
+fn ahk_key(name: &str) -> std::result::Result<VKey, BindingErrorKind> {
+    if let Some(key) = punctuation_key(name) {
+        return key;
+    }
+    let lower = name.to_ascii_lowercase();
+    let unsupported = |construct: &str| {
+        Err(BindingErrorKind::Unsupported(format!(
+            "{construct} `{name}`"
+        )))
+    };
+    let is_hex = |digits: &str| !digits.is_empty() && digits.chars().all(|c| c.is_ascii_hexdigit());
+    if let Some(code) = lower.strip_prefix("vk") {
+        // the scan code of `vkNNscNNN` is not needed
+        let code = code.split("sc").next().unwrap();
+        if is_hex(code) {
+            return u16::from_str_radix(code, 16)
+                .map(VKey::from_vk_code)
+                .map_err(|_| BindingErrorKind::UnknownKey(name.to_string()));
+        }
+    }
+    if lower.strip_prefix("sc").is_some_and(is_hex) {
+        return unsupported("scan code");
+    }
+
+    let key = match lower.as_str() {
+        "lbutton" | "rbutton" | "mbutton" | "xbutton1" | "xbutton2" | "wheelup" | "wheeldown"
+        | "wheelleft" | "wheelright" => return unsupported("mouse button"),
+        "control" => VKey::Control,
+        "bs" => VKey::Back,
+        "del" => VKey::Delete,
+        "ins" => VKey::Insert,
+        "pgup" => VKey::Prior,
+        "pgdn" => VKey::Next,
+        "appskey" => VKey::Apps,
+        "printscreen" => VKey::Snapshot,
+        "numpaddot" => VKey::Decimal,
+        "numpaddiv" => VKey::Divide,
+        "numpadmult" => VKey::Multiply,
+        "numpadadd" => VKey::Add,
+        "numpadsub" => VKey::Subtract,
+        // shares the virtual-key code of Enter, only the extended flag tells them apart
+        "numpadenter" => return unsupported("extended key"),
+        "media_next" => VKey::MediaNextTrack,
+        "media_prev" => VKey::MediaPrevTrack,
+        "launch_media" => VKey::LaunchMediaSelect,
+        // the other names match the virtual-key codes, ex: `Volume_Mute` is `VK_VOLUME_MUTE`
+        _ => {
+            VKey::from_keyname(name).map_err(|_| BindingErrorKind::UnknownKey(name.to_string()))?
+        }
+    };
+    Ok(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error_kind(result: Result<impl std::fmt::Debug>) -> (usize, BindingErrorKind) {
+        match result {
+            Err(WHKError::InvalidBinding { position, kind, .. }) => (position, kind),
+            other => panic!("unexpected result: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_from_electron() {
+        assert_eq!(
+            HotkeyBinding::from_electron("CommandOrControl+Shift+Z").unwrap(),
+            HotkeyBinding::new(VKey::Z, [VKey::Control, VKey::Shift])
+        );
+        assert_eq!(
+            HotkeyBinding::from_electron("Super+Alt+num5").unwrap(),
+            HotkeyBinding::new(VKey::Numpad5, [VKey::LWin, VKey::Menu])
+        );
+        assert_eq!(
+            HotkeyBinding::from_electron("Ctrl+Plus").unwrap(),
+            HotkeyBinding::new(VKey::OemPlus, [VKey::Control])
+        );
+        assert_eq!(
+            HotkeyBinding::from_electron("CmdOrCtrl+/").unwrap(),
+            HotkeyBinding::new(VKey::Oem2, [VKey::Control])
+        );
+        assert_eq!(
+            error_kind(HotkeyBinding::from_electron("Ctrl+A+B")),
+            (5, BindingErrorKind::ExpectedModifier(VKey::A))
+        );
+        assert!(matches!(
+            error_kind(HotkeyBinding::from_electron("Shift+?")),
+            (6, BindingErrorKind::Unsupported(_))
+        ));
+    }
+
+    #[test]
+    fn test_from_autohotkey() {
+        let imported = HotkeyBinding::from_autohotkey("^!#a").unwrap();
+        assert_eq!(
+            imported.binding,
+            HotkeyBinding::new(VKey::A, [VKey::Control, VKey::Menu, VKey::LWin])
+        );
+        assert_eq!(imported.behavior, TriggerBehavior::StopPropagation);
+
+        let imported = HotkeyBinding::from_autohotkey("~<^>!x up::").unwrap();
+        assert_eq!(
+            imported.binding,
+            HotkeyBinding::new(VKey::X, [VKey::LControl, VKey::RMenu])
+        );
+        assert_eq!(imported.behavior, TriggerBehavior::PassThrough);
+        assert_eq!(imported.trigger_on, TriggerOn::Release);
+
+        let imported = HotkeyBinding::from_autohotkey("$Numpad0 & NumpadDot").unwrap();
+        assert_eq!(
+            imported.binding,
+            HotkeyBinding::new(VKey::Decimal, [VKey::Numpad0])
+        );
+        assert_eq!(
+            HotkeyBinding::from_autohotkey("^=").unwrap().binding,
+            HotkeyBinding::new(VKey::OemPlus, [VKey::Control])
+        );
+        assert_eq!(
+            HotkeyBinding::from_autohotkey("+vk41").unwrap().binding,
+            HotkeyBinding::new(VKey::A, [VKey::Shift])
+        );
+
+        assert!(matches!(
+            error_kind(HotkeyBinding::from_autohotkey("~*a")),
+            (1, BindingErrorKind::Unsupported(_))
+        ));
+        assert!(matches!(
+            error_kind(HotkeyBinding::from_autohotkey("^LButton")),
+            (1, BindingErrorKind::Unsupported(_))
+        ));
+        assert!(matches!(
+            error_kind(HotkeyBinding::from_autohotkey("^NumpadEnter")),
+            (1, BindingErrorKind::Unsupported(_))
+        ));
+        assert!(matches!(
+            error_kind(HotkeyBinding::from_autohotkey("<a")),
+            (0, BindingErrorKind::Unsupported(_))
+        ));
+        assert_eq!(
+            error_kind(HotkeyBinding::from_autohotkey("a & Foo")),
+            (4, BindingErrorKind::UnknownKey("Foo".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_mod_flags() {
+        let binding = HotkeyBinding::from_mod_flags(MOD_CONTROL | MOD_SHIFT | MOD_NOREPEAT, 0x41);
+        assert_eq!(
+            binding.unwrap(),
+            HotkeyBinding::new(VKey::A, [VKey::Control, VKey::Shift])
+        );
+        assert_eq!(
+            HotkeyBinding::new(VKey::A, [VKey::RControl, VKey::LWin]).to_mod_flags(),
+            Some((MOD_CONTROL | MOD_WIN, 0x41))
+        );
+        assert_eq!(HotkeyBinding::new(VKey::A, [VKey::B]).to_mod_flags(), None);
+
+        assert!(matches!(
+            error_kind(HotkeyBinding::from_mod_flags(0x10, 0x41)),
+            (0, BindingErrorKind::Unsupported(_))
+        ));
+        assert_eq!(
+            error_kind(HotkeyBinding::from_mod_flags(MOD_ALT, 0x100)),
+            (8, BindingErrorKind::UnknownKey("0x100".to_string()))
+        );
+    }
+}
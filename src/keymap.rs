@@ -0,0 +1,270 @@
+//! This module defines the `Keymap`, a list of hotkeys described by data instead of code,
+//! ex: loaded from a TOML, JSON or RON file with the `serde` feature.
+//!
+//! Each entry names the action it executes, the application registers the callback of
+//! each action once with [`HotkeyManager::register_action`], then the hotkeys are built
+//! and registered by [`HotkeyManager::load_keymap`]:
+//!
+//! ```toml
+//! [[hotkeys]]
+//! keys = "Ctrl+Shift+S"
+//! action = "save-all"
+//!
+//! [[hotkeys]]
+//! keys = "Win+H"
+//! action = "left"
+//! layer = "nav"
+//! behavior = "PassThrough"
+//! bypass_pause = true
+//! ```
+//!
+//! The keys are kept as text until the keymap is loaded, so a typo in one of them is
+//! reported for its entry instead of failing the deserialization of the whole file.
+//!
+//! [`HotkeyManager::register_action`]: crate::HotkeyManager::register_action
+//! [`HotkeyManager::load_keymap`]: crate::HotkeyManager::load_keymap
+
+use crate::binding::HotkeyBinding;
+use crate::error::{Result, WHKError};
+use crate::hotkey::{Hotkey, HotkeyCallback, HotkeyId, TriggerBehavior};
+use crate::layer::DEFAULT_LAYER;
+use crate::reload::KeymapSource;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+/// A list of hotkeys described by data.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Keymap {
+    /// hotkeys in the order they are registered
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub hotkeys: Vec<KeymapEntry>,
+}
+
+impl Keymap {
+    /// Creates a new empty `Keymap`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an entry at the end of the keymap.
+    pub fn entry(mut self, entry: KeymapEntry) -> Self {
+        self.hotkeys.push(entry);
+        self
+    }
+}
+
+/// A hotkey of a [`Keymap`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KeymapEntry {
+    /// accelerator string of the hotkey, see [`HotkeyBinding`]
+    pub keys: String,
+    /// name of the action executed by the hotkey
+    pub action: String,
+    /// id of the hotkey, one is allocated if not set
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub id: Option<String>,
+    /// what happens with the key event after the hotkey triggers
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub behavior: TriggerBehavior,
+    /// will ignore the `paused` global state
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub bypass_pause: bool,
+    /// layer the hotkey is registered on, the default layer if not set
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub layer: Option<String>,
+}
+
+impl KeymapEntry {
+    /// Creates a new `KeymapEntry` executing the action when the keys are pressed.
+    pub fn new<K: Into<String>, A: Into<String>>(keys: K, action: A) -> Self {
+        Self {
+            keys: keys.into(),
+            action: action.into(),
+            id: None,
+            behavior: TriggerBehavior::StopPropagation,
+            bypass_pause: false,
+            layer: None,
+        }
+    }
+
+    /// Sets the id of the hotkey.
+    pub fn id<S: Into<String>>(mut self, id: S) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    /// Sets the behavior of the hotkey.
+    pub fn behavior(mut self, behavior: TriggerBehavior) -> Self {
+        self.behavior = behavior;
+        self
+    }
+
+    /// Triggers the hotkey even when the manager is paused.
+    pub fn bypass_pause(mut self) -> Self {
+        self.bypass_pause = true;
+        self
+    }
+
+    /// Sets the layer the hotkey is registered on.
+    pub fn layer<S: Into<String>>(mut self, layer: S) -> Self {
+        self.layer = Some(layer.into());
+        self
+    }
+
+    /// Returns the layer the hotkey is registered on.
+    pub fn layer_name(&self) -> &str {
+        self.layer.as_deref().unwrap_or(DEFAULT_LAYER)
+    }
+
+    /// Returns whether the entry is an edit of a previous one: they have the same id,
+    /// or neither has one and they execute the same action on the same layer.
+    pub fn replaces(&self, previous: &KeymapEntry) -> bool {
+        match (&self.id, &previous.id) {
+            (Some(id), Some(previous_id)) => id == previous_id,
+            (None, None) => self.action == previous.action && self.layer == previous.layer,
+            _ => false,
+        }
+    }
+
+    /// Builds the hotkey of the entry with the callbacks of the actions.
+    pub(crate) fn to_hotkey(&self, actions: &HashMap<String, HotkeyCallback>) -> Result<Hotkey> {
+        let binding: HotkeyBinding = self.keys.parse()?;
+        let callback = actions
+            .get(&self.action)
+            .cloned()
+            .ok_or_else(|| WHKError::UnknownAction(self.action.clone()))?;
+        let mut hotkey = binding
+            .to_hotkey(move || callback())
+            .behavior(self.behavior);
+        if self.bypass_pause {
+            hotkey = hotkey.bypass_pause();
+        }
+        if let Some(id) = &self.id {
+            hotkey = hotkey.id(id.as_str());
+        }
+        Ok(hotkey)
+    }
+}
+
+/// An entry of a [`Keymap`] which could not be registered.
+#[derive(Debug)]
+pub struct KeymapEntryError {
+    /// index of the entry in the keymap
+    pub index: usize,
+    /// accelerator string of the entry
+    pub keys: String,
+    /// why the entry could not be registered
+    pub error: WHKError,
+}
+
+impl fmt::Display for KeymapEntryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "keymap entry {} `{}`: {}",
+            self.index, self.keys, self.error
+        )
+    }
+}
+
+impl std::error::Error for KeymapEntryError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// Result of [`crate::HotkeyManager::load_keymap`], the entries which could not be
+/// registered don't prevent the others from being registered.
+#[derive(Debug, Default)]
+pub struct KeymapReport {
+    /// ids of the hotkeys registered, in the order of the entries
+    pub registered: Vec<HotkeyId>,
+    /// ids of the hotkeys of the previous keymap kept as they were
+    pub kept: Vec<HotkeyId>,
+    /// ids of the hotkeys of the previous keymap unregistered
+    pub removed: Vec<HotkeyId>,
+    /// entries which could not be registered, in order
+    pub errors: Vec<KeymapEntryError>,
+}
+
+impl KeymapReport {
+    /// Returns whether all the entries were registered.
+    pub fn is_ok(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+/// An entry of the keymap loaded in a manager, with the hotkey registered for it.
+pub(crate) struct LoadedEntry {
+    pub entry: KeymapEntry,
+    pub id: HotkeyId,
+    /// callback of the action when the entry was loaded
+    pub callback: HotkeyCallback,
+}
+
+/// The keymap loaded in a manager and where it comes from.
+#[derive(Default)]
+pub(crate) struct LoadedKeymap {
+    pub source: Option<Arc<dyn KeymapSource>>,
+    pub entries: Vec<LoadedEntry>,
+}
+
+impl LoadedKeymap {
+    /// Takes the loaded entry equal to the given one, if its action is still the same.
+    pub fn take_unchanged(
+        &mut self,
+        entry: &KeymapEntry,
+        actions: &HashMap<String, HotkeyCallback>,
+    ) -> Option<LoadedEntry> {
+        let index = self.entries.iter().position(|loaded| {
+            loaded.entry == *entry
+                && actions
+                    .get(&entry.action)
+                    .is_some_and(|callback| Arc::ptr_eq(callback, &loaded.callback))
+        })?;
+        Some(self.entries.remove(index))
+    }
+}
+
+impl fmt::Debug for LoadedKeymap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let entries: Vec<_> = self.entries.iter().map(|loaded| &loaded.entry).collect();
+        f.debug_struct("LoadedKeymap")
+            .field("source", &self.source.as_ref().map(|_| "<source>"))
+            .field("entries", &entries)
+            .finish()
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod serde_tests {
+    use super::*;
+
+    #[test]
+    fn test_deserialize_keymap() {
+        let keymap: Keymap = serde_json::from_str(
+            r#"{"hotkeys": [
+                {"keys": "Ctrl+S", "action": "save"},
+                {"keys": "Win+H", "action": "left", "id": "nav.left", "layer": "nav",
+                 "behavior": "PassThrough", "bypass_pause": true}
+            ]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            keymap,
+            Keymap::new()
+                .entry(KeymapEntry::new("Ctrl+S", "save"))
+                .entry(
+                    KeymapEntry::new("Win+H", "left")
+                        .id("nav.left")
+                        .layer("nav")
+                        .behavior(TriggerBehavior::PassThrough)
+                        .bypass_pause()
+                )
+        );
+        assert_eq!(serde_json::from_str::<Keymap>("{}").unwrap(), Keymap::new());
+    }
+}
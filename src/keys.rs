@@ -1,9 +1,11 @@
 use crate::error::WHKError;
+use crate::vk_codes::*;
 use num_enum::{FromPrimitive, IntoPrimitive};
 use std::{collections::HashMap, hash::Hash, sync::LazyLock};
-use windows::Win32::UI::Input::KeyboardAndMouse::*;
 
 macro_rules! vkeys_definition {
+    (@display_name $name:ident) => { stringify!($name) };
+    (@display_name $name:ident $alias:literal $(, $rest:literal)*) => { $alias };
     ($($name:ident = $value:ident $(aliases [$($alias:literal),*])? $(const $cName:ident)? ,)*) => {
         /// Represents a virtual key (VK) code.
         ///
@@ -17,10 +19,10 @@ macro_rules! vkeys_definition {
         #[repr(u16)]
         pub enum VKey {
             $(
-                $name = $value.0,
+                $name = $value,
             )*
             /// the scan code of the key has no mapping
-            None = VK__none_.0,
+            None = VK__none_,
             #[num_enum(catch_all)]
             UnknownOrReserved(u16),
         }
@@ -52,6 +54,18 @@ macro_rules! vkeys_definition {
                 }
             }
 
+            /// Returns the most common name of the key, its first alias if any,
+            /// ex: `Ctrl` for [`VKey::Control`]. It is accepted by [`VKey::from_keyname`].
+            pub fn display_name(&self) -> String {
+                match self {
+                    $(
+                        VKey::$name => vkeys_definition!(@display_name $name $($($alias),*)?).to_owned(),
+                    )*
+                    VKey::None => "0xFF".to_owned(),
+                    VKey::UnknownOrReserved(key) => format!("0x{:X}", key),
+                }
+            }
+
             fn try_from_aliases(alias: &str) -> Option<Self> {
                 VKEY_ALIASES_MAP.get(&alias.to_ascii_lowercase()).copied()
             }
@@ -91,6 +105,16 @@ macro_rules! vkeys_definition {
                 Ok(key)
             }
         }
+
+        #[cfg(all(test, windows))]
+        #[test]
+        fn test_vk_codes_match_windows() {
+            use windows::Win32::UI::Input::KeyboardAndMouse as win;
+            $(
+                assert_eq!(VKey::$name.to_vk_code(), win::$value.0, stringify!($value));
+            )*
+            assert_eq!(VKey::None.to_vk_code(), win::VK__none_.0);
+        }
     };
 }
 
@@ -422,10 +446,10 @@ mod tests {
 
     #[test]
     fn test_to_vk_code() {
-        assert_eq!(VKey::Back.to_vk_code(), VK_BACK.0);
-        assert_eq!(VKey::Return.to_vk_code(), VK_RETURN.0);
-        assert_eq!(VKey::Space.to_vk_code(), VK_SPACE.0);
-        assert_eq!(VKey::F12.to_vk_code(), VK_F12.0);
+        assert_eq!(VKey::Back.to_vk_code(), VK_BACK);
+        assert_eq!(VKey::Return.to_vk_code(), VK_RETURN);
+        assert_eq!(VKey::Space.to_vk_code(), VK_SPACE);
+        assert_eq!(VKey::F12.to_vk_code(), VK_F12);
         assert_eq!(VKey::UnknownOrReserved(1234).to_vk_code(), 1234); // Unknown key
     }
 
@@ -457,6 +481,18 @@ mod tests {
         assert_eq!(VKey::UnknownOrReserved(1234).to_string(), "0x4D2");
     }
 
+    #[test]
+    fn test_display_name() {
+        assert_eq!(VKey::Return.display_name(), "Enter");
+        assert_eq!(VKey::Control.display_name(), "Ctrl");
+        assert_eq!(VKey::Shift.display_name(), "Shift");
+        assert_eq!(VKey::UnknownOrReserved(1234).display_name(), "0x4D2");
+        assert_eq!(
+            VKey::from_keyname(&VKey::LWin.display_name()).unwrap(),
+            VKey::LWin
+        );
+    }
+
     #[test]
     fn test_from_str() {
         use std::str::FromStr;
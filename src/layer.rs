@@ -0,0 +1,144 @@
+//! This module defines the layers of hotkeys, named sets of hotkeys that can be
+//! activated on top of each other, ex: a "nav" layer where [H], [J], [K] and [L]
+//! are arrow keys.
+//!
+//! Active layers are kept on a stack, when a key is pressed the hotkeys of the top-most
+//! layer are matched first and the keys without hotkeys on it fall through to the layers
+//! below, down to the default layer which holds the hotkeys registered with
+//! [`crate::HotkeyManager::register_hotkey`] and is always active.
+
+use crate::hotkey::Hotkey;
+use crate::VKey;
+use std::collections::HashMap;
+
+/// Name of the layer holding the hotkeys registered without a layer, always active.
+pub const DEFAULT_LAYER: &str = "default";
+
+/// Defines how a hotkey changes the active layers when triggered.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LayerAction {
+    /// Activates the layer on top of the others
+    Push(String),
+    /// Deactivates the top-most layer
+    Pop,
+    /// Deactivates the layer if active, otherwise activates it on top of the others
+    Toggle(String),
+    /// Activates the layer while the trigger key of the hotkey is held
+    Momentary(String),
+}
+
+impl LayerAction {
+    /// Returns whether this action is undone when the trigger key is released.
+    pub fn tracks_release(&self) -> bool {
+        matches!(self, LayerAction::Momentary(_))
+    }
+}
+
+/// The hotkeys of each layer and the stack of active layers.
+#[derive(Debug, Default)]
+pub(crate) struct Layers {
+    /// hotkeys of each layer, by trigger key, in the order they are matched
+    pub hotkeys: HashMap<String, HashMap<VKey, Vec<Hotkey>>>,
+    /// active layers, from bottom to top
+    pub stack: Vec<String>,
+}
+
+impl Layers {
+    /// Activates a layer on top of the others.
+    pub fn push(&mut self, name: &str) {
+        if name == DEFAULT_LAYER {
+            return;
+        }
+        self.stack.retain(|layer| layer != name);
+        self.stack.push(name.to_string());
+    }
+
+    /// Deactivates the top-most layer.
+    pub fn pop(&mut self) -> Option<String> {
+        self.stack.pop()
+    }
+
+    /// Deactivates a layer wherever it is on the stack.
+    pub fn remove(&mut self, name: &str) -> bool {
+        let len = self.stack.len();
+        self.stack.retain(|layer| layer != name);
+        self.stack.len() != len
+    }
+
+    /// Deactivates the layer if active, otherwise activates it.
+    pub fn toggle(&mut self, name: &str) {
+        if !self.remove(name) {
+            self.push(name);
+        }
+    }
+
+    /// Changes the active layers as defined by the action.
+    pub fn apply(&mut self, action: &LayerAction) {
+        match action {
+            LayerAction::Push(name) | LayerAction::Momentary(name) => self.push(name),
+            LayerAction::Pop => {
+                self.pop();
+            }
+            LayerAction::Toggle(name) => self.toggle(name),
+        }
+    }
+
+    /// Returns the hotkeys of the active layers triggered by the given key,
+    /// from the top-most layer to the bottom one.
+    pub fn active_hotkeys(&self, key: VKey) -> impl Iterator<Item = &Vec<Hotkey>> {
+        self.stack
+            .iter()
+            .rev()
+            .filter_map(move |layer| self.hotkeys.get(layer)?.get(&key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layer_stack() {
+        let mut layers = Layers::default();
+        layers.push("nav");
+        layers.push("symbols");
+        layers.push(DEFAULT_LAYER);
+        assert_eq!(layers.stack, vec!["nav", "symbols"]);
+
+        // pushing an active layer moves it to the top
+        layers.push("nav");
+        assert_eq!(layers.stack, vec!["symbols", "nav"]);
+
+        layers.toggle("symbols");
+        assert_eq!(layers.stack, vec!["nav"]);
+        layers.apply(&LayerAction::Toggle("symbols".into()));
+        assert_eq!(layers.stack, vec!["nav", "symbols"]);
+
+        assert_eq!(layers.pop().as_deref(), Some("symbols"));
+        layers.apply(&LayerAction::Pop);
+        assert!(layers.stack.is_empty());
+        assert_eq!(layers.pop(), None);
+    }
+
+    #[test]
+    fn test_top_most_layer_first() {
+        let mut layers = Layers::default();
+        for name in ["nav", "vim-normal"] {
+            let hotkey = Hotkey::new(VKey::H, [], || {});
+            layers
+                .hotkeys
+                .entry(name.to_string())
+                .or_default()
+                .insert(VKey::H, vec![hotkey]);
+        }
+
+        assert_eq!(layers.active_hotkeys(VKey::H).count(), 0);
+        layers.push("vim-normal");
+        layers.push("nav");
+        let nav = &layers.hotkeys["nav"][&VKey::H];
+        let first = layers.active_hotkeys(VKey::H).next().unwrap();
+        assert!(std::ptr::eq(first, nav));
+        assert_eq!(layers.active_hotkeys(VKey::H).count(), 2);
+        assert_eq!(layers.active_hotkeys(VKey::J).count(), 0);
+    }
+}
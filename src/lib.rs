@@ -3,18 +3,55 @@
 //! Win-hotkeys is a Rust library for creating and managing global hotkeys on Windows.
 //! It provides an ergonomic API for setting up keyboard hooks, registering hotkeys,
 //! and handling keyboard events in a safe and efficient manner.
-#![cfg(windows)]
+//!
+//! The engine itself is platform-neutral: keyboard input is provided by an
+//! [`backend::InputBackend`], which is the low-level keyboard hook on Windows.
 
+pub mod backend;
+mod binding;
+mod capture;
 mod client_executor;
+mod condition;
+mod conflict;
+mod dual_role;
 pub mod error;
 pub mod events;
+mod filter;
+mod handle;
+#[cfg(windows)]
 pub mod hook;
 mod hotkey;
+mod import;
+mod keymap;
 mod keys;
+mod layer;
 mod manager;
+mod recorder;
+mod reload;
+mod remap;
+mod scope;
+mod sender;
+mod sequence;
 pub mod state;
 mod utils;
+mod vk_codes;
 
+pub use binding::*;
+pub use capture::*;
+pub use condition::*;
+pub use conflict::*;
+pub use dual_role::*;
+pub use filter::*;
+pub use handle::*;
 pub use hotkey::*;
+pub use import::*;
+pub use keymap::*;
 pub use keys::*;
+pub use layer::*;
 pub use manager::*;
+pub use recorder::*;
+pub use reload::*;
+pub use remap::*;
+pub use scope::*;
+pub use sender::*;
+pub use sequence::*;
@@ -2,224 +2,1868 @@
 //! unregistration, and execution of hotkeys. It also handles the main event
 //! loop that listens for keyboard events and invokes associated callbacks.
 
-use arc_swap::ArcSwapOption;
+use arc_swap::{ArcSwap, ArcSwapOption};
 
+use crate::backend::{self, InputBackend, KeyInput, RESPONSE_MARGIN};
+use crate::capture::{ActiveCapture, CaptureEnd, CaptureHandle, CaptureSession, CaptureStep};
 use crate::client_executor::{self, run_on_executor_thread};
+use crate::condition::HotkeyContext;
+use crate::conflict::{self, ConflictKind, ConflictPolicy, HotkeyConflict};
+use crate::dual_role::{DualRoleKey, DualRoleMatcher, DualRoleStep, Resolution};
 use crate::error::WHKError::HotKeyAlreadyRegistered;
 use crate::error::{Result, WHKError};
-use crate::events::{EventLoopEvent, KeyAction, KeyboardInputEvent};
-use crate::hotkey::{Hotkey, TriggerBehavior};
-use crate::state::KEYBOARD_STATE;
+use crate::events::{EventLoopEvent, KeyAction, KeyEventInfo, KeyboardInputEvent};
+use crate::filter::{self, FilterResult, KeyFilter};
+use crate::handle::HotkeyHandle;
+use crate::hotkey::{
+    self, Hotkey, HotkeyCallback, HotkeyId, Propagation, SyncDecision, TriggerBehavior, TriggerOn,
+    MAX_SYNC_BUDGET,
+};
+use crate::keymap::{Keymap, KeymapEntryError, KeymapReport, LoadedEntry, LoadedKeymap};
+use crate::layer::{LayerAction, Layers, DEFAULT_LAYER};
+use crate::log_on_dev;
+use crate::recorder::{self, RecordOptions, RecordedShortcut};
+use crate::reload::{KeymapSource, KeymapWatcher};
+use crate::remap::{Remap, RemapMatcher};
+use crate::sequence::{self, HotkeySequence, SequenceMatcher, SequenceStep};
+use crate::state::{KeyboardState, KEYBOARD_STATE};
 use crate::VKey;
-use crate::{hook, log_on_dev};
+use crossbeam_channel::{Receiver, RecvTimeoutError};
+use std::cmp::Reverse;
 use std::collections::{HashMap, HashSet};
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::{Arc, LazyLock, Mutex};
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicI32, AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock, Mutex, Weak};
+use std::time::{Duration, Instant, SystemTime};
 
-type HotkeysMap = Arc<Mutex<HashMap<VKey, HashSet<Hotkey>>>>;
+type HotkeysTable = HashMap<VKey, Vec<Hotkey>>;
+type HotkeysMap = Arc<Mutex<HotkeysTable>>;
+type SequencesList = Arc<Mutex<Vec<HotkeySequence>>>;
+type PressedHotkeysList = Arc<Mutex<Vec<PressedHotkey>>>;
+type DualRoleKeysMap = Arc<Mutex<HashMap<VKey, DualRoleKey>>>;
+type LayersMap = Arc<Mutex<Layers>>;
+type RemapsMap = Arc<Mutex<HashMap<VKey, Vec<Remap>>>>;
+type TapCountersMap = Arc<Mutex<HashMap<HotkeyId, TapCounter>>>;
+type ActionsMap = Arc<Mutex<HashMap<String, HotkeyCallback>>>;
 type KeyboardCallback = dyn Fn(KeyboardInputEvent) + Send + Sync + 'static;
 type FreeKeyboardCallback = dyn Fn() + Send + Sync + 'static;
+type KeyboardCallbackSlot = Arc<ArcSwapOption<Box<KeyboardCallback>>>;
 
-static HOTKEYS: LazyLock<HotkeysMap> =
-    LazyLock::new(|| Arc::new(Mutex::new(HotkeyManager::get_initial_hotkeys())));
-
-static PAUSED: AtomicBool = AtomicBool::new(false);
-static STEALING: AtomicBool = AtomicBool::new(false);
-
-static CLIENT_KEYBOARD_CALLBACK: ArcSwapOption<Box<KeyboardCallback>> =
-    ArcSwapOption::const_empty();
-static CLIENT_ON_FREE_KEYBOARD_CB: ArcSwapOption<Box<FreeKeyboardCallback>> =
-    ArcSwapOption::const_empty();
+/// the manager returned by [`HotkeyManager::current`], its id is 0 so it comes before
+/// the other managers with the same priority, even the ones created before it
+static CURRENT: LazyLock<HotkeyManager> = LazyLock::new(|| HotkeyManager::with_id(0));
+/// managers receiving the key events, the current one is attached from the start.
+/// Replaced as a whole, so the hook thread reads them without waiting for a lock
+static MANAGERS: LazyLock<ArcSwap<Vec<AttachedManager>>> =
+    LazyLock::new(|| ArcSwap::from_pointee(vec![AttachedManager::new(&CURRENT)]));
+static NEXT_MANAGER_ID: AtomicU64 = AtomicU64::new(1);
+/// identifies the registrations of the hotkeys, sequences and remaps, never 0
+static NEXT_REGISTRATION: AtomicU64 = AtomicU64::new(1);
 
 /// Manages the hotkeys, including their registration, unregistration, and execution.
 ///
 /// The `HotkeyManager` listens for keyboard events and triggers the corresponding
 /// hotkey callbacks when events match registered hotkeys.
-#[derive(Debug, Clone)]
+///
+/// Cloned instances share the same state. [`HotkeyManager::current`] is the default
+/// instance of the process, while [`HotkeyManager::new`] creates independent ones,
+/// all of them fed from the same keyboard hook in order of priority once attached.
+#[derive(Clone)]
 #[allow(dead_code)]
 pub struct HotkeyManager {
+    /// unique id, ties of priority are resolved in creation order
+    id: u64,
+    /// managers with higher priority receive the key events first
+    priority: Arc<AtomicI32>,
     /// stores the registered hotkeys
     hotkeys: HotkeysMap,
+    /// stores the registered hotkey sequences
+    sequences: SequencesList,
+    /// triggered hotkeys waiting for the release of their trigger key
+    pressed_hotkeys: PressedHotkeysList,
+    /// stores the registered dual-role keys
+    dual_role_keys: DualRoleKeysMap,
+    /// taps counted so far for the multi-tap hotkeys, by hotkey id
+    tap_counters: TapCountersMap,
+    /// taps blocked while waiting for a multi-tap hotkey to complete
+    blocked_taps: Arc<Mutex<BlockedTaps>>,
+    /// stores the registered remaps, by source key
+    remaps: RemapsMap,
+    /// stores the hotkeys of each layer and the active layers
+    layers: LayersMap,
+    /// the sequence prefix typed so far
+    sequence_matcher: Arc<SequenceMatcher>,
+    /// the dual-role key being pressed
+    dual_role_matcher: Arc<DualRoleMatcher>,
+    /// the remapped keys being held
+    remap_matcher: Arc<RemapMatcher>,
     /// indicates whether the hotkey manager is paused
-    paused: &'static AtomicBool,
-    /// indicates whether the hotkey manager is in stealing mode
-    stealing: &'static AtomicBool,
+    paused: Arc<AtomicBool>,
+    /// the keyboard capture session running, if any
+    capture: Arc<Mutex<Option<ActiveCapture>>>,
+    /// whether a capture session is running, read by the hook thread without locking
+    capturing: Arc<AtomicBool>,
+    /// how key events injected by software are handled
+    injected_policy: Arc<Mutex<InjectedPolicy>>,
+    /// user-defined flags available to the hotkey conditions
+    flags: Arc<Mutex<HashSet<String>>>,
+    /// filters evaluated before the hotkeys, in order of priority
+    filters: Arc<ArcSwap<Vec<KeyFilter>>>,
+    /// how hotkeys conflicting with the registered ones are handled
+    conflict_policy: Arc<Mutex<ConflictPolicy>>,
+    /// callbacks of the keymap actions, by name
+    actions: ActionsMap,
+    /// the keymap loaded and its source
+    keymap: Arc<Mutex<LoadedKeymap>>,
+    /// listener receiving all the keyboard events
+    keyboard_callback: KeyboardCallbackSlot,
+    /// shared by the clones, the manager is detached once all of them are dropped
+    owners: Arc<()>,
+}
+
+impl fmt::Debug for HotkeyManager {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HotkeyManager")
+            .field("id", &self.id)
+            .field("priority", &self.priority)
+            .field("hotkeys", &self.hotkeys)
+            .field("sequences", &self.sequences)
+            .field("dual_role_keys", &self.dual_role_keys)
+            .field("remaps", &self.remaps)
+            .field("layers", &self.layers)
+            .field("paused", &self.paused)
+            .field("capture", &self.capture)
+            .field("injected_policy", &self.injected_policy)
+            .field("flags", &self.flags)
+            .field("filters", &self.filters)
+            .field("conflict_policy", &self.conflict_policy)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for HotkeyManager {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl HotkeyManager {
+    /// Returns the default manager of the process.
     pub fn current() -> HotkeyManager {
+        CURRENT.clone()
+    }
+
+    /// Creates a new manager with its own hotkeys, pause state and keyboard listener,
+    /// independent of the other managers. It doesn't receive the key events until
+    /// [`HotkeyManager::attach`] is called.
+    pub fn new() -> HotkeyManager {
+        HotkeyManager::with_id(NEXT_MANAGER_ID.fetch_add(1, Ordering::Relaxed))
+    }
+
+    fn with_id(id: u64) -> HotkeyManager {
         HotkeyManager {
-            hotkeys: HOTKEYS.clone(),
-            paused: &PAUSED,
-            stealing: &STEALING,
+            id,
+            priority: Default::default(),
+            hotkeys: Arc::new(Mutex::new(HotkeyManager::get_initial_hotkeys())),
+            sequences: Default::default(),
+            pressed_hotkeys: Default::default(),
+            dual_role_keys: Default::default(),
+            tap_counters: Default::default(),
+            blocked_taps: Default::default(),
+            remaps: Default::default(),
+            layers: Default::default(),
+            sequence_matcher: Default::default(),
+            dual_role_matcher: Default::default(),
+            remap_matcher: Default::default(),
+            paused: Default::default(),
+            capture: Default::default(),
+            capturing: Default::default(),
+            injected_policy: Default::default(),
+            flags: Default::default(),
+            filters: Default::default(),
+            conflict_policy: Default::default(),
+            actions: Default::default(),
+            keymap: Default::default(),
+            keyboard_callback: Default::default(),
+            owners: Default::default(),
         }
     }
 
-    /// Returns whether the hotkey manager is in stealing mode.
+    /// Returns the priority of the manager.
+    pub fn priority(&self) -> i32 {
+        self.priority.load(Ordering::SeqCst)
+    }
+
+    /// Sets the priority of the manager. Managers with higher priority receive the
+    /// key events first, a key press handled by one of them doesn't reach the
+    /// managers with lower priority, apart from their global keyboard listener.
+    /// Managers with the same priority receive the events in creation order.
+    pub fn set_priority(&self, priority: i32) {
+        self.priority.store(priority, Ordering::SeqCst);
+    }
+
+    /// Makes the manager receive the key events, until it is detached or all its clones
+    /// are dropped.
+    pub fn attach(&self) {
+        MANAGERS.rcu(|managers| {
+            let mut managers = Vec::clone(managers);
+            if !managers
+                .iter()
+                .any(|attached| attached.manager.id == self.id)
+            {
+                managers.push(AttachedManager::new(self));
+            }
+            managers
+        });
+    }
+
+    /// Stops feeding key events to the manager, its registrations are kept.
+    pub fn detach(&self) {
+        MANAGERS.rcu(|managers| {
+            managers
+                .iter()
+                .filter(|attached| attached.manager.id != self.id)
+                .cloned()
+                .collect::<Vec<_>>()
+        });
+        self.cancel_pending();
+    }
+
+    /// Returns the managers receiving key events, in order of priority.
+    ///
+    /// The managers dropped by their owners are detached, which can send input, so this
+    /// must not be called by the hook thread.
+    fn attached() -> Vec<HotkeyManager> {
+        let previous = MANAGERS.rcu(|managers| {
+            managers
+                .iter()
+                .filter(|attached| !attached.is_dropped())
+                .cloned()
+                .collect::<Vec<_>>()
+        });
+        for attached in previous.iter().filter(|attached| attached.is_dropped()) {
+            attached.manager.cancel_pending();
+        }
+
+        let mut attached: Vec<HotkeyManager> = MANAGERS
+            .load()
+            .iter()
+            .map(|attached| attached.manager.clone())
+            .collect();
+        attached.sort_by_key(|manager| (Reverse(manager.priority()), manager.id));
+        attached
+    }
+
+    /// Returns whether the hotkey manager is in stealing mode, i.e. a capture session is running.
     pub fn is_stealing_mode(&self) -> bool {
-        self.stealing.load(Ordering::SeqCst)
+        self.capturing.load(Ordering::SeqCst)
+    }
+
+    /// Sets the stealing mode for the hotkey manager until the `ESC` key is pressed,
+    /// or client manually frees the keyboard. This is a [`CaptureSession`] with the
+    /// default configuration.
+    pub fn steal_keyboard<F>(&self, on_free: F)
+    where
+        F: Fn() + Send + Sync + 'static,
+    {
+        self.start_capture(CaptureSession::new().on_end(move |_| on_free()));
+    }
+
+    /// Disables the stealing mode for the hotkey manager, ending the running capture session.
+    pub fn free_keyboard(&self) {
+        self.end_capture(None, CaptureEnd::Stopped);
+    }
+
+    /// Starts capturing the keyboard, key presses are blocked and delivered to the
+    /// returned handle instead of triggering hotkeys. A running session is replaced.
+    pub fn start_capture(&self, session: CaptureSession) -> CaptureHandle {
+        log_on_dev!("Keyboard capture started");
+        let (capture, handle) = ActiveCapture::start(session, self.clone());
+        let has_timeout = capture.deadline().is_some();
+        let previous = {
+            let mut current = self.capture.lock().unwrap();
+            self.capturing.store(true, Ordering::SeqCst);
+            current.replace(capture)
+        };
+        if let Some(previous) = previous {
+            previous.finish(CaptureEnd::Stopped);
+        }
+        if has_timeout {
+            EventLoopEvent::Wake.send();
+        }
+        handle
+    }
+
+    /// Records the next valid chord pressed by the user, ex: to let the user choose
+    /// a shortcut. The keyboard is captured until a chord is recorded or the recording
+    /// is cancelled, see [`RecordOptions`].
+    ///
+    /// The returned channel receives a [`RecordedShortcut::Rejected`] for each chord that
+    /// can't be used, then the recorded chord or [`RecordedShortcut::Cancelled`].
+    pub fn record_shortcut(&self, options: RecordOptions) -> Receiver<RecordedShortcut> {
+        let (tx, rx) = crossbeam_channel::unbounded();
+        let capture = self.start_capture(options.capture_session());
+        std::thread::spawn(move || recorder::record(options, capture, tx));
+        rx
+    }
+
+    /// Ends the running capture session, only if it has the given id when provided.
+    pub(crate) fn end_capture(&self, id: Option<u64>, reason: CaptureEnd) {
+        let mut capture = self.capture.lock().unwrap();
+        if capture
+            .as_ref()
+            .is_some_and(|capture| id.is_none_or(|id| capture.id == id))
+        {
+            log_on_dev!("Keyboard capture ended: {reason:?}");
+            self.take_capture(&mut capture).finish(reason);
+        }
+    }
+
+    /// Takes the running capture session out of its slot, it must be running.
+    fn take_capture(&self, capture: &mut Option<ActiveCapture>) -> ActiveCapture {
+        self.capturing.store(false, Ordering::SeqCst);
+        capture.take().unwrap()
+    }
+
+    /// Feeds a key event to the running capture session, returns how it must be
+    /// handled if captured.
+    fn capture_event(&self, event: &KeyboardInputEvent) -> Option<KeyAction> {
+        let mut capture = self.capture.lock().unwrap();
+        match capture.as_ref()?.on_event(event) {
+            CaptureStep::Ignored => None,
+            CaptureStep::Captured(action) => Some(action),
+            CaptureStep::Ended(reason, action) => {
+                log_on_dev!("Keyboard capture ended: {reason:?}");
+                self.take_capture(&mut capture).finish(reason);
+                action
+            }
+        }
+    }
+
+    /// Returns how key events injected by software are handled.
+    pub fn injected_policy(&self) -> InjectedPolicy {
+        *self.injected_policy.lock().unwrap()
+    }
+
+    /// Sets how key events injected by software, ex: by `SendInput`, are handled.
+    /// Events sent by this crate are never processed, regardless of this policy.
+    pub fn set_injected_policy(&self, policy: InjectedPolicy) {
+        *self.injected_policy.lock().unwrap() = policy;
+    }
+
+    /// Sets or clears a user-defined flag, available to the hotkey conditions
+    /// through [`HotkeyContext::flag`].
+    pub fn set_flag(&self, name: &str, value: bool) {
+        let mut flags = self.flags.lock().unwrap();
+        if value {
+            flags.insert(name.to_string());
+        } else {
+            flags.remove(name);
+        }
+    }
+
+    /// Returns whether a user-defined flag is set.
+    pub fn flag(&self, name: &str) -> bool {
+        self.flags.lock().unwrap().contains(name)
+    }
+
+    /// Adds a filter evaluated on every key event before the hotkeys, which can pass,
+    /// block or replace the event, ex: disable the Windows key while gaming.
+    /// Returns the id of the filter.
+    ///
+    /// Filters with higher priority are evaluated first, the first one not passing the
+    /// event decides how it is handled. They run on the event loop thread even when the
+    /// manager is paused, so they must be fast.
+    pub fn add_filter<F>(&self, priority: i32, filter: F) -> u64
+    where
+        F: Fn(&KeyboardInputEvent) -> FilterResult + Send + Sync + 'static,
+    {
+        let filter = KeyFilter::new(priority, filter);
+        let id = filter.id;
+        self.filters.rcu(|filters| {
+            let mut filters = Vec::clone(filters);
+            filter::insert(&mut filters, filter.clone());
+            filters
+        });
+        id
+    }
+
+    /// Removes a filter by its id.
+    pub fn remove_filter(&self, filter_id: u64) {
+        self.filters.rcu(|filters| {
+            filters
+                .iter()
+                .filter(|filter| filter.id != filter_id)
+                .cloned()
+                .collect::<Vec<_>>()
+        });
+    }
+
+    /// Returns how hotkeys conflicting with the registered ones are handled.
+    pub fn conflict_policy(&self) -> ConflictPolicy {
+        self.conflict_policy.lock().unwrap().clone()
+    }
+
+    /// Sets how a hotkey is handled when registered with the same keys as a hotkey of
+    /// the same layer, neither of them having a scope or conditions.
+    /// The hotkeys already registered are not checked again, see [`HotkeyManager::conflicts`].
+    pub fn set_conflict_policy(&self, policy: ConflictPolicy) {
+        *self.conflict_policy.lock().unwrap() = policy;
+    }
+
+    /// Returns the registered hotkeys triggered by the same keys, including the system
+    /// hotkeys, see [`ConflictKind`].
+    pub fn conflicts(&self) -> Vec<HotkeyConflict> {
+        let layers = self.layers.lock().unwrap();
+        let hotkeys = self.hotkeys.lock().unwrap();
+        let layer_hotkeys = layers.hotkeys.iter().flat_map(|(layer, hotkeys)| {
+            hotkeys
+                .values()
+                .flatten()
+                .map(move |hotkey| (layer.as_str(), hotkey))
+        });
+        conflict::find_conflicts(
+            hotkeys
+                .values()
+                .flatten()
+                .map(|hotkey| (DEFAULT_LAYER, hotkey))
+                .chain(layer_hotkeys),
+        )
+    }
+
+    /// Registers a new hotkey, returns its id.
+    pub fn register_hotkey(&self, hotkey: Hotkey) -> Result<HotkeyId> {
+        self.register_layer_hotkey(DEFAULT_LAYER, hotkey)
+    }
+
+    /// Registers a new hotkey on a layer, it is only matched while the layer is active.
+    pub fn register_layer_hotkey(&self, layer: &str, hotkey: Hotkey) -> Result<HotkeyId> {
+        self.insert_hotkey(layer, hotkey).map(|(id, _)| id)
+    }
+
+    /// Registers a hotkey on a layer, returns its id and its registration.
+    fn insert_hotkey(&self, layer: &str, mut hotkey: Hotkey) -> Result<(HotkeyId, u64)> {
+        let id = hotkey.id.clone();
+        let registration = NEXT_REGISTRATION.fetch_add(1, Ordering::Relaxed);
+        hotkey.registration = registration;
+        let warnings = {
+            let mut layers = self.layers.lock()?;
+            let mut hotkeys = self.hotkeys.lock()?;
+            let warnings = self.check_hotkey(&mut layers, &mut hotkeys, layer, &hotkey)?;
+            let table = layer_table(&mut layers, &mut hotkeys, layer);
+            hotkey::insert(table.entry(hotkey.trigger_key).or_default(), hotkey);
+            warnings
+        };
+        // reported once unlocked, so the callback can use the manager
+        warnings();
+        Ok((id, registration))
+    }
+
+    /// Registers a new hotkey, returns a handle unregistering it when dropped.
+    pub fn register_hotkey_handle(&self, hotkey: Hotkey) -> Result<HotkeyHandle> {
+        self.register_layer_hotkey_handle(DEFAULT_LAYER, hotkey)
+    }
+
+    /// Registers a new hotkey on a layer, returns a handle unregistering it when dropped.
+    pub fn register_layer_hotkey_handle(
+        &self,
+        layer: &str,
+        hotkey: Hotkey,
+    ) -> Result<HotkeyHandle> {
+        let (id, registration) = self.insert_hotkey(layer, hotkey)?;
+        Ok(HotkeyHandle::new(id, registration, self.clone()))
+    }
+
+    /// Checks whether a hotkey can be registered on a layer, applying the conflict policy.
+    /// Returns the warnings to report once the hotkey is registered.
+    fn check_hotkey(
+        &self,
+        layers: &mut Layers,
+        hotkeys: &mut HotkeysTable,
+        layer: &str,
+        hotkey: &Hotkey,
+    ) -> Result<impl FnOnce()> {
+        if hotkey.trigger_key == VKey::None {
+            return Err(WHKError::HotkeyInvalidTriggerKey(hotkey.trigger_key));
+        }
+        if hotkey.sync_callback.is_some() && hotkey.trigger_on != TriggerOn::Press {
+            return Err(WHKError::SyncActionNotOnPress);
+        }
+        if find_hotkey(layers, hotkeys, &hotkey.id, None).is_some() {
+            return Err(WHKError::HotkeyIdInUse(hotkey.id.clone()));
+        }
+        let registered = layer_table(layers, hotkeys, layer)
+            .get(&hotkey.trigger_key)
+            .map_or(&[][..], Vec::as_slice);
+        if registered.contains(hotkey) {
+            return Err(HotKeyAlreadyRegistered);
+        }
+        self.check_conflicts(layer, hotkey, registered)
+    }
+
+    /// Applies the conflict policy to a hotkey about to be registered on a layer,
+    /// given the hotkeys of the layer with the same trigger key. Returns the warnings
+    /// to report once the hotkey is registered.
+    fn check_conflicts(
+        &self,
+        layer: &str,
+        hotkey: &Hotkey,
+        registered: &[Hotkey],
+    ) -> Result<impl FnOnce()> {
+        let policy = self.conflict_policy.lock()?.clone();
+        let mut conflicts = registered
+            .iter()
+            .filter_map(|registered| {
+                conflict::conflict_between((layer, hotkey), (layer, registered))
+            })
+            .filter(|conflict| conflict.kind == ConflictKind::Ambiguous);
+
+        let mut warnings = Vec::new();
+        match policy {
+            ConflictPolicy::Allow => {}
+            ConflictPolicy::Warn(callback) => {
+                warnings.extend(conflicts.map(|conflict| (callback.clone(), conflict)))
+            }
+            ConflictPolicy::Reject => {
+                if let Some(conflict) = conflicts.next() {
+                    return Err(WHKError::HotkeyConflict(Box::new(conflict)));
+                }
+            }
+        }
+        Ok(move || {
+            for (callback, conflict) in warnings {
+                callback(&conflict);
+            }
+        })
+    }
+
+    /// Registers the callback of a keymap action, replacing the previous one with
+    /// the same name. Only the hotkeys loaded afterwards execute it.
+    pub fn register_action<F>(&self, name: &str, callback: F) -> Result<()>
+    where
+        F: Fn() + Send + Sync + 'static,
+    {
+        let callback: HotkeyCallback = Arc::new(Box::new(callback));
+        self.actions.lock()?.insert(name.to_string(), callback);
+        Ok(())
+    }
+
+    /// Unregisters the callback of a keymap action.
+    pub fn unregister_action(&self, name: &str) -> Result<()> {
+        self.actions.lock()?.remove(name);
+        Ok(())
+    }
+
+    /// Registers the hotkeys of a keymap, executing the actions registered with
+    /// [`HotkeyManager::register_action`], in place of the hotkeys of the keymap loaded
+    /// before. The entries unchanged since then keep their hotkey, along with its state.
+    ///
+    /// Each entry is registered on its own: an entry with invalid keys, an unknown
+    /// action or rejected by the manager is reported without preventing the others
+    /// from being registered, and the hotkey of the previous entry it replaces stays
+    /// registered, see [`KeymapEntry::replaces`](crate::KeymapEntry::replaces). The
+    /// hotkeys are swapped while the registry is locked, so each key event is matched
+    /// against either the previous keymap or the new one.
+    pub fn load_keymap(&self, keymap: &Keymap) -> Result<KeymapReport> {
+        let actions = self.actions.lock()?.clone();
+        let mut loaded = self.keymap.lock()?;
+        let mut report = KeymapReport::default();
+        let mut entries = Vec::new();
+        let warnings = {
+            let mut layers = self.layers.lock()?;
+            let mut hotkeys = self.hotkeys.lock()?;
+            let mut added = Vec::new();
+            for (index, entry) in keymap.hotkeys.iter().enumerate() {
+                match loaded.take_unchanged(entry, &actions) {
+                    Some(kept)
+                        if find_hotkey(&mut layers, &mut hotkeys, &kept.id, None).is_some() =>
+                    {
+                        report.kept.push(kept.id.clone());
+                        entries.push(kept);
+                    }
+                    // built before the registry changes
+                    _ => added.push((index, entry, entry.to_hotkey(&actions))),
+                }
+            }
+            // taken out first, so the changed entries can reuse their id
+            let mut previous = Vec::new();
+            for entry in loaded.entries.drain(..) {
+                if let Some((layer, _, hotkey)) =
+                    remove_hotkey(&mut layers, &mut hotkeys, &entry.id, None)
+                {
+                    previous.push((entry, layer, hotkey));
+                }
+            }
+
+            let mut warnings = Vec::new();
+            let mut restored = Vec::new();
+            for (index, entry, hotkey) in added {
+                let layer = entry.layer_name();
+                let checked = hotkey.and_then(|hotkey| {
+                    warnings.push(self.check_hotkey(&mut layers, &mut hotkeys, layer, &hotkey)?);
+                    Ok(hotkey)
+                });
+                match checked {
+                    Ok(hotkey) => {
+                        report.registered.push(hotkey.id.clone());
+                        entries.push(LoadedEntry {
+                            entry: entry.clone(),
+                            id: hotkey.id.clone(),
+                            callback: actions[&entry.action].clone(),
+                        });
+                        let table = layer_table(&mut layers, &mut hotkeys, layer);
+                        hotkey::insert(table.entry(hotkey.trigger_key).or_default(), hotkey);
+                    }
+                    Err(error) => {
+                        if let Some(replaced) = previous
+                            .iter()
+                            .position(|(previous, ..)| entry.replaces(&previous.entry))
+                        {
+                            restored.push(previous.remove(replaced));
+                        }
+                        report.errors.push(KeymapEntryError {
+                            index,
+                            keys: entry.keys.clone(),
+                            error,
+                        });
+                    }
+                }
+            }
+
+            // restored once the new entries are registered, unless they now conflict
+            for (entry, layer, hotkey) in restored {
+                match self.check_hotkey(&mut layers, &mut hotkeys, &layer, &hotkey) {
+                    Ok(warning) => {
+                        warnings.push(warning);
+                        report.kept.push(entry.id.clone());
+                        entries.push(entry);
+                        let table = layer_table(&mut layers, &mut hotkeys, &layer);
+                        hotkey::insert(table.entry(hotkey.trigger_key).or_default(), hotkey);
+                    }
+                    Err(_) => previous.push((entry, layer, hotkey)),
+                }
+            }
+            report
+                .removed
+                .extend(previous.into_iter().map(|(entry, ..)| entry.id));
+            warnings
+        };
+        loaded.entries = entries;
+        drop(loaded);
+
+        let mut tap_counters = self.tap_counters.lock()?;
+        for id in &report.removed {
+            tap_counters.remove(id);
+        }
+        drop(tap_counters);
+        // reported once unlocked, so the callback can use the manager
+        for warning in warnings {
+            warning();
+        }
+        Ok(report)
+    }
+
+    /// Sets where the keymap is read from and loads it, see [`HotkeyManager::reload_keymap`].
+    pub fn set_keymap_source<S: KeymapSource>(&self, source: S) -> Result<KeymapReport> {
+        self.keymap.lock()?.source = Some(Arc::new(source));
+        self.reload_keymap()
+    }
+
+    /// Reads the keymap from its source again and loads it, see
+    /// [`HotkeyManager::load_keymap`]. The previous keymap stays registered if the source
+    /// can't be read.
+    pub fn reload_keymap(&self) -> Result<KeymapReport> {
+        let source = self.keymap.lock()?.source.clone();
+        let keymap = source.ok_or(WHKError::NoKeymapSource)?.load()?;
+        self.load_keymap(&keymap)
+    }
+
+    /// Reloads the keymap whenever its source is modified, checking it at the given
+    /// interval. The result of each reload is given to the callback, and the watcher
+    /// stops when dropped.
+    pub fn watch_keymap<F>(&self, interval: Duration, on_reload: F) -> KeymapWatcher
+    where
+        F: Fn(Result<KeymapReport>) + Send + 'static,
+    {
+        KeymapWatcher::start(self.clone(), interval, on_reload)
+    }
+
+    /// Returns when the keymap source was last modified, if known.
+    pub(crate) fn keymap_modified(&self) -> Option<SystemTime> {
+        let source = self.keymap.lock().ok()?.source.clone()?;
+        source.modified()
+    }
+
+    /// Unregisters a hotkey by its id, from all layers.
+    pub fn unregister_hotkey(&self, hotkey_id: &HotkeyId) -> Result<()> {
+        self.unregister_registration(hotkey_id, None)
+    }
+
+    /// Same as [`HotkeyManager::unregister_hotkey`], only if the hotkey is the given
+    /// registration when provided, as a [`HotkeyHandle`] must not control another hotkey
+    /// registered later with the same id.
+    pub(crate) fn unregister_registration(
+        &self,
+        hotkey_id: &HotkeyId,
+        registration: Option<u64>,
+    ) -> Result<()> {
+        let removed = {
+            let mut layers = self.layers.lock()?;
+            let mut hotkeys = self.hotkeys.lock()?;
+            remove_hotkey(&mut layers, &mut hotkeys, hotkey_id, registration).is_some()
+        };
+        if removed {
+            self.tap_counters.lock()?.remove(hotkey_id);
+        }
+        Ok(())
+    }
+
+    /// Enables or disables a registered hotkey, disabled hotkeys are never matched.
+    pub fn set_hotkey_enabled(&self, hotkey_id: &HotkeyId, enabled: bool) -> Result<()> {
+        self.set_registration_enabled(hotkey_id, None, enabled)
+    }
+
+    /// Same as [`HotkeyManager::set_hotkey_enabled`], only if the hotkey is the given
+    /// registration when provided.
+    pub(crate) fn set_registration_enabled(
+        &self,
+        hotkey_id: &HotkeyId,
+        registration: Option<u64>,
+        enabled: bool,
+    ) -> Result<()> {
+        let mut layers = self.layers.lock()?;
+        let mut hotkeys = self.hotkeys.lock()?;
+        let (_, hotkey) = find_hotkey(&mut layers, &mut hotkeys, hotkey_id, registration)
+            .ok_or_else(|| WHKError::UnknownHotkey(hotkey_id.clone()))?;
+        hotkey.enabled = enabled;
+        Ok(())
+    }
+
+    /// Returns whether a registered hotkey is enabled.
+    pub fn is_hotkey_enabled(&self, hotkey_id: &HotkeyId) -> Result<bool> {
+        self.is_registration_enabled(hotkey_id, None)
+    }
+
+    /// Same as [`HotkeyManager::is_hotkey_enabled`], only if the hotkey is the given
+    /// registration when provided.
+    pub(crate) fn is_registration_enabled(
+        &self,
+        hotkey_id: &HotkeyId,
+        registration: Option<u64>,
+    ) -> Result<bool> {
+        let mut layers = self.layers.lock()?;
+        let mut hotkeys = self.hotkeys.lock()?;
+        find_hotkey(&mut layers, &mut hotkeys, hotkey_id, registration)
+            .map(|(_, hotkey)| hotkey.enabled)
+            .ok_or_else(|| WHKError::UnknownHotkey(hotkey_id.clone()))
+    }
+
+    /// Changes the keys of a registered hotkey, keeping its id, layer and callbacks.
+    /// The hotkey keeps its previous keys if the new ones can't be registered.
+    pub fn rebind_hotkey<T: AsRef<[VKey]>>(
+        &self,
+        hotkey_id: &HotkeyId,
+        trigger_key: VKey,
+        modifiers: T,
+    ) -> Result<()> {
+        self.rebind_registration(hotkey_id, None, trigger_key, modifiers)
+    }
+
+    /// Same as [`HotkeyManager::rebind_hotkey`], only if the hotkey is the given
+    /// registration when provided.
+    pub(crate) fn rebind_registration<T: AsRef<[VKey]>>(
+        &self,
+        hotkey_id: &HotkeyId,
+        registration: Option<u64>,
+        trigger_key: VKey,
+        modifiers: T,
+    ) -> Result<()> {
+        let warnings = {
+            let mut layers = self.layers.lock()?;
+            let mut hotkeys = self.hotkeys.lock()?;
+            let (layer, index, mut hotkey) =
+                remove_hotkey(&mut layers, &mut hotkeys, hotkey_id, registration)
+                    .ok_or_else(|| WHKError::UnknownHotkey(hotkey_id.clone()))?;
+            let previous = (hotkey.trigger_key, hotkey.modifiers.clone());
+            hotkey = hotkey.trigger(trigger_key).modifiers(modifiers);
+
+            match self.check_hotkey(&mut layers, &mut hotkeys, &layer, &hotkey) {
+                Ok(warnings) => {
+                    let table = layer_table(&mut layers, &mut hotkeys, &layer);
+                    hotkey::insert(table.entry(hotkey.trigger_key).or_default(), hotkey);
+                    warnings
+                }
+                Err(err) => {
+                    // restored where it was, so it keeps its registration order
+                    (hotkey.trigger_key, hotkey.modifiers) = previous;
+                    let table = layer_table(&mut layers, &mut hotkeys, &layer);
+                    table
+                        .entry(hotkey.trigger_key)
+                        .or_default()
+                        .insert(index, hotkey);
+                    return Err(err);
+                }
+            }
+        };
+        self.tap_counters.lock()?.remove(hotkey_id);
+        warnings();
+        Ok(())
+    }
+
+    /// Activates a layer on top of the active ones,
+    /// if it was already active it is moved to the top.
+    pub fn push_layer(&self, layer: &str) {
+        self.layers.lock().unwrap().push(layer);
+    }
+
+    /// Deactivates the top-most layer, returning its name.
+    pub fn pop_layer(&self) -> Option<String> {
+        self.layers.lock().unwrap().pop()
+    }
+
+    /// Deactivates the layer if active, otherwise activates it on top of the active ones.
+    pub fn toggle_layer(&self, layer: &str) {
+        self.layers.lock().unwrap().toggle(layer);
+    }
+
+    /// Returns the names of the active layers, from bottom to top.
+    /// The first one is always the [`DEFAULT_LAYER`].
+    pub fn active_layers(&self) -> Vec<String> {
+        let layers = self.layers.lock().unwrap();
+        std::iter::once(DEFAULT_LAYER.to_string())
+            .chain(layers.stack.iter().cloned())
+            .collect()
+    }
+
+    /// Registers a new hotkey sequence, returns its unique id.
+    ///
+    /// A sequence can't be the start of a registered one, or start with one, as the
+    /// shorter sequence would always trigger before the longer one completes.
+    pub fn register_sequence(&self, mut sequence: HotkeySequence) -> Result<u64> {
+        if sequence.chords.is_empty() {
+            return Err(WHKError::EmptySequence);
+        }
+        if let Some(chord) = sequence
+            .chords
+            .iter()
+            .find(|chord| chord.trigger_key == VKey::None)
+        {
+            return Err(WHKError::HotkeyInvalidTriggerKey(chord.trigger_key));
+        }
+
+        let mut sequences = self.sequences.lock()?;
+        if sequences.contains(&sequence) {
+            return Err(HotKeyAlreadyRegistered);
+        }
+        if sequences
+            .iter()
+            .any(|registered| registered.overlaps(&sequence))
+        {
+            return Err(WHKError::SequencePrefixConflict);
+        }
+        let id = NEXT_REGISTRATION.fetch_add(1, Ordering::Relaxed);
+        sequence.id = id;
+        sequences.push(sequence);
+        Ok(id)
+    }
+
+    /// Unregisters a hotkey sequence by its unique id.
+    pub fn unregister_sequence(&self, sequence_id: u64) -> Result<()> {
+        self.sequences
+            .lock()?
+            .retain(|sequence| sequence.id != sequence_id);
+        self.sequence_matcher.cancel();
+        Ok(())
+    }
+
+    /// Registers a key that acts differently when tapped and when held.
+    pub fn register_dual_role(&self, key: DualRoleKey) -> Result<()> {
+        if key.key == VKey::None {
+            return Err(WHKError::HotkeyInvalidTriggerKey(key.key));
+        }
+
+        let mut keys = self.dual_role_keys.lock()?;
+        if keys.contains_key(&key.key) {
+            return Err(HotKeyAlreadyRegistered);
+        }
+        keys.insert(key.key, key);
+        Ok(())
+    }
+
+    /// Unregisters a dual-role key.
+    pub fn unregister_dual_role(&self, key: VKey) -> Result<()> {
+        self.dual_role_keys.lock()?.remove(&key);
+        Ok(())
+    }
+
+    /// Registers a key remap, returns its unique id.
+    pub fn register_remap(&self, mut remap: Remap) -> Result<u64> {
+        for key in [remap.from, remap.to] {
+            if key == VKey::None {
+                return Err(WHKError::HotkeyInvalidTriggerKey(key));
+            }
+        }
+
+        let mut remaps = self.remaps.lock()?;
+        let remaps = remaps.entry(remap.from).or_default();
+        if remaps.iter().any(|r| r.modifiers == remap.modifiers) {
+            return Err(HotKeyAlreadyRegistered);
+        }
+        let id = NEXT_REGISTRATION.fetch_add(1, Ordering::Relaxed);
+        remap.id = id;
+        remaps.push(remap);
+        Ok(id)
+    }
+
+    /// Unregisters a key remap by its unique id.
+    /// Keys held while unregistering are still released as the remapped key.
+    pub fn unregister_remap(&self, remap_id: u64) -> Result<()> {
+        for remaps in self.remaps.lock()?.values_mut() {
+            remaps.retain(|remap| remap.id != remap_id);
+        }
+        Ok(())
+    }
+
+    /// Unregisters all hotkeys, hotkey sequences, dual-role keys, remaps, filters and
+    /// keymap actions, deactivating all layers and clearing the flags and the keymap
+    /// source.
+    pub fn unregister_all(&mut self) -> Result<()> {
+        *self.hotkeys.lock()? = HotkeyManager::get_initial_hotkeys();
+        self.sequences.lock()?.clear();
+        self.pressed_hotkeys.lock()?.clear();
+        self.dual_role_keys.lock()?.clear();
+        self.tap_counters.lock()?.clear();
+        self.blocked_taps.lock()?.clear();
+        self.remaps.lock()?.clear();
+        self.filters.store(Default::default());
+        *self.layers.lock()? = Layers::default();
+        self.flags.lock()?.clear();
+        self.actions.lock()?.clear();
+        *self.keymap.lock()? = LoadedKeymap::default();
+        self.sequence_matcher.cancel();
+        self.dual_role_matcher.cancel();
+        Ok(())
+    }
+
+    /// Runs the main event loop to listen for keyboard events in a separate thread.
+    ///
+    /// It matches events against registered hotkeys and executes the corresponding callbacks.
+    /// Keyboard input is captured using the default backend of the platform,
+    /// see [`HotkeyManager::start_keyboard_capturing_with`] to provide a custom one.
+    pub fn start_keyboard_capturing() -> Result<std::thread::JoinHandle<()>> {
+        Self::start_keyboard_capturing_with(backend::default_backend()?)
+    }
+
+    /// Same as [`HotkeyManager::start_keyboard_capturing`] but keyboard events
+    /// are provided by the given backend.
+    pub fn start_keyboard_capturing_with<B: InputBackend>(
+        backend: B,
+    ) -> Result<std::thread::JoinHandle<()>> {
+        // clean event loop channel, to remove events before start
+        while EventLoopEvent::reciever().try_recv().is_ok() {}
+
+        // Create/clear keyboard state
+        KEYBOARD_STATE.lock()?.clear();
+        for manager in HotkeyManager::attached() {
+            manager.pressed_hotkeys.lock()?.clear();
+            manager.tap_counters.lock()?.clear();
+            manager.blocked_taps.lock()?.clear();
+            manager.remap_matcher.cancel();
+        }
+        client_executor::start_executor_thread();
+        if let Err(err) = backend::install(backend) {
+            client_executor::stop_executor_thread();
+            return Err(err);
+        }
+
+        let handle = std::thread::spawn(|| {
+            let events = EventLoopEvent::reciever();
+            loop {
+                // pending sequences and dual-role keys are resolved if no key is pressed
+                // before their deadline
+                let deadline = HotkeyManager::attached()
+                    .iter()
+                    .filter_map(HotkeyManager::pending_deadline)
+                    .min();
+                let received = match deadline {
+                    Some(deadline) => events.recv_deadline(deadline),
+                    None => events.recv().map_err(|_| RecvTimeoutError::Disconnected),
+                };
+
+                let (event, response) = match received {
+                    Ok(EventLoopEvent::Keyboard(event, id, deadline)) => {
+                        (event, Some((id, deadline)))
+                    }
+                    Ok(EventLoopEvent::Notify(event)) => (event, None),
+                    Ok(EventLoopEvent::Stop) | Err(RecvTimeoutError::Disconnected) => break,
+                    Ok(EventLoopEvent::Wake) => continue,
+                    Err(RecvTimeoutError::Timeout) => {
+                        for manager in HotkeyManager::attached() {
+                            manager.process_deadlines();
+                        }
+                        continue;
+                    }
+                };
+
+                let deadline = response.map(|(_, deadline)| deadline);
+                let key_action = HotkeyManager::process_keyboard_event(event, deadline);
+                if let Some((id, _)) = response {
+                    key_action.send(id);
+                }
+            }
+            for manager in HotkeyManager::attached() {
+                manager.cancel_pending();
+            }
+        });
+
+        Ok(handle)
+    }
+
+    /// Returns when the pending sequence or dual-role key must be resolved, if any.
+    fn pending_deadline(&self) -> Option<Instant> {
+        let capture_deadline = self
+            .capture
+            .lock()
+            .unwrap()
+            .as_ref()
+            .and_then(ActiveCapture::deadline);
+        [
+            self.sequence_matcher.pending_deadline(),
+            self.dual_role_matcher.pending_deadline(),
+            self.blocked_taps.lock().unwrap().deadline,
+            capture_deadline,
+        ]
+        .into_iter()
+        .flatten()
+        .min()
+    }
+
+    /// Resolves the pending sequence, dual-role key and capture session whose deadline passed.
+    fn process_deadlines(&self) {
+        let mut capture = self.capture.lock().unwrap();
+        if capture
+            .as_ref()
+            .and_then(ActiveCapture::deadline)
+            .is_some_and(|deadline| deadline <= Instant::now())
+        {
+            self.take_capture(&mut capture).finish(CaptureEnd::Timeout);
+        }
+        drop(capture);
+
+        if let Some(swallowed) = self.sequence_matcher.expire() {
+            let state = KEYBOARD_STATE.lock().unwrap().clone();
+            backend::send_input(&sequence::replay_inputs(&swallowed, &state));
+        }
+        if let Some(resolution) = self.dual_role_matcher.expire() {
+            self.resolve_dual_role(resolution, Instant::now() + MAX_SYNC_BUDGET);
+        }
+
+        let mut tap_counters = self.tap_counters.lock().unwrap();
+        let mut blocked_taps = self.blocked_taps.lock().unwrap();
+        if blocked_taps
+            .deadline
+            .is_some_and(|deadline| deadline <= Instant::now())
+        {
+            // the multi-tap hotkeys can no longer complete
+            tap_counters.retain(|_, counter| counter.count == 0);
+            let swallowed = blocked_taps.take();
+            drop(blocked_taps);
+            drop(tap_counters);
+            let state = KEYBOARD_STATE.lock().unwrap().clone();
+            backend::send_input(&replay_taps(&swallowed, &state));
+        }
+    }
+
+    /// Drops the pending sequence and dual-role key, releasing the remapped keys.
+    fn cancel_pending(&self) {
+        self.sequence_matcher.cancel();
+        self.dual_role_matcher.cancel();
+        self.blocked_taps.lock().unwrap().clear();
+        // remapped keys must not stay down once the manager stops receiving events
+        let held: Vec<KeyInput> = self
+            .remap_matcher
+            .cancel()
+            .into_iter()
+            .map(KeyInput::Up)
+            .collect();
+        backend::send_input(&held);
+    }
+
+    /// Feeds a keyboard event to the attached managers, in order of priority.
+    /// The backend stops waiting for the response at `response_deadline`, if any.
+    pub(crate) fn process_keyboard_event(
+        event: KeyboardInputEvent,
+        response_deadline: Option<Instant>,
+    ) -> KeyAction {
+        let is_key_down = matches!(event, KeyboardInputEvent::KeyDown { .. });
+        // the synchronous callbacks of all the managers share the time the backend waits,
+        // which started before the event was queued
+        let mut deadline = Instant::now() + MAX_SYNC_BUDGET;
+        if let Some(response_deadline) = response_deadline {
+            deadline = deadline.min(
+                response_deadline
+                    .checked_sub(RESPONSE_MARGIN)
+                    .unwrap_or(response_deadline),
+            );
+        }
+        let mut action = KeyAction::Allow;
+        for manager in HotkeyManager::attached() {
+            manager.notify_listener(&event);
+            // a handled key press doesn't reach the managers with lower priority,
+            // while releases reach all of them to end their pressed hotkeys
+            let handled = action != KeyAction::Allow;
+            if handled && is_key_down {
+                continue;
+            }
+            let manager_action = manager.process_event(event.clone(), deadline);
+            if !handled {
+                action = manager_action;
+            }
+        }
+        action
+    }
+
+    /// Sends the event to the global keyboard listener, if any.
+    fn notify_listener(&self, event: &KeyboardInputEvent) {
+        if let Some(cb) = self.keyboard_callback.load().as_ref() {
+            let cb = cb.clone();
+            let event = event.clone();
+            run_on_executor_thread(Arc::new(move || {
+                cb(event.clone());
+            }));
+        }
+    }
+
+    /// Matches a keyboard event against the registrations of this manager.
+    /// The synchronous callbacks must decide before the deadline of the event.
+    fn process_event(&self, event: KeyboardInputEvent, deadline: Instant) -> KeyAction {
+        let manager = self;
+        let processed = manager.injected_policy().should_process(event.info());
+        let (vk_code, state, time) = match &event {
+            KeyboardInputEvent::KeyDown {
+                vk_code,
+                state,
+                info,
+            } => (*vk_code, state.clone(), info.time),
+            KeyboardInputEvent::KeyUp { vk_code, state, .. } => {
+                return manager.process_release(&event, *vk_code, state, processed, deadline);
+            }
+        };
+
+        if !processed {
+            return KeyAction::Allow;
+        }
+        if let Some(action) = manager.capture_event(&event) {
+            return action;
+        }
+
+        match manager.filter(&event) {
+            FilterResult::Pass => {}
+            FilterResult::Block => return TriggerBehavior::StopPropagation.key_action(&state),
+            FilterResult::Replace(target) => {
+                manager.remap_matcher.hold(vk_code, target);
+                return KeyAction::Substitute(target);
+            }
+        }
+
+        let paused_state = manager.pause_handler();
+
+        let remapped = manager.remap_matcher.on_key_down(
+            &manager.remaps.lock().unwrap(),
+            vk_code,
+            &state,
+            paused_state.is_paused(),
+        );
+        if let Some(target) = remapped {
+            return KeyAction::Substitute(target);
+        }
+
+        let step = manager.dual_role_matcher.on_key_down(
+            &manager.dual_role_keys.lock().unwrap(),
+            vk_code,
+            &state,
+            time,
+            paused_state.is_paused(),
+        );
+        match step {
+            DualRoleStep::Unrelated => {}
+            DualRoleStep::Swallowed => {
+                return TriggerBehavior::StopPropagation.key_action(&state);
+            }
+            DualRoleStep::Decided(resolution) => {
+                manager.resolve_dual_role(resolution, deadline);
+                return TriggerBehavior::StopPropagation.key_action(&state);
+            }
+        }
+
+        let step = manager.sequence_matcher.on_key_down(
+            &manager.sequences.lock().unwrap(),
+            vk_code,
+            &state,
+            paused_state.is_paused(),
+        );
+        match step {
+            SequenceStep::Unrelated => manager.process_hotkeys(vk_code, &state, time, deadline),
+            SequenceStep::Matched(action) => action,
+            SequenceStep::Aborted(swallowed) => {
+                let mut inputs = sequence::replay_inputs(&swallowed, &state);
+                let action = manager.process_hotkeys(vk_code, &state, time, deadline);
+                if action != KeyAction::Allow {
+                    backend::send_input(&inputs);
+                    return action;
+                }
+                // the key is sent again after the replayed chords, to keep the order
+                inputs.push(KeyInput::Down(VKey::from(vk_code)));
+                backend::send_input(&inputs);
+                KeyAction::Block
+            }
+            SequenceStep::Restarted(swallowed, action) => {
+                backend::send_input(&sequence::replay_inputs(&swallowed, &state));
+                action
+            }
+        }
+    }
+
+    /// Returns whether an attached manager may block or replace the release of the key,
+    /// the other releases are allowed without waiting for the event loop.
+    ///
+    /// Called by the hook thread, so it only reads the snapshot of the attached managers
+    /// and leaves detaching the dropped ones to the event loop.
+    pub(crate) fn decides_release(vk_code: u16) -> bool {
+        MANAGERS
+            .load()
+            .iter()
+            .filter(|attached| !attached.is_dropped())
+            .any(|attached| {
+                let manager = &attached.manager;
+                manager.dual_role_matcher.is_active()
+                    || manager.remap_matcher.is_held(vk_code)
+                    || manager.is_stealing_mode()
+                    || !manager.filters.load().is_empty()
+            })
+    }
+
+    /// Ends what the released key started. Releases are always tracked, even the ones
+    /// ignored by the injected policy, the filters and capture session only decide how
+    /// they are handled.
+    fn process_release(
+        &self,
+        event: &KeyboardInputEvent,
+        vk_code: u16,
+        state: &KeyboardState,
+        processed: bool,
+        deadline: Instant,
+    ) -> KeyAction {
+        if let Some(target) = self.remap_matcher.on_key_up(vk_code) {
+            return KeyAction::Substitute(target);
+        }
+        self.sequence_matcher.on_key_up(vk_code);
+        self.release_hotkeys(vk_code);
+        let action = match self.dual_role_matcher.on_key_up(vk_code) {
+            DualRoleStep::Unrelated => KeyAction::Allow,
+            DualRoleStep::Swallowed => KeyAction::Block,
+            DualRoleStep::Decided(resolution) => {
+                self.resolve_dual_role(resolution, deadline);
+                TriggerBehavior::StopPropagation.key_action(state)
+            }
+        };
+
+        if !processed {
+            return action;
+        }
+        if let Some(action) = self.capture_event(event) {
+            return action;
+        }
+        match self.filter(event) {
+            FilterResult::Pass => action,
+            FilterResult::Block => KeyAction::Block,
+            FilterResult::Replace(target) => KeyAction::Substitute(target),
+        }
+    }
+
+    /// Evaluates the filters in order of priority, returns the result of the first
+    /// one that doesn't pass the event.
+    fn filter(&self, event: &KeyboardInputEvent) -> FilterResult {
+        // a snapshot of the filters, so they can add or remove filters
+        let filters = self.filters.load_full();
+        filter::evaluate(&filters, event)
+    }
+
+    /// Matches a key press against the registered hotkeys, then applies
+    /// the layer change of the triggered hotkey, if any.
+    fn process_hotkeys(
+        &self,
+        vk_code: u16,
+        state: &KeyboardState,
+        time: u32,
+        deadline: Instant,
+    ) -> KeyAction {
+        let matched = self.match_hotkeys(vk_code, state, time);
+        if let Some(layer_action) = matched.layer_action {
+            self.layers.lock().unwrap().apply(&layer_action);
+        }
+        // synchronous callbacks run once the registrations are unlocked,
+        // so they can use the manager
+        let action = match matched.sync_decision {
+            Some(decision) => {
+                let id = decision.id.clone();
+                let propagation = decision.decide(deadline);
+                // the autorepeats of a hotkey waiting for its release reuse the decision
+                let mut pressed = self.pressed_hotkeys.lock().unwrap();
+                if let Some(pressed) = pressed.iter_mut().find(|pressed| pressed.id == id) {
+                    pressed.propagation = Some(propagation);
+                }
+                propagation.key_action(state)
+            }
+            None => matched.action,
+        };
+        if matched.interrupted_taps.is_empty() {
+            return action;
+        }
+
+        let mut inputs = replay_taps(&matched.interrupted_taps, state);
+        if action != KeyAction::Allow {
+            backend::send_input(&inputs);
+            return action;
+        }
+        // the key is sent again after the replayed taps, to keep the order
+        inputs.push(KeyInput::Down(VKey::from(vk_code)));
+        backend::send_input(&inputs);
+        KeyAction::Block
+    }
+
+    /// Matches a key press against the hotkeys of the active layers, from the top-most one,
+    /// the first eligible hotkey being triggered. The multi-tap hotkeys before it are
+    /// counted, and completing one takes precedence, ex: over a single-tap hotkey with the
+    /// same keys.
+    fn match_hotkeys(&self, vk_code: u16, state: &KeyboardState, time: u32) -> HotkeyMatch {
+        let key = VKey::from(vk_code);
+        let mut tap_counters = self.tap_counters.lock().unwrap();
+        let mut blocked_taps = self.blocked_taps.lock().unwrap();
+        // pressing any other key interrupts the taps in progress
+        if !key.is_modifier_key() {
+            tap_counters.retain(|_, counter| counter.trigger_key == key);
+        }
+
+        let paused_state = self.pause_handler();
+        let layers = self.layers.lock().unwrap();
+        let hotkeys = self.hotkeys.lock().unwrap();
+        let flags = self.flags.lock().unwrap();
+        let context = HotkeyContext::new(state, &layers, paused_state.is_paused(), &flags);
+        let candidates = layers
+            .active_hotkeys(key)
+            .chain(hotkeys.get(&key))
+            .flatten();
+
+        // the foreground window is only queried if a scoped hotkey is matched
+        let mut foreground = None;
+        let mut single_tap: Option<&Hotkey> = None;
+        let mut pending_tap = None;
+        let mut counted_tap = None;
+        for hotkey in candidates {
+            if !hotkey.enabled {
+                continue;
+            }
+
+            if paused_state.is_paused() && !hotkey.bypass_pause {
+                continue;
+            }
+
+            if !hotkey.is_trigger_state(state) {
+                continue;
+            }
+
+            if let Some(scope) = &hotkey.scope {
+                let window = foreground
+                    .get_or_insert_with(|| backend::foreground_window().unwrap_or_default());
+                if !scope.matches(window) {
+                    continue;
+                }
+            }
+
+            if !hotkey
+                .conditions
+                .iter()
+                .all(|condition| condition(&context))
+            {
+                continue;
+            }
+
+            if hotkey.taps <= 1 {
+                // the hotkeys are in the order they are matched, see `Hotkey::precedence`
+                single_tap = Some(hotkey);
+                break;
+            }
+
+            let counter = tap_counters
+                .entry(hotkey.id.clone())
+                .or_insert_with(|| TapCounter::new(hotkey.trigger_key));
+            match counter.tap(hotkey, time) {
+                Tap::Completed => {
+                    // the blocked taps are part of the hotkey
+                    blocked_taps.clear();
+                    return self.trigger_hotkey(hotkey, state);
+                }
+                Tap::Autorepeat if counter.count == 0 => {
+                    return HotkeyMatch::new(hotkey.behaviour.key_action(state))
+                }
+                Tap::Pending | Tap::Autorepeat if hotkey.pass_through_taps => {}
+                Tap::Pending => {
+                    pending_tap.get_or_insert(hotkey);
+                    counted_tap.get_or_insert(hotkey);
+                }
+                Tap::Autorepeat => {
+                    pending_tap.get_or_insert(hotkey);
+                }
+            }
+        }
+
+        if let (None, Some(hotkey)) = (single_tap, counted_tap) {
+            blocked_taps.block(state, hotkey.tap_window);
+        }
+        // the blocked taps are replayed before a key which doesn't continue them
+        let interrupted_taps = match pending_tap {
+            Some(_) if single_tap.is_none() => Vec::new(),
+            _ if key.is_modifier_key() => Vec::new(),
+            _ => blocked_taps.take(),
+        };
+
+        let mut matched = match (single_tap, pending_tap) {
+            (Some(hotkey), _) => self.trigger_hotkey(hotkey, state),
+            (None, Some(hotkey)) => HotkeyMatch::new(hotkey.behaviour.key_action(state)),
+            (None, None) => HotkeyMatch::new(KeyAction::Allow),
+        };
+        matched.interrupted_taps = interrupted_taps;
+        matched
+    }
+
+    /// Executes the callback of a matched hotkey and returns how its key event must be handled,
+    /// along with the layer change to apply.
+    fn trigger_hotkey(&self, hotkey: &Hotkey, state: &KeyboardState) -> HotkeyMatch {
+        let action = hotkey.behaviour.key_action(state);
+        if !hotkey.tracks_release() {
+            run_on_executor_thread(hotkey.callback.clone());
+            return HotkeyMatch {
+                action,
+                layer_action: hotkey.layer_action.clone(),
+                sync_decision: hotkey.sync_decision(),
+                interrupted_taps: Vec::new(),
+            };
+        }
+
+        let mut pressed = self.pressed_hotkeys.lock().unwrap();
+        let id = hotkey.id.clone();
+        // autorepeat of an already pressed hotkey
+        if let Some(pressed) = pressed.iter().find(|p| p.id == id) {
+            let propagation = pressed.propagation;
+            return HotkeyMatch::new(propagation.map_or(action, |p| p.key_action(state)));
+        }
+
+        let mut sync_decision = None;
+        if hotkey.trigger_on != TriggerOn::Release {
+            run_on_executor_thread(hotkey.callback.clone());
+            sync_decision = hotkey.sync_decision();
+        }
+        let mut on_release = Vec::new();
+        if hotkey.trigger_on != TriggerOn::Press {
+            on_release.push(hotkey.callback.clone());
+        }
+        on_release.extend(hotkey.on_release.clone());
+        let layer = match &hotkey.layer_action {
+            Some(LayerAction::Momentary(layer)) => Some(layer.clone()),
+            _ => None,
+        };
+        pressed.push(PressedHotkey {
+            id,
+            trigger_key: hotkey.trigger_key,
+            on_release,
+            layer,
+            propagation: None,
+        });
+        HotkeyMatch {
+            action,
+            layer_action: hotkey.layer_action.clone(),
+            sync_decision,
+            interrupted_taps: Vec::new(),
+        }
+    }
+
+    /// Applies the decision taken for a dual-role key, replaying the keys
+    /// that were buffered while it was undecided.
+    fn resolve_dual_role(&self, resolution: Resolution, deadline: Instant) {
+        let mut inputs = Vec::new();
+        let (key, buffered, holding) = match resolution {
+            Resolution::Tap(key, buffered) => {
+                match &key.on_tap {
+                    Some(cb) => run_on_executor_thread(cb.clone()),
+                    None => inputs.extend([KeyInput::Down(key.key), KeyInput::Up(key.key)]),
+                }
+                (key, buffered, false)
+            }
+            Resolution::Hold(key, buffered) => {
+                if let Some(modifier) = key.hold_modifier {
+                    KEYBOARD_STATE.lock().unwrap().keydown(modifier);
+                    inputs.push(KeyInput::Down(modifier));
+                }
+                if let Some(cb) = &key.on_hold {
+                    run_on_executor_thread(cb.clone());
+                }
+                (key, buffered, true)
+            }
+            Resolution::HoldEnd(key) => {
+                if let Some(modifier) = key.hold_modifier {
+                    KEYBOARD_STATE.lock().unwrap().keyup(modifier);
+                    inputs.push(KeyInput::Up(modifier));
+                }
+                if let Some(cb) = &key.on_hold_release {
+                    run_on_executor_thread(cb.clone());
+                }
+                backend::send_input(&inputs);
+                return;
+            }
+        };
+
+        for buffered_key in buffered {
+            let vk = VKey::from(buffered_key.vk_code);
+            let mut state = buffered_key.state;
+            state.keyup(key.key);
+            state.keyup(vk);
+            if let (true, Some(modifier)) = (holding, key.hold_modifier) {
+                state.keydown(modifier);
+            }
+            state.keydown(vk);
+
+            if self.process_hotkeys(buffered_key.vk_code, &state, buffered_key.time, deadline)
+                == KeyAction::Allow
+            {
+                inputs.push(KeyInput::Down(vk));
+                if buffered_key.released {
+                    inputs.push(KeyInput::Up(vk));
+                }
+            }
+            if buffered_key.released {
+                self.release_hotkeys(buffered_key.vk_code);
+            }
+        }
+        backend::send_input(&inputs);
+    }
+
+    /// Executes the release callbacks of the pressed hotkeys triggered by the released key.
+    fn release_hotkeys(&self, vk_code: u16) {
+        let key = VKey::from(vk_code);
+        for counter in self.tap_counters.lock().unwrap().values_mut() {
+            if counter.trigger_key == key {
+                counter.held = false;
+            }
+        }
+        let mut released_layers = Vec::new();
+        self.pressed_hotkeys.lock().unwrap().retain(|pressed| {
+            if pressed.trigger_key != key {
+                return true;
+            }
+            for cb in &pressed.on_release {
+                run_on_executor_thread(cb.clone());
+            }
+            released_layers.extend(pressed.layer.clone());
+            false
+        });
+
+        if !released_layers.is_empty() {
+            let mut layers = self.layers.lock().unwrap();
+            for layer in released_layers {
+                layers.remove(&layer);
+            }
+        }
+    }
+
+    /// This gracefully interrupt the event loop by sending
+    /// a control signal. This allows the `HotkeyManager` to clean up resources and stop
+    /// processing keyboard events.
+    pub fn stop_keyboard_capturing() {
+        EventLoopEvent::send(EventLoopEvent::Stop);
+        backend::uninstall();
+        client_executor::stop_executor_thread();
     }
 
-    /// Sets the stealing mode for the hotkey manager until the `ESC` key is pressed,
-    /// or client manually frees the keyboard.
-    pub fn steal_keyboard<F>(&self, on_free: F)
+    pub fn set_global_keyboard_listener<F>(&self, cb: F)
     where
-        F: Fn() + Send + Sync + 'static,
+        F: Fn(KeyboardInputEvent) + Send + Sync + 'static,
     {
-        log_on_dev!("Keyboard stealing mode enabled");
-        self.stealing.store(true, Ordering::SeqCst);
-        CLIENT_ON_FREE_KEYBOARD_CB.store(Some(Arc::new(Box::new(on_free))));
+        self.keyboard_callback.store(Some(Arc::new(Box::new(cb))));
     }
 
-    /// Disables the stealing mode for the hotkey manager.
-    pub fn free_keyboard(&self) {
-        log_on_dev!("Keyboard stealing mode disabled");
-        self.stealing.store(false, Ordering::SeqCst);
-        if let Some(on_free_cb) = CLIENT_ON_FREE_KEYBOARD_CB.swap(None) {
-            run_on_executor_thread(on_free_cb);
-        }
+    pub fn remove_global_keyboard_listener(&self) {
+        self.keyboard_callback.store(None);
     }
 
-    /// Registers a new hotkey.
-    pub fn register_hotkey(&self, hotkey: Hotkey) -> Result<u64> {
-        if hotkey.trigger_key == VKey::None {
-            return Err(WHKError::HotkeyInvalidTriggerKey(hotkey.trigger_key));
+    /// Signals the `HotkeyManager` to pause processing of hotkeys.
+    pub fn pause_handler(&self) -> HotkeysPauseHandler {
+        HotkeysPauseHandler {
+            state: self.paused.clone(),
         }
+    }
+}
 
-        let id = hotkey.as_hash();
-        let was_already_inserted = !self
-            .hotkeys
-            .lock()?
-            .entry(hotkey.trigger_key)
-            .or_default()
-            .insert(hotkey);
+/// A manager receiving the key events.
+#[derive(Clone)]
+struct AttachedManager {
+    /// owners of the manager, it is detached once they are all dropped
+    owners: Weak<()>,
+    /// copy of the manager which is not one of its owners
+    manager: HotkeyManager,
+}
 
-        if was_already_inserted {
-            return Err(HotKeyAlreadyRegistered);
+impl AttachedManager {
+    fn new(manager: &HotkeyManager) -> Self {
+        Self {
+            owners: Arc::downgrade(&manager.owners),
+            manager: HotkeyManager {
+                owners: Default::default(),
+                ..manager.clone()
+            },
         }
-        Ok(id)
     }
 
-    /// Unregisters a hotkey by its unique id.
-    pub fn unregister_hotkey(&self, hotkey_id: u64) -> Result<()> {
-        for hotkeys in self.hotkeys.lock()?.values_mut() {
-            hotkeys.retain(|hotkey| hotkey.as_hash() != hotkey_id);
-        }
-        Ok(())
+    fn is_dropped(&self) -> bool {
+        self.owners.strong_count() == 0
     }
+}
 
-    /// Unregisters all hotkeys.
-    pub fn unregister_all(&mut self) -> Result<()> {
-        *self.hotkeys.lock()? = HotkeyManager::get_initial_hotkeys();
-        Ok(())
+/// Returns the hotkeys of a layer, by trigger key.
+fn layer_table<'a>(
+    layers: &'a mut Layers,
+    hotkeys: &'a mut HotkeysTable,
+    layer: &str,
+) -> &'a mut HotkeysTable {
+    if layer == DEFAULT_LAYER {
+        return hotkeys;
     }
+    layers.hotkeys.entry(layer.to_string()).or_default()
+}
 
-    /// Runs the main event loop to listen for keyboard events in a separate thread.
-    ///
-    /// It matches events against registered hotkeys and executes the corresponding callbacks.
-    pub fn start_keyboard_capturing() -> Result<std::thread::JoinHandle<()>> {
-        hook::start()?;
-        client_executor::start_executor_thread();
+/// Returns the hotkeys of all the layers, the default one first.
+fn all_tables<'a>(
+    layers: &'a mut Layers,
+    hotkeys: &'a mut HotkeysTable,
+) -> impl Iterator<Item = (&'a str, &'a mut HotkeysTable)> {
+    std::iter::once((DEFAULT_LAYER, hotkeys)).chain(
+        layers
+            .hotkeys
+            .iter_mut()
+            .map(|(layer, table)| (layer.as_str(), table)),
+    )
+}
 
-        let handle = std::thread::spawn(|| {
-            // clean event loop channel, to remove events before start
-            while EventLoopEvent::reciever().try_recv().is_ok() {}
+/// Returns whether the hotkey has the id, and is the registration when provided.
+fn is_registered_as(hotkey: &Hotkey, id: &HotkeyId, registration: Option<u64>) -> bool {
+    hotkey.id == *id && registration.is_none_or(|registration| hotkey.registration == registration)
+}
 
-            'event_loop: while let Ok(loop_event) = EventLoopEvent::reciever().recv() {
-                let event = match loop_event {
-                    EventLoopEvent::Stop => break 'event_loop,
-                    EventLoopEvent::Keyboard(event) => event,
-                };
+/// Finds a registered hotkey by its id, and its registration when provided,
+/// returns it along with its layer.
+fn find_hotkey<'a>(
+    layers: &'a mut Layers,
+    hotkeys: &'a mut HotkeysTable,
+    id: &HotkeyId,
+    registration: Option<u64>,
+) -> Option<(&'a str, &'a mut Hotkey)> {
+    all_tables(layers, hotkeys).find_map(|(layer, table)| {
+        let hotkey = table
+            .values_mut()
+            .flatten()
+            .find(|hotkey| is_registered_as(hotkey, id, registration))?;
+        Some((layer, hotkey))
+    })
+}
 
-                let key_action = HotkeyManager::process_keyboard_event(event);
-                key_action.send();
-            }
-        });
+/// Removes a registered hotkey by its id, and its registration when provided,
+/// returns it along with its layer and its position among the hotkeys with the same
+/// trigger key.
+fn remove_hotkey(
+    layers: &mut Layers,
+    hotkeys: &mut HotkeysTable,
+    id: &HotkeyId,
+    registration: Option<u64>,
+) -> Option<(String, usize, Hotkey)> {
+    all_tables(layers, hotkeys).find_map(|(layer, table)| {
+        table.values_mut().find_map(|hotkeys| {
+            let index = hotkeys
+                .iter()
+                .position(|hotkey| is_registered_as(hotkey, id, registration))?;
+            Some((layer.to_string(), index, hotkeys.remove(index)))
+        })
+    })
+}
 
-        Ok(handle)
+/// A hotkey that was triggered and waits for the release of its trigger key.
+struct PressedHotkey {
+    id: HotkeyId,
+    trigger_key: VKey,
+    /// callbacks to execute when the trigger key is released
+    on_release: Vec<Arc<Box<FreeKeyboardCallback>>>,
+    /// layer activated while the trigger key is held
+    layer: Option<String>,
+    /// decision of the synchronous callback on the press, if any
+    propagation: Option<Propagation>,
+}
+
+impl std::fmt::Debug for PressedHotkey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PressedHotkey")
+            .field("id", &self.id)
+            .field("trigger_key", &self.trigger_key)
+            .field("layer", &self.layer)
+            .field("propagation", &self.propagation)
+            .finish()
     }
+}
 
-    pub(crate) fn process_keyboard_event(event: KeyboardInputEvent) -> KeyAction {
-        if let Some(cb) = CLIENT_KEYBOARD_CALLBACK.load().as_ref() {
-            let cb = cb.clone();
-            let event = event.clone();
-            run_on_executor_thread(Arc::new(move || {
-                cb(event.clone());
-            }));
-        }
+/// Defines how key events injected by software are handled by the `HotkeyManager`.
+/// Injected events always reach the global keyboard listener.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum InjectedPolicy {
+    /// Process injected events like the physical ones
+    #[default]
+    Process,
+    /// Ignore all injected events, they are allowed without triggering hotkeys
+    Ignore,
+    /// Only process injected events tagged with the given `dwExtraInfo` value
+    ProcessOnlyFrom(usize),
+}
 
-        let KeyboardInputEvent::KeyDown { vk_code, state } = event else {
-            return KeyAction::Allow;
-        };
+impl InjectedPolicy {
+    /// Returns whether a key event must be matched against the hotkeys.
+    pub fn should_process(&self, info: &KeyEventInfo) -> bool {
+        if !info.injected {
+            return true;
+        }
+        match self {
+            InjectedPolicy::Process => true,
+            InjectedPolicy::Ignore => false,
+            InjectedPolicy::ProcessOnlyFrom(tag) => info.extra_info == *tag,
+        }
+    }
+}
 
-        let manager = HotkeyManager::current();
-        let paused_state = HotkeysPauseHandler::current();
+/// Outcome of matching a key press against the registered hotkeys.
+struct HotkeyMatch {
+    /// how the key event must be handled
+    action: KeyAction,
+    /// how the active layers change
+    layer_action: Option<LayerAction>,
+    /// synchronous callback deciding how the key event must be handled instead
+    sync_decision: Option<SyncDecision>,
+    /// blocked taps interrupted by the key, which must be replayed before it
+    interrupted_taps: Vec<KeyboardState>,
+}
 
-        let is_stealing = manager.is_stealing_mode();
-        if is_stealing && VKey::from(vk_code) == VKey::Escape {
-            manager.free_keyboard();
+impl HotkeyMatch {
+    fn new(action: KeyAction) -> Self {
+        Self {
+            action,
+            layer_action: None,
+            sync_decision: None,
+            interrupted_taps: Vec::new(),
         }
+    }
+}
 
-        // on ESC press we exit stealing mode, but still will block the ESC key
-        if is_stealing {
-            return if state.is_down(VKey::LWin) {
-                KeyAction::Replace
-            } else {
-                KeyAction::Block
-            };
-        }
+/// Taps counted for a multi-tap hotkey.
+#[derive(Debug)]
+struct TapCounter {
+    trigger_key: VKey,
+    /// taps done so far, reset when the hotkey triggers
+    count: u8,
+    /// time of the last tap, in milliseconds
+    last_tap: u32,
+    /// whether the trigger key is still pressed since the last tap
+    held: bool,
+}
 
-        if let Some(hotkeys) = HOTKEYS.lock().unwrap().get(&VKey::from(vk_code)) {
-            for hotkey in hotkeys {
-                if paused_state.is_paused() && !hotkey.bypass_pause {
-                    continue;
-                }
+/// Outcome of counting a tap.
+enum Tap {
+    /// the tap completed the hotkey
+    Completed,
+    /// more taps are needed
+    Pending,
+    /// the trigger key was not released since the last tap
+    Autorepeat,
+}
 
-                if !hotkey.is_trigger_state(&state) {
-                    continue;
-                }
+impl TapCounter {
+    fn new(trigger_key: VKey) -> Self {
+        Self {
+            trigger_key,
+            count: 0,
+            last_tap: 0,
+            held: false,
+        }
+    }
 
-                run_on_executor_thread(hotkey.callback.clone());
-                return match hotkey.behaviour {
-                    TriggerBehavior::PassThrough => KeyAction::Allow,
-                    TriggerBehavior::StopPropagation => {
-                        if state.is_down(VKey::LWin) {
-                            KeyAction::Replace
-                        } else {
-                            KeyAction::Block
-                        }
-                    }
-                };
-            }
+    fn tap(&mut self, hotkey: &Hotkey, time: u32) -> Tap {
+        if self.held {
+            return Tap::Autorepeat;
+        }
+
+        let elapsed = time.wrapping_sub(self.last_tap);
+        if self.count > 0 && u128::from(elapsed) > hotkey.tap_window.as_millis() {
+            self.count = 0;
         }
+        self.count += 1;
+        self.last_tap = time;
+        self.held = true;
 
-        KeyAction::Allow
+        if self.count >= hotkey.taps {
+            self.count = 0;
+            Tap::Completed
+        } else {
+            Tap::Pending
+        }
     }
+}
 
-    /// This gracefully interrupt the event loop by sending
-    /// a control signal. This allows the `HotkeyManager` to clean up resources and stop
-    /// processing keyboard events.
-    pub fn stop_keyboard_capturing() {
-        EventLoopEvent::send(EventLoopEvent::Stop);
-        hook::stop();
-        client_executor::stop_executor_thread();
+/// Taps blocked while waiting for a multi-tap hotkey to complete.
+#[derive(Debug, Default)]
+struct BlockedTaps {
+    /// keyboard state of each blocked tap
+    swallowed: Vec<KeyboardState>,
+    /// when the taps are replayed if the hotkey was not completed
+    deadline: Option<Instant>,
+}
+
+impl BlockedTaps {
+    fn block(&mut self, state: &KeyboardState, tap_window: Duration) {
+        self.swallowed.push(state.clone());
+        self.deadline = Some(Instant::now() + tap_window);
     }
 
-    pub fn set_global_keyboard_listener<F>(&self, cb: F)
-    where
-        F: Fn(KeyboardInputEvent) + Send + Sync + 'static,
-    {
-        CLIENT_KEYBOARD_CALLBACK.store(Some(Arc::new(Box::new(cb))));
+    fn take(&mut self) -> Vec<KeyboardState> {
+        self.deadline = None;
+        std::mem::take(&mut self.swallowed)
     }
 
-    pub fn remove_global_keyboard_listener(&self) {
-        CLIENT_KEYBOARD_CALLBACK.store(None);
+    fn clear(&mut self) {
+        self.take();
     }
+}
 
-    /// Signals the `HotkeyManager` to pause processing of hotkeys.
-    pub fn pause_handler(&self) -> HotkeysPauseHandler {
-        HotkeysPauseHandler { state: self.paused }
+/// Builds the synthetic events that replay the blocked taps. A trigger key still held is
+/// not released, ex: a [SHIFT] tapped once and held to type a capital letter.
+fn replay_taps(swallowed: &[KeyboardState], state: &KeyboardState) -> Vec<KeyInput> {
+    let mut inputs = sequence::replay_inputs(swallowed, state);
+    let held = swallowed
+        .last()
+        .and_then(|tap| tap.pressing.last())
+        .filter(|key| state.is_down(**key));
+    if let Some(key) = held {
+        if let Some(index) = inputs
+            .iter()
+            .rposition(|input| *input == KeyInput::Up(*key))
+        {
+            inputs.remove(index);
+        }
     }
+    inputs
 }
 
 /// A handle for signaling the `HotkeyManager` to stop processing hotkeys without
@@ -228,13 +1872,13 @@ impl HotkeyManager {
 ///
 /// The `PauseHandle` is used to manage the pause state of the `HotkeyManager`.
 pub struct HotkeysPauseHandler {
-    state: &'static AtomicBool,
+    state: Arc<AtomicBool>,
 }
 
 impl HotkeysPauseHandler {
-    /// Creates a new `PauseHandler` that controls the pause state of the `HotkeyManager`.
+    /// Creates a new `PauseHandler` that controls the pause state of [`HotkeyManager::current`].
     pub fn current() -> Self {
-        Self { state: &PAUSED }
+        HotkeyManager::current().pause_handler()
     }
 
     /// Toggles the pause state of the `HotkeyManager`.
@@ -264,11 +1908,12 @@ impl HotkeyManager {
     /// this functions returns a map of initial hotkeys,
     /// these are no-overridable as they are important system hotkeys
     /// like lock screen and security screen
-    fn get_initial_hotkeys() -> HashMap<VKey, HashSet<Hotkey>> {
+    pub(crate) fn get_initial_hotkeys() -> HashMap<VKey, Vec<Hotkey>> {
         let lock_screen_shortcut = Hotkey::new(VKey::L, [VKey::LWin], || {
             log_on_dev!("Locking screen");
             KEYBOARD_STATE.lock().unwrap().request_syncronization();
         })
+        .id("system.lock-screen")
         .bypass_pause()
         .behavior(TriggerBehavior::PassThrough);
 
@@ -277,12 +1922,1123 @@ impl HotkeyManager {
                 log_on_dev!("Security screen");
                 KEYBOARD_STATE.lock().unwrap().request_syncronization();
             })
+            .id("system.security-screen")
             .bypass_pause()
             .behavior(TriggerBehavior::PassThrough);
 
         let mut hotkeys = HashMap::new();
-        hotkeys.insert(VKey::L, HashSet::from([lock_screen_shortcut]));
-        hotkeys.insert(VKey::Delete, HashSet::from([security_screen_shortcut]));
+        hotkeys.insert(VKey::L, vec![lock_screen_shortcut]);
+        hotkeys.insert(VKey::Delete, vec![security_screen_shortcut]);
         hotkeys
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::backend::testing::ScriptedEngine;
+    use crate::{
+        CaptureEnd, CaptureSession, FilterResult, KeyOutput, KeymapEntry, Propagation,
+        RecordOptions, RecordedShortcut, RejectReason, Scope, WindowContext, DEFAULT_TAP_WINDOW,
+    };
+
+    const WAIT: Duration = Duration::from_secs(1);
+
+    #[test]
+    fn test_trigger_on_release() {
+        let engine = ScriptedEngine::start();
+        let (tx, rx) = crossbeam_channel::unbounded();
+        HotkeyManager::current()
+            .register_hotkey(
+                Hotkey::new(VKey::Space, [VKey::Control], move || {
+                    tx.send(()).unwrap();
+                })
+                .trigger_on(TriggerOn::Release),
+            )
+            .unwrap();
+
+        engine.press(VKey::LControl);
+        assert_eq!(engine.press(VKey::Space), KeyAction::Block);
+        assert!(rx.recv_timeout(Duration::from_millis(50)).is_err());
+        engine.release(VKey::LControl);
+        engine.release(VKey::Space);
+        assert!(rx.recv_timeout(WAIT).is_ok(), "Callback should be executed");
+
+        // releasing the key without pressing the hotkey does nothing
+        engine.chord([VKey::Space]);
+        assert!(rx.recv_timeout(Duration::from_millis(50)).is_err());
+    }
+
+    #[test]
+    fn test_press_release_pair() {
+        let engine = ScriptedEngine::start();
+        let (tx, rx) = crossbeam_channel::unbounded();
+        let tx_release = tx.clone();
+        HotkeyManager::current()
+            .register_hotkey(
+                Hotkey::new(VKey::T, [VKey::Menu], move || {
+                    tx.send("press").unwrap();
+                })
+                .on_release(move || {
+                    tx_release.send("release").unwrap();
+                }),
+            )
+            .unwrap();
+
+        engine.press(VKey::LMenu);
+        assert_eq!(engine.press(VKey::T), KeyAction::Block);
+        // autorepeat doesn't execute the callback again
+        assert_eq!(engine.press(VKey::T), KeyAction::Block);
+        engine.release(VKey::T);
+        engine.release(VKey::LMenu);
+
+        assert_eq!(rx.recv_timeout(WAIT).unwrap(), "press");
+        assert_eq!(rx.recv_timeout(WAIT).unwrap(), "release");
+        assert!(rx.recv_timeout(Duration::from_millis(50)).is_err());
+    }
+
+    #[test]
+    fn test_trigger_on_both() {
+        let engine = ScriptedEngine::start();
+        let (tx, rx) = crossbeam_channel::unbounded();
+        HotkeyManager::current()
+            .register_hotkey(
+                Hotkey::new(VKey::F9, [], move || {
+                    tx.send(()).unwrap();
+                })
+                .trigger_on(TriggerOn::Both)
+                .behavior(TriggerBehavior::PassThrough),
+            )
+            .unwrap();
+
+        assert_eq!(engine.chord([VKey::F9]), vec![KeyAction::Allow]);
+        assert!(rx.recv_timeout(WAIT).is_ok(), "Press should be executed");
+        assert!(rx.recv_timeout(WAIT).is_ok(), "Release should be executed");
+    }
+
+    #[test]
+    fn test_double_tap() {
+        let engine = ScriptedEngine::start();
+        let (tx, rx) = crossbeam_channel::unbounded();
+        HotkeyManager::current()
+            .register_hotkey(
+                Hotkey::new(VKey::LShift, [], move || {
+                    tx.send(()).unwrap();
+                })
+                .taps(2)
+                .pass_through_taps(),
+            )
+            .unwrap();
+
+        assert_eq!(engine.chord([VKey::LShift]), vec![KeyAction::Allow]);
+        engine.advance(Duration::from_millis(100));
+        assert_eq!(engine.chord([VKey::LShift]), vec![KeyAction::Block]);
+        assert!(rx.recv_timeout(WAIT).is_ok(), "Callback should be executed");
+
+        // taps slower than the window start counting again
+        engine.advance(Duration::from_millis(100));
+        engine.chord([VKey::LShift]);
+        engine.advance(DEFAULT_TAP_WINDOW + Duration::from_millis(1));
+        assert_eq!(engine.chord([VKey::LShift]), vec![KeyAction::Allow]);
+        assert!(rx.recv_timeout(Duration::from_millis(50)).is_err());
+    }
+
+    #[test]
+    fn test_taps_are_interrupted_by_other_keys() {
+        let engine = ScriptedEngine::start();
+        let (tx, rx) = crossbeam_channel::unbounded();
+        HotkeyManager::current()
+            .register_hotkey(
+                Hotkey::new(VKey::Q, [VKey::Control], move || {
+                    tx.send(()).unwrap();
+                })
+                .taps(3),
+            )
+            .unwrap();
+
+        engine.press(VKey::LControl);
+        // taps that don't complete the hotkey are blocked by default
+        assert_eq!(engine.press(VKey::Q), KeyAction::Block);
+        // autorepeat is not counted as a tap
+        assert_eq!(engine.press(VKey::Q), KeyAction::Block);
+        engine.release(VKey::Q);
+        engine.chord([VKey::Q]);
+        // the blocked taps are replayed before the interrupting key
+        assert_eq!(engine.chord([VKey::W]), vec![KeyAction::Block]);
+        assert_eq!(
+            engine.take_sent(),
+            vec![
+                KeyInput::Down(VKey::Q),
+                KeyInput::Up(VKey::Q),
+                KeyInput::Down(VKey::Q),
+                KeyInput::Up(VKey::Q),
+                KeyInput::Down(VKey::W),
+            ]
+        );
+        engine.chord([VKey::Q]);
+        assert!(rx.recv_timeout(Duration::from_millis(50)).is_err());
+
+        engine.chord([VKey::Q]);
+        assert_eq!(engine.chord([VKey::Q]), vec![KeyAction::Block]);
+        assert!(rx.recv_timeout(WAIT).is_ok(), "Callback should be executed");
+        assert!(engine.take_sent().is_empty(), "Nothing should be replayed");
+    }
+
+    #[test]
+    fn test_taps_follow_precedence() {
+        let engine = ScriptedEngine::start();
+        let manager = HotkeyManager::current();
+        let (tx, rx) = crossbeam_channel::unbounded();
+        let hotkey = |key, taps, name| {
+            let tx = tx.clone();
+            Hotkey::new(key, [VKey::Control], move || tx.send(name).unwrap()).taps(taps)
+        };
+        manager
+            .register_hotkey(hotkey(VKey::Q, 1, "single"))
+            .unwrap();
+        manager
+            .register_hotkey(hotkey(VKey::Q, 2, "double"))
+            .unwrap();
+        manager
+            .register_hotkey(hotkey(VKey::W, 1, "single").priority(1))
+            .unwrap();
+        manager
+            .register_hotkey(hotkey(VKey::W, 2, "double"))
+            .unwrap();
+
+        // completing a multi-tap hotkey takes precedence over the same keys
+        engine.press(VKey::LControl);
+        engine.chord([VKey::Q]);
+        assert_eq!(rx.recv_timeout(WAIT).unwrap(), "single");
+        engine.chord([VKey::Q]);
+        assert_eq!(rx.recv_timeout(WAIT).unwrap(), "double");
+
+        // but not over a hotkey with higher priority
+        engine.chord([VKey::W]);
+        engine.chord([VKey::W]);
+        assert_eq!(rx.recv_timeout(WAIT).unwrap(), "single");
+        assert_eq!(rx.recv_timeout(WAIT).unwrap(), "single");
+        assert!(rx.recv_timeout(Duration::from_millis(50)).is_err());
+    }
+
+    #[test]
+    fn test_blocked_taps_are_replayed() {
+        let engine = ScriptedEngine::start();
+        let manager = HotkeyManager::current();
+        manager
+            .register_hotkey(
+                Hotkey::new(VKey::Q, [VKey::Control], || {})
+                    .taps(2)
+                    .tap_window(Duration::from_millis(50)),
+            )
+            .unwrap();
+        manager
+            .register_hotkey(Hotkey::new(VKey::LShift, [], || {}).taps(2))
+            .unwrap();
+
+        // a single tap is replayed once the tap window ends
+        engine.press(VKey::LControl);
+        assert_eq!(engine.chord([VKey::Q]), vec![KeyAction::Block]);
+        engine.release(VKey::LControl);
+        assert_eq!(
+            engine.wait_sent(WAIT),
+            vec![
+                KeyInput::Down(VKey::LControl),
+                KeyInput::Down(VKey::Q),
+                KeyInput::Up(VKey::Q),
+                KeyInput::Up(VKey::LControl),
+            ]
+        );
+
+        // a tapped key still held keeps working as a modifier
+        assert_eq!(engine.press(VKey::LShift), KeyAction::Block);
+        assert_eq!(engine.press(VKey::A), KeyAction::Block);
+        assert_eq!(
+            engine.take_sent(),
+            vec![KeyInput::Down(VKey::LShift), KeyInput::Down(VKey::A)]
+        );
+    }
+
+    #[test]
+    fn test_injected_policy() {
+        let engine = ScriptedEngine::start();
+        let (tx, rx) = crossbeam_channel::unbounded();
+        let manager = HotkeyManager::current();
+        manager
+            .register_hotkey(Hotkey::new(VKey::F8, [], move || {
+                tx.send(()).unwrap();
+            }))
+            .unwrap();
+        let injected = |extra_info| KeyEventInfo {
+            injected: true,
+            extra_info,
+            ..engine.event_info()
+        };
+
+        manager.set_injected_policy(InjectedPolicy::Ignore);
+        assert_eq!(engine.press_with(VKey::F8, injected(0)), KeyAction::Allow);
+        engine.release_with(VKey::F8, injected(0));
+        // physical events are still processed
+        assert_eq!(engine.chord([VKey::F8]), vec![KeyAction::Block]);
+        assert!(rx.recv_timeout(WAIT).is_ok(), "Callback should be executed");
+
+        manager.set_injected_policy(InjectedPolicy::ProcessOnlyFrom(0xCAFE));
+        assert_eq!(
+            engine.press_with(VKey::F8, injected(0xBEEF)),
+            KeyAction::Allow
+        );
+        engine.release_with(VKey::F8, injected(0xBEEF));
+        assert!(rx.recv_timeout(Duration::from_millis(50)).is_err());
+        assert_eq!(
+            engine.press_with(VKey::F8, injected(0xCAFE)),
+            KeyAction::Block
+        );
+        engine.release_with(VKey::F8, injected(0xCAFE));
+        assert!(rx.recv_timeout(WAIT).is_ok(), "Callback should be executed");
+
+        // ignored releases still end the pressed hotkeys
+        let (released_tx, released_rx) = crossbeam_channel::unbounded();
+        manager
+            .register_hotkey(Hotkey::new(VKey::F7, [], || {}).on_release(move || {
+                released_tx.send(()).unwrap();
+            }))
+            .unwrap();
+        manager.set_injected_policy(InjectedPolicy::Ignore);
+        assert_eq!(engine.press(VKey::F7), KeyAction::Block);
+        engine.release_with(VKey::F7, injected(0));
+        assert!(
+            released_rx.recv_timeout(WAIT).is_ok(),
+            "Release should be executed"
+        );
+    }
+
+    #[test]
+    fn test_hotkey_emits_keys() {
+        let engine = ScriptedEngine::start();
+        let manager = HotkeyManager::current();
+        manager
+            .register_hotkey(
+                Hotkey::from_keys([VKey::Capital, VKey::H]).emit(KeyOutput::chord([VKey::Left])),
+            )
+            .unwrap();
+
+        engine.press(VKey::Capital);
+        engine.press(VKey::LShift);
+        // the hotkey doesn't match with [SHIFT] held
+        assert_eq!(engine.press(VKey::H), KeyAction::Allow);
+        engine.release(VKey::H);
+        engine.release(VKey::LShift);
+        assert_eq!(engine.press(VKey::H), KeyAction::Block);
+
+        assert_eq!(
+            engine.wait_sent(WAIT),
+            vec![KeyInput::Down(VKey::Left), KeyInput::Up(VKey::Left)]
+        );
+    }
+
+    #[test]
+    fn test_remap_holds_target_key() {
+        let engine = ScriptedEngine::start();
+        HotkeyManager::current()
+            .register_remap(Remap::new(VKey::Capital, VKey::Escape))
+            .unwrap();
+
+        let escape = KeyAction::Substitute(VKey::Escape);
+        assert_eq!(engine.press(VKey::Capital), escape);
+        // autorepeat keeps the remapped key down
+        assert_eq!(engine.press(VKey::Capital), escape);
+        assert!(KEYBOARD_STATE.lock().unwrap().is_down(VKey::Escape));
+        assert_eq!(engine.release(VKey::Capital), escape);
+        assert!(!KEYBOARD_STATE.lock().unwrap().is_down(VKey::Escape));
+        assert_eq!(engine.chord([VKey::A]), vec![KeyAction::Allow]);
+    }
+
+    #[test]
+    fn test_remap_to_modifier_triggers_hotkeys() {
+        let engine = ScriptedEngine::start();
+        let (tx, rx) = crossbeam_channel::unbounded();
+        let manager = HotkeyManager::current();
+        manager
+            .register_remap(Remap::new(VKey::Capital, VKey::LControl))
+            .unwrap();
+        manager
+            .register_remap(Remap::new(VKey::J, VKey::Down).modifiers([VKey::Menu]))
+            .unwrap();
+        manager
+            .register_hotkey(Hotkey::new(VKey::C, [VKey::Control], move || {
+                tx.send(()).unwrap();
+            }))
+            .unwrap();
+
+        assert_eq!(
+            engine.chord([VKey::Capital, VKey::C]),
+            vec![KeyAction::Substitute(VKey::LControl), KeyAction::Block]
+        );
+        assert!(rx.recv_timeout(WAIT).is_ok(), "Callback should be executed");
+
+        // remaps with modifiers only apply while they are held
+        assert_eq!(engine.chord([VKey::J]), vec![KeyAction::Allow]);
+        assert_eq!(
+            engine.chord([VKey::LMenu, VKey::J]),
+            vec![KeyAction::Allow, KeyAction::Substitute(VKey::Down)]
+        );
+    }
+
+    #[test]
+    fn test_layers() {
+        let engine = ScriptedEngine::start();
+        let (tx, rx) = crossbeam_channel::unbounded();
+        let manager = HotkeyManager::current();
+        let tx_default = tx.clone();
+        manager
+            .register_hotkey(Hotkey::new(VKey::H, [VKey::Control], move || {
+                tx_default.send("default").unwrap();
+            }))
+            .unwrap();
+        manager
+            .register_layer_hotkey(
+                "nav",
+                Hotkey::new(VKey::H, [VKey::Control], move || {
+                    tx.send("nav").unwrap();
+                }),
+            )
+            .unwrap();
+        manager
+            .register_hotkey(
+                Hotkey::new(VKey::Capital, [], || {})
+                    .layer_action(LayerAction::Momentary("nav".into())),
+            )
+            .unwrap();
+        manager
+            .register_hotkey(
+                Hotkey::new(VKey::F1, [], || {}).layer_action(LayerAction::Toggle("nav".into())),
+            )
+            .unwrap();
+
+        engine.chord([VKey::LControl, VKey::H]);
+        assert_eq!(rx.recv_timeout(WAIT).unwrap(), "default");
+
+        // the layer is active while [CAPSLOCK] is held
+        engine.press(VKey::Capital);
+        engine.press(VKey::Capital);
+        assert_eq!(manager.active_layers(), vec![DEFAULT_LAYER, "nav"]);
+        engine.chord([VKey::LControl, VKey::H]);
+        assert_eq!(rx.recv_timeout(WAIT).unwrap(), "nav");
+        engine.release(VKey::Capital);
+        assert_eq!(manager.active_layers(), vec![DEFAULT_LAYER]);
+
+        engine.chord([VKey::F1]);
+        assert_eq!(manager.active_layers(), vec![DEFAULT_LAYER, "nav"]);
+        // keys without hotkeys on the layer fall through to the layers below
+        assert_eq!(engine.chord([VKey::F1]), vec![KeyAction::Block]);
+        assert_eq!(manager.pop_layer(), None);
+    }
+
+    #[test]
+    fn test_current_manager_comes_first() {
+        // created before the current manager may be, with the same priority
+        let early = HotkeyManager::new();
+        let engine = ScriptedEngine::start();
+        early.attach();
+        let (tx, rx) = crossbeam_channel::unbounded();
+        let tx_early = tx.clone();
+        early
+            .register_hotkey(Hotkey::new(VKey::H, [VKey::Control], move || {
+                tx_early.send("early").unwrap();
+            }))
+            .unwrap();
+        HotkeyManager::current()
+            .register_hotkey(Hotkey::new(VKey::H, [VKey::Control], move || {
+                tx.send("current").unwrap();
+            }))
+            .unwrap();
+
+        assert_eq!(HotkeyManager::current().id, 0);
+        assert_eq!(
+            engine.chord([VKey::LControl, VKey::H]),
+            vec![KeyAction::Allow, KeyAction::Block]
+        );
+        assert_eq!(rx.recv_timeout(WAIT).unwrap(), "current");
+        early.detach();
+    }
+
+    #[test]
+    fn test_scoped_hotkeys() {
+        let engine = ScriptedEngine::start();
+        let (tx, rx) = crossbeam_channel::unbounded();
+        let manager = HotkeyManager::current();
+        let tx_editor = tx.clone();
+        manager
+            .register_hotkey(
+                Hotkey::new(VKey::B, [VKey::Control], move || {
+                    tx_editor.send("editor").unwrap();
+                })
+                .scope(Scope::processes(["code.exe"])),
+            )
+            .unwrap();
+        manager
+            .register_hotkey(Hotkey::new(VKey::B, [VKey::Control], move || {
+                tx.send("global").unwrap();
+            }))
+            .unwrap();
+        manager
+            .register_hotkey(
+                Hotkey::new(VKey::F5, [], || {}).scope(Scope::except_processes(["game.exe"])),
+            )
+            .unwrap();
+
+        engine.set_foreground_window(Some(WindowContext::new("Code.exe")));
+        engine.chord([VKey::LControl, VKey::B]);
+        assert_eq!(rx.recv_timeout(WAIT).unwrap(), "editor");
+        assert_eq!(engine.chord([VKey::F5]), vec![KeyAction::Block]);
+
+        engine.set_foreground_window(Some(WindowContext::new("game.exe")));
+        engine.chord([VKey::LControl, VKey::B]);
+        assert_eq!(rx.recv_timeout(WAIT).unwrap(), "global");
+        assert_eq!(engine.chord([VKey::F5]), vec![KeyAction::Allow]);
+
+        // without a foreground window only the exclusions match
+        engine.set_foreground_window(None);
+        assert_eq!(engine.chord([VKey::F5]), vec![KeyAction::Block]);
+    }
+
+    #[test]
+    fn test_hotkey_conditions() {
+        let engine = ScriptedEngine::start();
+        let manager = HotkeyManager::current();
+        manager
+            .register_hotkey(Hotkey::new(VKey::J, [], || {}).when(|ctx| ctx.is_caps_lock_on()))
+            .unwrap();
+        manager
+            .register_hotkey(
+                Hotkey::new(VKey::K, [], || {})
+                    .when(|ctx| ctx.flag("recording"))
+                    .when(|ctx| ctx.top_layer() == DEFAULT_LAYER),
+            )
+            .unwrap();
+
+        // unmet conditions don't block the key
+        assert_eq!(engine.chord([VKey::J]), vec![KeyAction::Allow]);
+        engine.chord([VKey::Capital]);
+        assert_eq!(engine.chord([VKey::J]), vec![KeyAction::Block]);
+        engine.chord([VKey::Capital]);
+        assert_eq!(engine.chord([VKey::J]), vec![KeyAction::Allow]);
+
+        assert_eq!(engine.chord([VKey::K]), vec![KeyAction::Allow]);
+        manager.set_flag("recording", true);
+        assert!(manager.flag("recording"));
+        assert_eq!(engine.chord([VKey::K]), vec![KeyAction::Block]);
+        manager.push_layer("nav");
+        assert_eq!(engine.chord([VKey::K]), vec![KeyAction::Allow]);
+        manager.set_flag("recording", false);
+    }
+
+    #[test]
+    fn test_sync_action_decides_propagation() {
+        let engine = ScriptedEngine::start();
+        let manager = HotkeyManager::current();
+        let consume = Arc::new(AtomicBool::new(false));
+        let consume_cb = consume.clone();
+        let (gate, gate_rx) = crossbeam_channel::bounded::<()>(0);
+        manager
+            .register_hotkey(Hotkey::new(VKey::F5, [], || {}).sync_action(move || {
+                if consume_cb.load(Ordering::SeqCst) {
+                    Propagation::Block
+                } else {
+                    Propagation::Allow
+                }
+            }))
+            .unwrap();
+        manager
+            .register_hotkey(
+                Hotkey::new(VKey::F6, [], || {})
+                    .sync_action(move || {
+                        let _ = gate_rx.recv();
+                        Propagation::Allow
+                    })
+                    .sync_budget(Duration::from_millis(20), Propagation::Block),
+            )
+            .unwrap();
+
+        assert_eq!(engine.chord([VKey::F5]), vec![KeyAction::Allow]);
+        consume.store(true, Ordering::SeqCst);
+        assert_eq!(engine.chord([VKey::F5]), vec![KeyAction::Block]);
+
+        // the fallback is used when the callback exceeds its budget
+        assert_eq!(engine.chord([VKey::F6]), vec![KeyAction::Block]);
+        drop(gate);
+
+        // the autorepeats of a hotkey waiting for its release reuse the decision
+        manager
+            .register_hotkey(
+                Hotkey::new(VKey::F7, [], || {})
+                    .sync_action(|| Propagation::Allow)
+                    .on_release(|| {}),
+            )
+            .unwrap();
+        assert_eq!(engine.press(VKey::F7), KeyAction::Allow);
+        assert_eq!(engine.press(VKey::F7), KeyAction::Allow);
+        engine.release(VKey::F7);
+
+        let on_release = Hotkey::new(VKey::F8, [], || {})
+            .sync_action(|| Propagation::Block)
+            .trigger_on(TriggerOn::Release);
+        assert!(matches!(
+            manager.register_hotkey(on_release),
+            Err(WHKError::SyncActionNotOnPress)
+        ));
+    }
+
+    #[test]
+    fn test_sync_budget_is_shared_by_the_managers() {
+        let engine = ScriptedEngine::start();
+        let (gate, gate_rx) = crossbeam_channel::bounded::<()>(0);
+        let high = HotkeyManager::new();
+        high.attach();
+        high.set_priority(10);
+        high.register_hotkey(
+            Hotkey::new(VKey::F5, [], || {})
+                .sync_action(move || {
+                    let _ = gate_rx.recv();
+                    Propagation::Allow
+                })
+                .sync_budget(MAX_SYNC_BUDGET, Propagation::Allow),
+        )
+        .unwrap();
+        HotkeyManager::current()
+            .register_hotkey(
+                Hotkey::new(VKey::F5, [], || {})
+                    .sync_action(|| Propagation::Allow)
+                    .sync_budget(MAX_SYNC_BUDGET, Propagation::Block),
+            )
+            .unwrap();
+
+        // the second callback only has the time left by the first one, so its fallback
+        // is answered before the backend stops waiting for the event
+        assert_eq!(engine.chord([VKey::F5]), vec![KeyAction::Block]);
+        drop(gate);
+    }
+
+    #[test]
+    fn test_sync_deadline_includes_the_queued_time() {
+        let engine = ScriptedEngine::start();
+        let (gate, gate_rx) = crossbeam_channel::bounded::<()>(0);
+        HotkeyManager::current()
+            .register_hotkey(
+                Hotkey::new(VKey::F7, [], || {})
+                    .sync_action(move || {
+                        let _ = gate_rx.recv();
+                        Propagation::Allow
+                    })
+                    .sync_budget(MAX_SYNC_BUDGET, Propagation::Block),
+            )
+            .unwrap();
+
+        // the fallback is answered before the backend stops waiting for the event,
+        // even if the press waited behind a slow one
+        let queued = backend::TIMEOUT - Duration::from_millis(50);
+        assert_eq!(engine.press_queued(VKey::F7, queued), KeyAction::Block);
+        drop(gate);
+        engine.release(VKey::F7);
+    }
+
+    #[test]
+    fn test_filters() {
+        let engine = ScriptedEngine::start();
+        let manager = HotkeyManager::current();
+        let gaming = Arc::new(AtomicBool::new(false));
+        let gaming_filter = gaming.clone();
+        manager.add_filter(10, move |event| {
+            let is_win = VKey::from(event.vk_code()).is_windows_key();
+            if is_win && gaming_filter.load(Ordering::SeqCst) {
+                FilterResult::Block
+            } else {
+                FilterResult::Pass
+            }
+        });
+        let replace_id = manager.add_filter(0, |event| match VKey::from(event.vk_code()) {
+            VKey::Capital => FilterResult::Replace(VKey::Escape),
+            _ => FilterResult::Pass,
+        });
+        manager
+            .register_hotkey(Hotkey::new(VKey::E, [VKey::LWin], || {}))
+            .unwrap();
+
+        assert_eq!(engine.press(VKey::LWin), KeyAction::Allow);
+        assert_eq!(engine.press(VKey::E), KeyAction::Replace);
+        engine.release(VKey::E);
+        engine.release(VKey::LWin);
+
+        gaming.store(true, Ordering::SeqCst);
+        assert_eq!(engine.press(VKey::LWin), KeyAction::Replace);
+        assert_eq!(engine.release(VKey::LWin), KeyAction::Block);
+
+        assert_eq!(
+            engine.press(VKey::Capital),
+            KeyAction::Substitute(VKey::Escape)
+        );
+        assert_eq!(
+            engine.release(VKey::Capital),
+            KeyAction::Substitute(VKey::Escape)
+        );
+
+        manager.remove_filter(replace_id);
+        assert_eq!(engine.chord([VKey::Capital]), vec![KeyAction::Allow]);
+    }
+
+    #[test]
+    fn test_capture_session() {
+        let engine = ScriptedEngine::start();
+        let manager = HotkeyManager::current();
+        let (tx, rx) = crossbeam_channel::unbounded();
+        manager
+            .register_hotkey(Hotkey::new(VKey::A, [VKey::Control], move || {
+                tx.send(()).unwrap();
+            }))
+            .unwrap();
+
+        let handle = manager.start_capture(
+            CaptureSession::new()
+                .exit_chord([VKey::Control, VKey::Q])
+                .pass_through([VKey::VolumeUp]),
+        );
+        assert!(manager.is_stealing_mode());
+        assert_eq!(
+            engine.chord([VKey::LControl, VKey::A]),
+            vec![KeyAction::Block, KeyAction::Block]
+        );
+        assert!(rx.recv_timeout(Duration::from_millis(100)).is_err());
+        assert_eq!(engine.chord([VKey::VolumeUp]), vec![KeyAction::Allow]);
+        // [ESC] is not an exit chord of this session
+        assert_eq!(engine.chord([VKey::Escape]), vec![KeyAction::Block]);
+        assert!(handle.is_active());
+        assert_eq!(
+            engine.chord([VKey::LControl, VKey::Q]),
+            vec![KeyAction::Block, KeyAction::Block]
+        );
+        assert_eq!(handle.end_reason(), Some(CaptureEnd::ExitChord));
+
+        let captured: Vec<(bool, u16)> = handle
+            .into_iter()
+            .map(|event| {
+                let is_down = matches!(event, KeyboardInputEvent::KeyDown { .. });
+                (is_down, event.vk_code())
+            })
+            .collect();
+        let control = VKey::LControl.to_vk_code();
+        assert_eq!(
+            captured,
+            vec![
+                (true, control),
+                (true, VKey::A.to_vk_code()),
+                (false, VKey::A.to_vk_code()),
+                (false, control),
+                (true, VKey::Escape.to_vk_code()),
+                (false, VKey::Escape.to_vk_code()),
+                (true, control),
+            ]
+        );
+
+        engine.chord([VKey::LControl, VKey::A]);
+        rx.recv_timeout(WAIT).unwrap();
+
+        // the session ends on its own once the timeout expires
+        let handle =
+            manager.start_capture(CaptureSession::new().timeout(Duration::from_millis(50)));
+        assert!(handle.events().recv_timeout(WAIT).is_err());
+        assert_eq!(handle.end_reason(), Some(CaptureEnd::Timeout));
+        assert!(!manager.is_stealing_mode());
+    }
+
+    #[test]
+    fn test_record_shortcut() {
+        let engine = ScriptedEngine::start();
+        let manager = HotkeyManager::current();
+        let recorded = manager.record_shortcut(RecordOptions::new());
+
+        engine.chord([VKey::LWin, VKey::L]);
+        assert_eq!(
+            recorded.recv_timeout(WAIT).unwrap(),
+            RecordedShortcut::Rejected {
+                keys: vec![VKey::LWin, VKey::L],
+                reason: RejectReason::Reserved,
+            }
+        );
+        engine.chord([VKey::RShift]);
+        assert!(matches!(
+            recorded.recv_timeout(WAIT).unwrap(),
+            RecordedShortcut::Rejected {
+                reason: RejectReason::ModifierOnly,
+                ..
+            }
+        ));
+
+        // modifiers can be released before the trigger key
+        engine.press(VKey::RControl);
+        engine.press(VKey::K);
+        engine.release(VKey::RControl);
+        engine.release(VKey::K);
+        let shortcut = recorded.recv_timeout(WAIT).unwrap();
+        assert_eq!(
+            shortcut,
+            RecordedShortcut::Chord {
+                trigger_key: VKey::K,
+                modifiers: vec![VKey::Control],
+            }
+        );
+        assert!(recorded.recv_timeout(WAIT).is_err());
+        assert!(!manager.is_stealing_mode());
+
+        let hotkey = shortcut.to_hotkey().unwrap();
+        manager.register_hotkey(hotkey).unwrap();
+        assert_eq!(
+            engine.chord([VKey::LControl, VKey::K]),
+            vec![KeyAction::Allow, KeyAction::Block]
+        );
+
+        let recorded = manager.record_shortcut(RecordOptions::new());
+        engine.chord([VKey::Escape]);
+        assert_eq!(
+            recorded.recv_timeout(WAIT).unwrap(),
+            RecordedShortcut::Cancelled
+        );
+    }
+
+    #[test]
+    fn test_independent_managers() {
+        let engine = ScriptedEngine::start();
+        let (tx, rx) = crossbeam_channel::unbounded();
+        let current = HotkeyManager::current();
+        let tx_current = tx.clone();
+        current
+            .register_hotkey(Hotkey::new(VKey::H, [VKey::Control], move || {
+                tx_current.send("current").unwrap();
+            }))
+            .unwrap();
+
+        let high = HotkeyManager::new();
+        high.attach();
+        high.set_priority(10);
+        high.register_hotkey(Hotkey::new(VKey::H, [VKey::Control], move || {
+            tx.send("high").unwrap();
+        }))
+        .unwrap();
+
+        // the manager with higher priority handles the key press first
+        assert_eq!(
+            engine.chord([VKey::LControl, VKey::H]),
+            vec![KeyAction::Allow, KeyAction::Block]
+        );
+        assert_eq!(rx.recv_timeout(WAIT).unwrap(), "high");
+        assert!(rx.recv_timeout(Duration::from_millis(100)).is_err());
+
+        // pausing a manager doesn't pause the others
+        high.pause_handler().set(true);
+        assert!(!current.pause_handler().is_paused());
+        engine.chord([VKey::LControl, VKey::H]);
+        assert_eq!(rx.recv_timeout(WAIT).unwrap(), "current");
+
+        high.pause_handler().set(false);
+        high.set_priority(-1);
+        engine.chord([VKey::LControl, VKey::H]);
+        assert_eq!(rx.recv_timeout(WAIT).unwrap(), "current");
+
+        high.detach();
+        current.set_priority(-5);
+        engine.chord([VKey::LControl, VKey::H]);
+        assert_eq!(rx.recv_timeout(WAIT).unwrap(), "current");
+
+        // a manager is detached once all its clones are dropped
+        high.set_priority(10);
+        high.attach();
+        drop(high);
+        engine.chord([VKey::LControl, VKey::H]);
+        assert_eq!(rx.recv_timeout(WAIT).unwrap(), "current");
+    }
+
+    #[test]
+    fn test_conflicts() {
+        let manager = HotkeyManager::new();
+        let lctrl_a = manager
+            .register_hotkey(Hotkey::new(VKey::A, [VKey::LControl], || {}))
+            .unwrap();
+        let rctrl_a = manager
+            .register_hotkey(Hotkey::new(VKey::A, [VKey::RControl], || {}))
+            .unwrap();
+        let nav_a = manager
+            .register_layer_hotkey("nav", Hotkey::new(VKey::A, [VKey::Control], || {}))
+            .unwrap();
+        manager
+            .register_hotkey(Hotkey::new(VKey::A, [VKey::Control, VKey::Shift], || {}))
+            .unwrap();
+
+        let conflicts = manager.conflicts();
+        let kinds: Vec<_> = conflicts.iter().map(|conflict| conflict.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ConflictKind::Ambiguous,
+                ConflictKind::Shadowed,
+                ConflictKind::Shadowed
+            ]
+        );
+        assert_eq!(
+            (&conflicts[0].first.id, &conflicts[0].second.id),
+            (&lctrl_a, &rctrl_a)
+        );
+        assert!(conflicts[1..]
+            .iter()
+            .all(|conflict| conflict.first.id == nav_a && conflict.second.layer == DEFAULT_LAYER));
+
+        let warnings = Arc::new(Mutex::new(Vec::new()));
+        let warnings_policy = warnings.clone();
+        manager.set_conflict_policy(ConflictPolicy::warn(move |conflict| {
+            warnings_policy.lock().unwrap().push(conflict.clone());
+        }));
+        manager
+            .register_hotkey(Hotkey::new(VKey::B, [VKey::LShift], || {}))
+            .unwrap();
+        let rshift_b = manager
+            .register_hotkey(Hotkey::new(VKey::B, [VKey::RShift], || {}))
+            .unwrap();
+        let warnings = warnings.lock().unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].first.id, rshift_b);
+
+        // hotkeys with a different precedence are not ambiguous
+        manager.set_conflict_policy(ConflictPolicy::Reject);
+        manager
+            .register_hotkey(
+                Hotkey::new(VKey::A, [VKey::LControl], || {}).scope(Scope::processes(["a.exe"])),
+            )
+            .unwrap();
+        manager
+            .register_layer_hotkey("nav", Hotkey::new(VKey::A, [VKey::RControl], || {}))
+            .unwrap();
+        manager
+            .register_hotkey(Hotkey::new(VKey::B, [VKey::Shift], || {}))
+            .unwrap();
+        assert!(matches!(
+            manager.register_hotkey(Hotkey::new(VKey::A, [VKey::LControl], || {})),
+            Err(HotKeyAlreadyRegistered)
+        ));
+        assert!(matches!(
+            manager.register_layer_hotkey("nav", Hotkey::new(VKey::A, [VKey::LControl], || {})),
+            Err(WHKError::HotkeyConflict(conflict)) if conflict.first.layer == "nav"
+        ));
+        assert!(matches!(
+            manager.register_hotkey(Hotkey::new(VKey::L, [VKey::RWin], || {})),
+            Err(WHKError::HotkeyConflict(_))
+        ));
+        manager
+            .register_hotkey(Hotkey::new(VKey::L, [VKey::RWin], || {}).priority(-1))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_hotkey_priority() {
+        let engine = ScriptedEngine::start();
+        let manager = HotkeyManager::current();
+        let (tx, rx) = crossbeam_channel::unbounded();
+        let register = |name: &'static str, hotkey: Hotkey| {
+            let tx = tx.clone();
+            manager
+                .register_hotkey(hotkey.action(move || tx.send(name).unwrap()))
+                .unwrap();
+        };
+        register("first", Hotkey::new(VKey::P, [VKey::RControl], || {}));
+        register("second", Hotkey::new(VKey::P, [VKey::LControl], || {}));
+
+        // ties are matched in registration order
+        engine.chord([VKey::LControl, VKey::P]);
+        assert_eq!(rx.recv_timeout(WAIT).unwrap(), "first");
+
+        // a higher priority beats more specific modifiers
+        register(
+            "urgent",
+            Hotkey::new(VKey::P, [VKey::Control], || {}).priority(5),
+        );
+        for _ in 0..3 {
+            engine.chord([VKey::RControl, VKey::P]);
+            assert_eq!(rx.recv_timeout(WAIT).unwrap(), "urgent");
+        }
+        assert!(rx.recv_timeout(Duration::from_millis(100)).is_err());
+    }
+
+    #[test]
+    fn test_hotkey_handle() {
+        let engine = ScriptedEngine::start();
+        let manager = HotkeyManager::current();
+        let (tx, rx) = crossbeam_channel::unbounded();
+        let handle = manager
+            .register_hotkey_handle(
+                Hotkey::new(VKey::O, [VKey::Control], move || tx.send(()).unwrap())
+                    .id("editor.open"),
+            )
+            .unwrap();
+        assert_eq!(handle.id(), &HotkeyId::named("editor.open"));
+        assert!(matches!(
+            manager.register_hotkey(Hotkey::new(VKey::P, [], || {}).id("editor.open")),
+            Err(WHKError::HotkeyIdInUse(_))
+        ));
+
+        handle.disable().unwrap();
+        assert!(!handle.is_enabled().unwrap());
+        assert_eq!(
+            engine.chord([VKey::LControl, VKey::O]),
+            vec![KeyAction::Allow, KeyAction::Allow]
+        );
+        handle.enable().unwrap();
+        engine.chord([VKey::LControl, VKey::O]);
+        rx.recv_timeout(WAIT).unwrap();
+
+        handle.rebind(VKey::K, [VKey::Control]).unwrap();
+        assert_eq!(
+            engine.chord([VKey::LControl, VKey::O]),
+            vec![KeyAction::Allow, KeyAction::Allow]
+        );
+        engine.chord([VKey::LControl, VKey::K]);
+        rx.recv_timeout(WAIT).unwrap();
+
+        // a failed rebind keeps the previous keys
+        manager
+            .register_hotkey(Hotkey::new(VKey::J, [VKey::Control], || {}))
+            .unwrap();
+        assert!(matches!(
+            handle.rebind(VKey::J, [VKey::Control]),
+            Err(HotKeyAlreadyRegistered)
+        ));
+        engine.chord([VKey::LControl, VKey::K]);
+        rx.recv_timeout(WAIT).unwrap();
+
+        drop(handle);
+        assert!(matches!(
+            manager.is_hotkey_enabled(&HotkeyId::named("editor.open")),
+            Err(WHKError::UnknownHotkey(_))
+        ));
+        assert_eq!(
+            engine.chord([VKey::LControl, VKey::K]),
+            vec![KeyAction::Allow, KeyAction::Allow]
+        );
+
+        // a handle doesn't control another hotkey registered later with the same id
+        let stale = manager
+            .register_hotkey_handle(Hotkey::new(VKey::O, [VKey::Control], || {}).id("editor.open"))
+            .unwrap();
+        manager.unregister_hotkey(stale.id()).unwrap();
+        manager
+            .register_hotkey(Hotkey::new(VKey::O, [VKey::Control], || {}).id("editor.open"))
+            .unwrap();
+        assert!(matches!(stale.disable(), Err(WHKError::UnknownHotkey(_))));
+        assert!(matches!(
+            stale.rebind(VKey::P, [VKey::Control]),
+            Err(WHKError::UnknownHotkey(_))
+        ));
+        drop(stale);
+        assert!(manager
+            .is_hotkey_enabled(&HotkeyId::named("editor.open"))
+            .unwrap());
+    }
+
+    #[test]
+    fn test_load_keymap() {
+        let engine = ScriptedEngine::start();
+        let manager = HotkeyManager::current();
+        let (tx, rx) = crossbeam_channel::unbounded();
+        manager
+            .register_action("save", move || tx.send("save").unwrap())
+            .unwrap();
+
+        let keymap = Keymap::new()
+            .entry(KeymapEntry::new("Ctrl+Shift+S", "save").id("keymap.save"))
+            .entry(KeymapEntry::new("Ctrl+Foo", "save"))
+            .entry(KeymapEntry::new("Ctrl+Q", "quit"))
+            .entry(KeymapEntry::new("F7", "save").behavior(TriggerBehavior::PassThrough));
+        let report = manager.load_keymap(&keymap).unwrap();
+        assert_eq!(report.registered.len(), 2);
+        assert_eq!(report.registered[0], HotkeyId::named("keymap.save"));
+        let errors: Vec<_> = report.errors.iter().map(|error| error.index).collect();
+        assert_eq!(errors, [1, 2]);
+        assert!(matches!(
+            &report.errors[1].error,
+            WHKError::UnknownAction(action) if action == "quit"
+        ));
+
+        engine.chord([VKey::LControl, VKey::LShift, VKey::S]);
+        assert_eq!(rx.recv_timeout(WAIT).unwrap(), "save");
+        assert_eq!(engine.chord([VKey::F7]), vec![KeyAction::Allow]);
+        assert_eq!(rx.recv_timeout(WAIT).unwrap(), "save");
+    }
+
+    #[test]
+    fn test_reload_keymap() {
+        let engine = ScriptedEngine::start();
+        let manager = HotkeyManager::current();
+        let (tx, rx) = crossbeam_channel::unbounded();
+        manager
+            .register_action("undo", move || tx.send("undo").unwrap())
+            .unwrap();
+        let keymap = Arc::new(Mutex::new(
+            Keymap::new()
+                .entry(KeymapEntry::new("Ctrl+Z", "undo").id("keymap.undo"))
+                .entry(KeymapEntry::new("Ctrl+Y", "undo")),
+        ));
+        let source = keymap.clone();
+        let report = manager
+            .set_keymap_source(move || Ok(source.lock().unwrap().clone()))
+            .unwrap();
+        assert_eq!(report.registered.len(), 2);
+        let undo = HotkeyId::named("keymap.undo");
+        manager.set_hotkey_enabled(&undo, false).unwrap();
+
+        // the unchanged entries keep their hotkey, the others are swapped
+        *keymap.lock().unwrap() = Keymap::new()
+            .entry(KeymapEntry::new("Ctrl+Z", "undo").id("keymap.undo"))
+            .entry(KeymapEntry::new("Ctrl+U", "undo"));
+        let report = manager.reload_keymap().unwrap();
+        assert_eq!(report.kept, [HotkeyId::named("keymap.undo")]);
+        assert_eq!(report.removed.len(), 1);
+        assert_eq!(report.registered.len(), 1);
+        assert!(!manager.is_hotkey_enabled(&undo).unwrap());
+        assert_eq!(
+            engine.chord([VKey::LControl, VKey::Y]),
+            vec![KeyAction::Allow, KeyAction::Allow]
+        );
+        engine.chord([VKey::LControl, VKey::U]);
+        assert_eq!(rx.recv_timeout(WAIT).unwrap(), "undo");
+
+        // a changed entry reuses its id
+        *keymap.lock().unwrap() =
+            Keymap::new().entry(KeymapEntry::new("Ctrl+Shift+Z", "undo").id("keymap.undo"));
+        let report = manager.reload_keymap().unwrap();
+        assert_eq!(report.registered, [HotkeyId::named("keymap.undo")]);
+        assert_eq!(report.removed.len(), 2);
+        assert!(manager.is_hotkey_enabled(&undo).unwrap());
+
+        // an invalid edit keeps the previous hotkey of the entry
+        *keymap.lock().unwrap() =
+            Keymap::new().entry(KeymapEntry::new("Ctrl+Shift+Foo", "undo").id("keymap.undo"));
+        let report = manager.reload_keymap().unwrap();
+        assert_eq!(report.kept, [HotkeyId::named("keymap.undo")]);
+        assert!(report.removed.is_empty());
+        assert_eq!(report.errors.len(), 1);
+        engine.chord([VKey::LControl, VKey::LShift, VKey::Z]);
+        assert_eq!(rx.recv_timeout(WAIT).unwrap(), "undo");
+
+        // the actions and the source are unregistered along with the hotkeys
+        HotkeyManager::current().unregister_all().unwrap();
+        assert!(matches!(
+            manager.reload_keymap(),
+            Err(WHKError::NoKeymapSource)
+        ));
+        let report = manager
+            .load_keymap(&Keymap::new().entry(KeymapEntry::new("Ctrl+Z", "undo")))
+            .unwrap();
+        assert!(matches!(
+            &report.errors[0].error,
+            WHKError::UnknownAction(action) if action == "undo"
+        ));
+    }
+}
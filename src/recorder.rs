@@ -0,0 +1,270 @@
+//! This module defines the shortcut recorder, started with
+//! [`crate::HotkeyManager::record_shortcut`], which captures the keyboard until the user
+//! presses a valid chord, ex: on the "press a shortcut to bind" field of a settings UI.
+//!
+//! The chord is taken when all its keys are released, so the modifiers can be pressed in
+//! any order, and is returned as a [`RecordedShortcut`] ready to become a [`Hotkey`].
+
+use crate::capture::{CaptureHandle, CaptureSession};
+use crate::events::KeyboardInputEvent;
+use crate::hotkey::{self, Hotkey};
+use crate::manager::HotkeyManager;
+use crate::state::KeyboardState;
+use crate::VKey;
+use crossbeam_channel::Sender;
+use std::collections::BTreeSet;
+use std::time::Duration;
+
+/// Why a recorded chord can't be used as a shortcut.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RejectReason {
+    /// The chord only has modifier keys
+    ModifierOnly,
+    /// The chord is reserved by the system or by the options
+    Reserved,
+}
+
+/// Result of a shortcut recording.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RecordedShortcut {
+    /// A valid chord was pressed, the recording ends
+    Chord {
+        trigger_key: VKey,
+        modifiers: Vec<VKey>,
+    },
+    /// The chord pressed can't be used, the recording continues
+    Rejected {
+        keys: Vec<VKey>,
+        reason: RejectReason,
+    },
+    /// The recording ended without chord, by the cancel chord, the timeout or
+    /// [`HotkeyManager::free_keyboard`]
+    Cancelled,
+}
+
+impl RecordedShortcut {
+    /// Returns a hotkey with the recorded keys and no callback,
+    /// `None` if no valid chord was recorded.
+    pub fn to_hotkey(&self) -> Option<Hotkey> {
+        match self {
+            RecordedShortcut::Chord {
+                trigger_key,
+                modifiers,
+            } => Some(Hotkey::new(*trigger_key, modifiers, || {})),
+            _ => None,
+        }
+    }
+}
+
+/// Options of a shortcut recording.
+#[derive(Debug, Clone)]
+pub struct RecordOptions {
+    /// record [CTRL], [SHIFT], [ALT] and [WIN] regardless of the side pressed
+    pub generic_modifiers: bool,
+    /// accept chords without a non-modifier key, ex: [CTRL] + [SHIFT]
+    pub allow_modifier_only: bool,
+    /// chord cancelling the recording
+    pub cancel_chord: Vec<VKey>,
+    /// time after which the recording is cancelled
+    pub timeout: Option<Duration>,
+    /// chords rejected in addition to the system ones, the last key of each one is the trigger key
+    pub reserved: Vec<Vec<VKey>>,
+}
+
+impl Default for RecordOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RecordOptions {
+    /// Creates new `RecordOptions` with generic modifiers, rejecting modifier-only chords
+    /// and cancelled by [ESC].
+    pub fn new() -> Self {
+        Self {
+            generic_modifiers: true,
+            allow_modifier_only: false,
+            cancel_chord: vec![VKey::Escape],
+            timeout: None,
+            reserved: Vec::new(),
+        }
+    }
+
+    /// Records the modifiers with the side pressed, ex: [LCTRL] instead of [CTRL].
+    pub fn keep_modifier_sides(mut self) -> Self {
+        self.generic_modifiers = false;
+        self
+    }
+
+    /// Accepts chords without a non-modifier key, the last modifier pressed is the trigger key.
+    pub fn allow_modifier_only(mut self) -> Self {
+        self.allow_modifier_only = true;
+        self
+    }
+
+    /// Sets the chord cancelling the recording.
+    pub fn cancel_chord<T: AsRef<[VKey]>>(mut self, keys: T) -> Self {
+        self.cancel_chord = keys.as_ref().to_vec();
+        self
+    }
+
+    /// Cancels the recording after the given time.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Rejects the given chord, ex: the shortcuts already used by the application.
+    pub fn reserve<T: AsRef<[VKey]>>(mut self, keys: T) -> Self {
+        self.reserved.push(keys.as_ref().to_vec());
+        self
+    }
+
+    /// Returns the capture session used by the recording.
+    pub(crate) fn capture_session(&self) -> CaptureSession {
+        let session = CaptureSession::new().exit_chord(&self.cancel_chord);
+        match self.timeout {
+            Some(timeout) => session.timeout(timeout),
+            None => session,
+        }
+    }
+
+    /// Decides whether the keys pressed, in order, make a valid shortcut.
+    pub(crate) fn evaluate(&self, keys: &[VKey]) -> RecordedShortcut {
+        let rejected = |reason| RecordedShortcut::Rejected {
+            keys: keys.to_vec(),
+            reason,
+        };
+
+        let mut keys: Vec<VKey> = keys.to_vec();
+        if self.generic_modifiers {
+            for key in &mut keys {
+                *key = generic_modifier(*key);
+            }
+        }
+        let trigger_index = match keys.iter().rposition(|key| !key.is_modifier_key()) {
+            Some(index) => index,
+            None if self.allow_modifier_only => keys.len() - 1,
+            None => return rejected(RejectReason::ModifierOnly),
+        };
+        let trigger_key = keys.remove(trigger_index);
+        let modifiers = keys;
+
+        let mut state = KeyboardState::new();
+        for key in modifiers.iter().chain([&trigger_key]) {
+            state.keydown(*key);
+        }
+        let is_system = HotkeyManager::get_initial_hotkeys()
+            .values()
+            .flatten()
+            .any(|hotkey| hotkey.is_trigger_state(&state));
+        let is_reserved = self.reserved.iter().any(|keys| {
+            let Some((reserved_trigger, reserved_modifiers)) = keys.split_last() else {
+                return false;
+            };
+            let reserved_modifiers: BTreeSet<VKey> = reserved_modifiers.iter().cloned().collect();
+            hotkey::is_trigger_state(*reserved_trigger, &reserved_modifiers, &state)
+        });
+        if is_system || is_reserved {
+            return rejected(RejectReason::Reserved);
+        }
+
+        RecordedShortcut::Chord {
+            trigger_key,
+            modifiers,
+        }
+    }
+}
+
+/// Returns the side-less version of a modifier key, [WIN] is always the left one.
+fn generic_modifier(key: VKey) -> VKey {
+    match key {
+        VKey::LControl | VKey::RControl => VKey::Control,
+        VKey::LShift | VKey::RShift => VKey::Shift,
+        VKey::LMenu | VKey::RMenu => VKey::Menu,
+        VKey::RWin => VKey::LWin,
+        key => key,
+    }
+}
+
+/// Evaluates the chords captured until a valid one is pressed or the capture ends.
+pub(crate) fn record(
+    options: RecordOptions,
+    capture: CaptureHandle,
+    results: Sender<RecordedShortcut>,
+) {
+    let mut chord = Vec::new();
+    for event in &capture {
+        match event {
+            KeyboardInputEvent::KeyDown { state, .. } => chord = state.pressing,
+            KeyboardInputEvent::KeyUp { state, .. } => {
+                // the chord is complete once all its keys are released
+                if !state.pressing.is_empty() || chord.is_empty() {
+                    continue;
+                }
+                let recorded = options.evaluate(&std::mem::take(&mut chord));
+                let is_chord = matches!(recorded, RecordedShortcut::Chord { .. });
+                if results.send(recorded).is_err() || is_chord {
+                    capture.stop();
+                    return;
+                }
+            }
+        }
+    }
+    let _ = results.send(RecordedShortcut::Cancelled);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_evaluate_chords() {
+        let options = RecordOptions::new().reserve([VKey::Control, VKey::S]);
+        assert_eq!(
+            options.evaluate(&[VKey::RControl, VKey::LShift, VKey::K]),
+            RecordedShortcut::Chord {
+                trigger_key: VKey::K,
+                modifiers: vec![VKey::Control, VKey::Shift],
+            }
+        );
+        assert_eq!(
+            options
+                .clone()
+                .keep_modifier_sides()
+                .evaluate(&[VKey::RControl, VKey::K]),
+            RecordedShortcut::Chord {
+                trigger_key: VKey::K,
+                modifiers: vec![VKey::RControl],
+            }
+        );
+
+        let rejected = |keys: &[VKey], reason| RecordedShortcut::Rejected {
+            keys: keys.to_vec(),
+            reason,
+        };
+        let keys = [VKey::LControl, VKey::LShift];
+        assert_eq!(
+            options.evaluate(&keys),
+            rejected(&keys, RejectReason::ModifierOnly)
+        );
+        assert_eq!(
+            options.clone().allow_modifier_only().evaluate(&keys),
+            RecordedShortcut::Chord {
+                trigger_key: VKey::Shift,
+                modifiers: vec![VKey::Control],
+            }
+        );
+
+        for keys in [
+            [VKey::RWin, VKey::L].as_slice(),
+            &[VKey::LControl, VKey::RMenu, VKey::Delete],
+            &[VKey::RControl, VKey::S],
+        ] {
+            assert_eq!(
+                options.evaluate(keys),
+                rejected(keys, RejectReason::Reserved)
+            );
+        }
+    }
+}
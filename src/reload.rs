@@ -0,0 +1,190 @@
+//! This module defines the sources a [`Keymap`] is reloaded from, with
+//! [`HotkeyManager::reload_keymap`] or automatically when it changes with
+//! [`HotkeyManager::watch_keymap`], ex: to apply the edits of a configuration file
+//! without restarting the application.
+
+use crate::error::{Result, WHKError};
+use crate::keymap::{Keymap, KeymapReport};
+use crate::manager::HotkeyManager;
+use crossbeam_channel::{RecvTimeoutError, Sender};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+type ParseKeymap = dyn Fn(&str) -> std::result::Result<Keymap, String> + Send + Sync + 'static;
+
+/// Provides the keymap of a [`HotkeyManager`], see [`HotkeyManager::set_keymap_source`].
+///
+/// Closures returning a `Result<Keymap>` are sources too, never reloaded by the watcher.
+pub trait KeymapSource: Send + Sync + 'static {
+    /// Reads the keymap.
+    fn load(&self) -> Result<Keymap>;
+
+    /// Returns when the keymap was last modified, the watcher reloads it when this changes.
+    /// `None` if unknown.
+    fn modified(&self) -> Option<SystemTime> {
+        None
+    }
+}
+
+impl<F> KeymapSource for F
+where
+    F: Fn() -> Result<Keymap> + Send + Sync + 'static,
+{
+    fn load(&self) -> Result<Keymap> {
+        self()
+    }
+}
+
+/// A keymap read from a file, in any format parsed by the given function,
+/// ex: `toml::from_str` with the `serde` feature.
+#[derive(Clone)]
+pub struct KeymapFile {
+    path: PathBuf,
+    parse: Arc<ParseKeymap>,
+}
+
+impl KeymapFile {
+    /// Creates a new `KeymapFile` parsing the file at the given path.
+    pub fn new<P, F, E>(path: P, parse: F) -> Self
+    where
+        P: AsRef<Path>,
+        F: Fn(&str) -> std::result::Result<Keymap, E> + Send + Sync + 'static,
+        E: fmt::Display,
+    {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            parse: Arc::new(move |text| parse(text).map_err(|err| err.to_string())),
+        }
+    }
+
+    /// Returns the path of the file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl KeymapSource for KeymapFile {
+    fn load(&self) -> Result<Keymap> {
+        let error = |message| WHKError::KeymapSource(format!("{}: {message}", self.path.display()));
+        let text = std::fs::read_to_string(&self.path).map_err(|err| error(err.to_string()))?;
+        (self.parse)(&text).map_err(error)
+    }
+
+    fn modified(&self) -> Option<SystemTime> {
+        std::fs::metadata(&self.path).ok()?.modified().ok()
+    }
+}
+
+impl fmt::Debug for KeymapFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeymapFile")
+            .field("path", &self.path)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Watcher returned by [`HotkeyManager::watch_keymap`], stops polling the keymap source
+/// when dropped.
+#[derive(Debug)]
+pub struct KeymapWatcher {
+    _stop: Sender<()>,
+}
+
+impl KeymapWatcher {
+    /// Polls the keymap source of the manager at the given interval, reloading it when its
+    /// modification time changes.
+    pub(crate) fn start<F>(manager: HotkeyManager, interval: Duration, on_reload: F) -> Self
+    where
+        F: Fn(Result<KeymapReport>) + Send + 'static,
+    {
+        let (stop, stopped) = crossbeam_channel::bounded::<()>(0);
+        let mut last_modified = manager.keymap_modified();
+        std::thread::spawn(move || {
+            // the sender is never used, the channel is disconnected when the watcher is dropped
+            while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+                let modified = manager.keymap_modified();
+                if modified.is_some() && modified != last_modified {
+                    last_modified = modified;
+                    on_reload(manager.reload_keymap());
+                }
+            }
+        });
+        Self { _stop: stop }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hotkey::HotkeyId;
+    use crate::keymap::KeymapEntry;
+
+    #[test]
+    fn test_keymap_file() {
+        let path = std::env::temp_dir().join(format!("whk-keymap-{}.txt", std::process::id()));
+        // one entry per line: keys and action
+        let file = KeymapFile::new(&path, |text: &str| {
+            text.lines()
+                .map(|line| match line.split_once(' ') {
+                    Some((keys, action)) => Ok(KeymapEntry::new(keys, action)),
+                    None => Err(format!("invalid line `{line}`")),
+                })
+                .collect::<std::result::Result<Vec<_>, _>>()
+                .map(|hotkeys| Keymap { hotkeys })
+        });
+        assert!(matches!(file.load(), Err(WHKError::KeymapSource(_))));
+        assert_eq!(file.modified(), None);
+
+        std::fs::write(&path, "Ctrl+S save\nF5 reload").unwrap();
+        assert_eq!(
+            file.load().unwrap(),
+            Keymap::new()
+                .entry(KeymapEntry::new("Ctrl+S", "save"))
+                .entry(KeymapEntry::new("F5", "reload"))
+        );
+        assert!(file.modified().is_some());
+
+        std::fs::write(&path, "Ctrl+S").unwrap();
+        let err = file.load().unwrap_err();
+        assert!(err.to_string().ends_with("invalid line `Ctrl+S`"));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_watch_keymap() {
+        let manager = HotkeyManager::new();
+        manager.register_action("save", || {}).unwrap();
+
+        let path = std::env::temp_dir().join(format!("whk-watch-{}.txt", std::process::id()));
+        std::fs::write(&path, "Ctrl+S").unwrap();
+        let report = manager
+            .set_keymap_source(KeymapFile::new(&path, |keys: &str| {
+                Ok::<_, String>(Keymap::new().entry(KeymapEntry::new(keys, "save").id("save")))
+            }))
+            .unwrap();
+        assert_eq!(report.registered.len(), 1);
+
+        let (tx, rx) = crossbeam_channel::unbounded();
+        let watcher = manager.watch_keymap(Duration::from_millis(10), move |report| {
+            tx.send(report).unwrap();
+        });
+        std::fs::write(&path, "Ctrl+Foo").unwrap();
+        let file = std::fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(10))
+            .unwrap();
+        let report = rx.recv_timeout(Duration::from_secs(1)).unwrap().unwrap();
+        // the hotkey of the invalid entry is kept
+        assert_eq!(report.kept, vec![HotkeyId::named("save")]);
+        assert!(report.removed.is_empty());
+        assert_eq!(report.errors.len(), 1);
+
+        drop(watcher);
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(
+            manager.reload_keymap(),
+            Err(WHKError::KeymapSource(_))
+        ));
+    }
+}
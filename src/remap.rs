@@ -0,0 +1,147 @@
+//! This module defines the `Remap` struct, which translates a physical key into another
+//! key before it reaches the applications, ex: [CAPSLOCK] to [ESC].
+//!
+//! While the source key is held the remapped key stays down, autorepeat included,
+//! and the keyboard state seen by the hotkeys contains the remapped key instead of the
+//! source one, so remapping a key to a modifier works with the registered hotkeys.
+
+use crate::hotkey;
+use crate::state::{KeyboardState, KEYBOARD_STATE};
+use crate::VKey;
+use std::collections::{BTreeSet, HashMap};
+use std::hash::{Hash, Hasher};
+use std::sync::Mutex;
+
+/// Translates a key into another one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Remap {
+    /// physical key to remap
+    pub from: VKey,
+    /// keys that must be held for the remap to apply, if empty the remap applies
+    /// regardless of the held modifiers. These are not released, so the remapped key
+    /// is received with them.
+    pub modifiers: BTreeSet<VKey>,
+    /// key sent instead of the physical one
+    pub to: VKey,
+    /// will ignore the `paused` global state
+    pub bypass_pause: bool,
+    /// identifies the remap once registered
+    pub(crate) id: u64,
+}
+
+impl Remap {
+    /// Creates a new `Remap` sending `to` when `from` is pressed.
+    pub fn new(from: VKey, to: VKey) -> Self {
+        Self {
+            from,
+            modifiers: BTreeSet::new(),
+            to,
+            bypass_pause: false,
+            id: 0,
+        }
+    }
+
+    /// Sets the keys that must be held for the remap to apply.
+    pub fn modifiers<T: AsRef<[VKey]>>(mut self, keys: T) -> Self {
+        self.modifiers = keys.as_ref().iter().cloned().collect();
+        self
+    }
+
+    /// Makes the remap work even when global hotkeys are paused
+    pub fn bypass_pause(mut self) -> Self {
+        self.bypass_pause = true;
+        self
+    }
+
+    /// Returns whether the remap applies to the given keyboard state.
+    pub fn is_trigger_state(&self, state: &KeyboardState) -> bool {
+        self.modifiers.is_empty() || hotkey::is_trigger_state(self.from, &self.modifiers, state)
+    }
+
+    /// Returns a hash representing the remapped key combination
+    pub fn as_hash(&self) -> u64 {
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        self.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+impl Hash for Remap {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.from.hash(state);
+        self.modifiers.hash(state);
+    }
+}
+
+/// Tracks the remapped keys held for the remaps of a [`crate::HotkeyManager`].
+#[derive(Debug, Default)]
+pub(crate) struct RemapMatcher {
+    /// source key => remapped key
+    active: Mutex<HashMap<VKey, VKey>>,
+}
+
+impl RemapMatcher {
+    /// Feeds a key press to the remap table, returns the key to send instead, if remapped.
+    pub(crate) fn on_key_down(
+        &self,
+        remaps: &HashMap<VKey, Vec<Remap>>,
+        vk_code: u16,
+        state: &KeyboardState,
+        paused: bool,
+    ) -> Option<VKey> {
+        let pressed = VKey::from(vk_code);
+        let mut active = self.active.lock().unwrap();
+
+        let target = match active.get(&pressed) {
+            // autorepeat of a remapped key
+            Some(target) => *target,
+            None => {
+                // remaps with modifiers take precedence over the plain ones
+                let remap = remaps
+                    .get(&pressed)?
+                    .iter()
+                    .filter(|remap| !paused || remap.bypass_pause)
+                    .filter(|remap| remap.is_trigger_state(state))
+                    .max_by_key(|remap| remap.modifiers.len())?;
+                active.insert(pressed, remap.to);
+                remap.to
+            }
+        };
+
+        let mut state = KEYBOARD_STATE.lock().unwrap();
+        state.keyup(pressed);
+        state.keydown(target);
+        Some(target)
+    }
+
+    /// Holds `target` instead of the pressed key until it is released,
+    /// ex: when a filter replaces the key.
+    pub(crate) fn hold(&self, vk_code: u16, target: VKey) {
+        let pressed = VKey::from(vk_code);
+        self.active.lock().unwrap().insert(pressed, target);
+        let mut state = KEYBOARD_STATE.lock().unwrap();
+        state.keyup(pressed);
+        state.keydown(target);
+    }
+
+    /// Feeds a key release to the remap table, returns the key to release instead, if remapped.
+    pub(crate) fn on_key_up(&self, vk_code: u16) -> Option<VKey> {
+        let target = self.active.lock().unwrap().remove(&VKey::from(vk_code))?;
+        KEYBOARD_STATE.lock().unwrap().keyup(target);
+        Some(target)
+    }
+
+    /// Returns whether the key is pressed as another key, its release is replaced.
+    pub(crate) fn is_held(&self, vk_code: u16) -> bool {
+        self.active
+            .lock()
+            .unwrap()
+            .contains_key(&VKey::from(vk_code))
+    }
+
+    /// Forgets the held remapped keys, returns the keys that must be released.
+    pub(crate) fn cancel(&self) -> Vec<VKey> {
+        let mut active = self.active.lock().unwrap();
+        active.drain().map(|(_, target)| target).collect()
+    }
+}
@@ -0,0 +1,186 @@
+//! This module defines the `Scope` of a hotkey, which restricts it to some applications
+//! depending on the foreground window, ex: only in `code.exe`, or everywhere except games.
+//!
+//! The foreground window is described by a [`WindowContext`], provided by the active
+//! [`crate::backend::InputBackend`] when a scoped hotkey is about to trigger.
+
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+/// Information about the foreground window, used to evaluate the hotkey scopes.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WindowContext {
+    /// executable file name of the process owning the window, ex: `code.exe`
+    pub process_name: String,
+    /// class name of the window, ex: `Chrome_WidgetWin_1`
+    pub window_class: String,
+    /// title of the window
+    pub title: String,
+}
+
+impl WindowContext {
+    /// Creates a new `WindowContext` for a window of the given process.
+    pub fn new<S: Into<String>>(process_name: S) -> Self {
+        Self {
+            process_name: process_name.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Sets the class name of the window.
+    pub fn window_class<S: Into<String>>(mut self, window_class: S) -> Self {
+        self.window_class = window_class.into();
+        self
+    }
+
+    /// Sets the title of the window.
+    pub fn title<S: Into<String>>(mut self, title: S) -> Self {
+        self.title = title.into();
+        self
+    }
+}
+
+type ScopePredicate = Arc<dyn Fn(&WindowContext) -> bool + Send + Sync + 'static>;
+
+/// Restricts a hotkey to the foreground windows matching a condition.
+///
+/// Process names and window classes are compared ignoring ASCII case.
+#[derive(Clone)]
+pub enum Scope {
+    /// The foreground window belongs to one of the given executables
+    Processes(Vec<String>),
+    /// The foreground window doesn't belong to any of the given executables
+    ExceptProcesses(Vec<String>),
+    /// The foreground window has one of the given classes
+    WindowClasses(Vec<String>),
+    /// The foreground window matches a custom predicate
+    Custom(ScopePredicate),
+}
+
+impl Scope {
+    /// Creates a `Scope` matching the windows of the given executables.
+    pub fn processes<I, S>(names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Scope::Processes(names.into_iter().map(Into::into).collect())
+    }
+
+    /// Creates a `Scope` matching all windows except the ones of the given executables.
+    pub fn except_processes<I, S>(names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Scope::ExceptProcesses(names.into_iter().map(Into::into).collect())
+    }
+
+    /// Creates a `Scope` matching the windows with one of the given classes.
+    pub fn window_classes<I, S>(classes: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Scope::WindowClasses(classes.into_iter().map(Into::into).collect())
+    }
+
+    /// Creates a `Scope` matching the windows for which the predicate returns `true`.
+    pub fn custom<F>(predicate: F) -> Self
+    where
+        F: Fn(&WindowContext) -> bool + Send + Sync + 'static,
+    {
+        Scope::Custom(Arc::new(predicate))
+    }
+
+    /// Returns whether the given foreground window is in this scope.
+    pub fn matches(&self, context: &WindowContext) -> bool {
+        let contains = |names: &[String], value: &str| {
+            names.iter().any(|name| name.eq_ignore_ascii_case(value))
+        };
+        match self {
+            Scope::Processes(names) => contains(names, &context.process_name),
+            Scope::ExceptProcesses(names) => !contains(names, &context.process_name),
+            Scope::WindowClasses(classes) => contains(classes, &context.window_class),
+            Scope::Custom(predicate) => predicate(context),
+        }
+    }
+}
+
+impl fmt::Debug for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Scope::Processes(names) => f.debug_tuple("Processes").field(names).finish(),
+            Scope::ExceptProcesses(names) => f.debug_tuple("ExceptProcesses").field(names).finish(),
+            Scope::WindowClasses(classes) => f.debug_tuple("WindowClasses").field(classes).finish(),
+            Scope::Custom(_) => f.debug_tuple("Custom").field(&"<predicate>").finish(),
+        }
+    }
+}
+
+impl Eq for Scope {}
+impl PartialEq for Scope {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Scope::Processes(a), Scope::Processes(b))
+            | (Scope::ExceptProcesses(a), Scope::ExceptProcesses(b))
+            | (Scope::WindowClasses(a), Scope::WindowClasses(b)) => a == b,
+            // predicates are only equal to themselves
+            (Scope::Custom(a), Scope::Custom(b)) => Arc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+}
+
+impl Hash for Scope {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            Scope::Processes(names) | Scope::ExceptProcesses(names) => names.hash(state),
+            Scope::WindowClasses(classes) => classes.hash(state),
+            Scope::Custom(predicate) => (Arc::as_ptr(predicate) as *const () as usize).hash(state),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scope_matches() {
+        let editor = WindowContext::new("Code.exe").window_class("Chrome_WidgetWin_1");
+        let game = WindowContext::new("game.exe").title("Some Game");
+
+        let only_editor = Scope::processes(["code.exe"]);
+        assert!(only_editor.matches(&editor));
+        assert!(!only_editor.matches(&game));
+
+        let except_games = Scope::except_processes(["game.exe", "other-game.exe"]);
+        assert!(except_games.matches(&editor));
+        assert!(!except_games.matches(&game));
+        assert!(except_games.matches(&WindowContext::default()));
+
+        let chromium = Scope::window_classes(["chrome_widgetwin_1"]);
+        assert!(chromium.matches(&editor));
+        assert!(!chromium.matches(&game));
+
+        let titled = Scope::custom(|context| context.title.contains("Game"));
+        assert!(titled.matches(&game));
+        assert!(!titled.matches(&editor));
+    }
+
+    #[test]
+    fn test_scope_identity() {
+        assert_eq!(Scope::processes(["a.exe"]), Scope::processes(["a.exe"]));
+        assert_ne!(
+            Scope::processes(["a.exe"]),
+            Scope::except_processes(["a.exe"])
+        );
+
+        let custom = Scope::custom(|_| true);
+        assert_eq!(custom, custom.clone());
+        assert_ne!(custom, Scope::custom(|_| true));
+    }
+}
@@ -0,0 +1,180 @@
+//! This module defines the `KeySender`, which sends synthetic keyboard input to the system,
+//! and `KeyOutput`, the keys that a hotkey can emit when triggered, ex: to remap
+//! `CapsLock + H` to the left arrow.
+
+use crate::backend::{self, KeyInput, SILENT_VK_CODE};
+use crate::state::{KeyboardState, KEYBOARD_STATE};
+use crate::VKey;
+
+/// Keyboard output that can be sent by a [`KeySender`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyOutput {
+    /// Keys pressed in order and released in reverse order, ex: [CTRL] + [C]
+    Chord(Vec<VKey>),
+    /// Chords sent one after another
+    Sequence(Vec<Vec<VKey>>),
+    /// Text typed independently of the keyboard layout
+    Text(String),
+}
+
+impl KeyOutput {
+    /// Creates a `KeyOutput` pressing the given keys together.
+    pub fn chord<T: AsRef<[VKey]>>(keys: T) -> Self {
+        KeyOutput::Chord(keys.as_ref().to_vec())
+    }
+
+    /// Creates a `KeyOutput` sending the given chords one after another.
+    pub fn sequence<C, T>(chords: C) -> Self
+    where
+        C: IntoIterator<Item = T>,
+        T: AsRef<[VKey]>,
+    {
+        KeyOutput::Sequence(
+            chords
+                .into_iter()
+                .map(|keys| keys.as_ref().to_vec())
+                .collect(),
+        )
+    }
+
+    /// Creates a `KeyOutput` typing the given text.
+    pub fn text<S: Into<String>>(text: S) -> Self {
+        KeyOutput::Text(text.into())
+    }
+}
+
+/// Sends synthetic keyboard input to the system.
+///
+/// The modifiers held by the user are released before sending the output and
+/// pressed again afterwards, so they don't alter it. Events sent by a `KeySender`
+/// never trigger hotkeys.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct KeySender;
+
+impl KeySender {
+    /// Creates a new `KeySender`.
+    pub fn new() -> Self {
+        KeySender
+    }
+
+    /// Presses the given keys in order and releases them in reverse order.
+    pub fn send_chord<T: AsRef<[VKey]>>(&self, keys: T) {
+        self.send(&KeyOutput::chord(keys));
+    }
+
+    /// Sends the given chords one after another.
+    pub fn send_sequence<C, T>(&self, chords: C)
+    where
+        C: IntoIterator<Item = T>,
+        T: AsRef<[VKey]>,
+    {
+        self.send(&KeyOutput::sequence(chords));
+    }
+
+    /// Types the given text, independently of the keyboard layout.
+    pub fn send_text(&self, text: &str) {
+        self.send(&KeyOutput::text(text));
+    }
+
+    /// Sends the given output.
+    pub fn send(&self, output: &KeyOutput) {
+        let state = KEYBOARD_STATE.lock().unwrap().clone();
+        backend::send_input(&output_inputs(output, &state));
+    }
+}
+
+/// Generates the key events of an output, wrapped by the release and
+/// restoration of the modifiers held on the given state.
+pub(crate) fn output_inputs(output: &KeyOutput, state: &KeyboardState) -> Vec<KeyInput> {
+    let held: Vec<VKey> = state
+        .pressing
+        .iter()
+        .filter(|key| key.is_modifier_key())
+        .cloned()
+        .collect();
+
+    let mut inputs = Vec::new();
+    // a Windows Key released alone would open the start menu
+    if held.iter().any(|key| key.is_windows_key()) {
+        let silent = VKey::from(SILENT_VK_CODE);
+        inputs.extend([KeyInput::Down(silent), KeyInput::Up(silent)]);
+    }
+    inputs.extend(held.iter().rev().map(|key| KeyInput::Up(*key)));
+
+    match output {
+        KeyOutput::Chord(keys) => push_chord(&mut inputs, keys),
+        KeyOutput::Sequence(chords) => {
+            for keys in chords {
+                push_chord(&mut inputs, keys);
+            }
+        }
+        KeyOutput::Text(text) => inputs.extend(text.chars().map(KeyInput::Char)),
+    }
+
+    inputs.extend(held.iter().map(|key| KeyInput::Down(*key)));
+    inputs
+}
+
+fn push_chord(inputs: &mut Vec<KeyInput>, keys: &[VKey]) {
+    inputs.extend(keys.iter().map(|key| KeyInput::Down(*key)));
+    inputs.extend(keys.iter().rev().map(|key| KeyInput::Up(*key)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_output_without_held_modifiers() {
+        let state = KeyboardState::new();
+        assert_eq!(
+            output_inputs(&KeyOutput::chord([VKey::Control, VKey::C]), &state),
+            vec![
+                KeyInput::Down(VKey::Control),
+                KeyInput::Down(VKey::C),
+                KeyInput::Up(VKey::C),
+                KeyInput::Up(VKey::Control),
+            ]
+        );
+        assert_eq!(
+            output_inputs(&KeyOutput::text("hé"), &state),
+            vec![KeyInput::Char('h'), KeyInput::Char('é')]
+        );
+    }
+
+    #[test]
+    fn test_held_modifiers_are_restored() {
+        let mut state = KeyboardState::new();
+        state.keydown(VKey::LShift);
+        state.keydown(VKey::Capital);
+        state.keydown(VKey::H);
+        assert_eq!(
+            output_inputs(&KeyOutput::sequence([[VKey::Left], [VKey::Home]]), &state),
+            vec![
+                KeyInput::Up(VKey::LShift),
+                KeyInput::Down(VKey::Left),
+                KeyInput::Up(VKey::Left),
+                KeyInput::Down(VKey::Home),
+                KeyInput::Up(VKey::Home),
+                KeyInput::Down(VKey::LShift),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_windows_key_is_released_silently() {
+        let mut state = KeyboardState::new();
+        state.keydown(VKey::LWin);
+        let silent = VKey::from(SILENT_VK_CODE);
+        assert_eq!(
+            output_inputs(&KeyOutput::text("a"), &state),
+            vec![
+                KeyInput::Down(silent),
+                KeyInput::Up(silent),
+                KeyInput::Up(VKey::LWin),
+                KeyInput::Char('a'),
+                KeyInput::Down(VKey::LWin),
+            ]
+        );
+    }
+}
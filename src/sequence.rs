@@ -0,0 +1,618 @@
+//! This module defines the `HotkeySequence` struct, a hotkey made of several chords
+//! that must be pressed one after another, like `Ctrl+K, Ctrl+C` on code editors.
+//!
+//! While the first chords of a sequence are pressed, they are swallowed. If the
+//! sequence is aborted (a key that doesn't continue it or the timeout expires)
+//! the swallowed chords are replayed so no input is lost.
+
+use crate::backend::KeyInput;
+use crate::client_executor::run_on_executor_thread;
+use crate::events::KeyAction;
+use crate::hotkey::{self, TriggerBehavior};
+use crate::state::KeyboardState;
+use crate::VKey;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Max time allowed between two chords of a sequence, if not configured.
+pub const DEFAULT_SEQUENCE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// A single step of a [`HotkeySequence`], a trigger key plus its modifiers.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Chord {
+    /// key that must be pressed to complete this chord
+    pub trigger_key: VKey,
+    /// keys that must be pressed before the trigger key ex: [CTRL] + [K]
+    pub modifiers: BTreeSet<VKey>,
+}
+
+impl Chord {
+    /// Creates a new `Chord` instance.
+    pub fn new<M: AsRef<[VKey]>>(trigger_key: VKey, modifiers: M) -> Self {
+        Self {
+            trigger_key,
+            modifiers: modifiers.as_ref().iter().cloned().collect(),
+        }
+    }
+
+    /// last key is used as trigger
+    pub fn from_keys<T: AsRef<[VKey]>>(keys: T) -> Self {
+        let mut keys: Vec<VKey> = keys.as_ref().to_vec();
+        let trigger_key = keys.pop().unwrap_or(VKey::None);
+        Self::new(trigger_key, keys)
+    }
+
+    /// Checks if current keyboard state completes this chord.
+    pub fn is_trigger_state(&self, state: &KeyboardState) -> bool {
+        hotkey::is_trigger_state(self.trigger_key, &self.modifiers, state)
+    }
+}
+
+/// Represents an ordered list of chords that triggers an action, ex: [CTRL + K] then [CTRL + C]
+pub struct HotkeySequence {
+    /// chords that must be pressed in order
+    pub chords: Vec<Chord>,
+    /// max time allowed between two chords
+    pub timeout: Duration,
+    /// what happens with the key event of the last chord
+    pub behaviour: TriggerBehavior,
+    /// will ignore the `paused` global state
+    pub bypass_pause: bool,
+    /// callback function to execute when the whole sequence is pressed
+    pub callback: Arc<Box<dyn Fn() + Send + Sync + 'static>>,
+    /// identifies the sequence once registered
+    pub(crate) id: u64,
+}
+
+impl HotkeySequence {
+    /// Creates a new `HotkeySequence` instance.
+    pub fn new<I, F>(chords: I, callback: F) -> HotkeySequence
+    where
+        I: IntoIterator<Item = Chord>,
+        F: Fn() + Send + Sync + 'static,
+    {
+        Self {
+            chords: chords.into_iter().collect(),
+            timeout: DEFAULT_SEQUENCE_TIMEOUT,
+            behaviour: TriggerBehavior::StopPropagation,
+            bypass_pause: false,
+            callback: Arc::new(Box::new(callback)),
+            id: 0,
+        }
+    }
+
+    /// Sets the max time allowed between two chords
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Sets the behavior of the last chord when the sequence triggers
+    pub fn behavior(mut self, action: TriggerBehavior) -> Self {
+        self.behaviour = action;
+        self
+    }
+
+    /// Makes the sequence work even when global hotkeys are paused
+    pub fn bypass_pause(mut self) -> Self {
+        self.bypass_pause = true;
+        self
+    }
+
+    pub fn action<F>(mut self, action: F) -> Self
+    where
+        F: Fn() + Send + Sync + 'static,
+    {
+        self.callback = Arc::new(Box::new(action));
+        self
+    }
+
+    /// Returns whether one of the sequences is the start of the other.
+    pub fn overlaps(&self, other: &HotkeySequence) -> bool {
+        self.chords
+            .iter()
+            .zip(&other.chords)
+            .all(|(chord, other)| chord == other)
+    }
+
+    /// Returns a hash representing the chords of the sequence
+    pub fn as_hash(&self) -> u64 {
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        self.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+impl fmt::Debug for HotkeySequence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HotkeySequence")
+            .field("chords", &self.chords)
+            .field("timeout", &self.timeout)
+            .field("trigger_action", &self.behaviour)
+            .field("callback", &"<callback>")
+            .finish()
+    }
+}
+
+impl Eq for HotkeySequence {}
+impl PartialEq for HotkeySequence {
+    fn eq(&self, other: &Self) -> bool {
+        self.chords == other.chords
+    }
+}
+
+impl Hash for HotkeySequence {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.chords.hash(state);
+    }
+}
+
+/// Outcome of feeding a key press to the sequence matcher.
+#[derive(Debug)]
+pub(crate) enum SequenceStep {
+    /// the key is not part of any sequence
+    Unrelated,
+    /// the key advanced or completed a sequence, and must be handled with the action
+    Matched(KeyAction),
+    /// the key broke the pending sequence, the swallowed chords must be replayed
+    Aborted(Vec<KeyboardState>),
+    /// the key broke the pending sequence and started another one, the swallowed chords
+    /// must be replayed and the key handled with the action
+    Restarted(Vec<KeyboardState>, KeyAction),
+}
+
+/// Tracks the sequence prefix typed so far for the sequences of a [`crate::HotkeyManager`].
+#[derive(Debug, Default)]
+pub(crate) struct SequenceMatcher {
+    pending: Mutex<Option<PendingSequence>>,
+}
+
+/// A sequence prefix that was already typed.
+#[derive(Debug)]
+struct PendingSequence {
+    /// ids of the sequences that start with the typed prefix
+    candidates: Vec<u64>,
+    /// number of chords typed
+    progress: usize,
+    /// when the sequence is aborted if no chord is pressed
+    deadline: Instant,
+    /// keyboard states of the swallowed chords
+    swallowed: Vec<KeyboardState>,
+    /// last swallowed trigger key while it is held, to swallow its autorepeat
+    held: Option<u16>,
+}
+
+impl SequenceMatcher {
+    /// Feeds a key press to the sequence matcher.
+    pub(crate) fn on_key_down(
+        &self,
+        sequences: &[HotkeySequence],
+        vk_code: u16,
+        state: &KeyboardState,
+        paused: bool,
+    ) -> SequenceStep {
+        let mut pending = self.pending.lock().unwrap();
+        let Some(mut current) = pending.take() else {
+            return match start(sequences, vk_code, state, paused) {
+                Some((next, action)) => {
+                    *pending = next;
+                    SequenceStep::Matched(action)
+                }
+                None => SequenceStep::Unrelated,
+            };
+        };
+
+        if Instant::now() >= current.deadline {
+            return restart(&mut pending, current, sequences, vk_code, state, paused);
+        }
+
+        // autorepeat of the last swallowed key
+        if current.held == Some(vk_code) {
+            *pending = Some(current);
+            return SequenceStep::Matched(TriggerBehavior::StopPropagation.key_action(state));
+        }
+
+        let matches: Vec<&HotkeySequence> = sequences
+            .iter()
+            .filter(|sequence| current.candidates.contains(&sequence.id))
+            .filter(|sequence| sequence.chords[current.progress].is_trigger_state(state))
+            .collect();
+
+        if matches.is_empty() {
+            // modifiers are pressed before the next chord
+            if VKey::from(vk_code).is_modifier_key() {
+                *pending = Some(current);
+                return SequenceStep::Unrelated;
+            }
+            return restart(&mut pending, current, sequences, vk_code, state, paused);
+        }
+
+        let swallowed = std::mem::take(&mut current.swallowed);
+        let (next, action) = advance(&matches, current.progress, vk_code, state, swallowed);
+        *pending = next;
+        SequenceStep::Matched(action)
+    }
+
+    /// Feeds a key release to the sequence matcher.
+    pub(crate) fn on_key_up(&self, vk_code: u16) {
+        if let Some(pending) = self.pending.lock().unwrap().as_mut() {
+            if pending.held == Some(vk_code) {
+                pending.held = None;
+            }
+        }
+    }
+
+    /// Returns when the pending sequence will expire, if any.
+    pub(crate) fn pending_deadline(&self) -> Option<Instant> {
+        self.pending.lock().unwrap().as_ref().map(|p| p.deadline)
+    }
+
+    /// Aborts the pending sequence if its deadline passed, returning the swallowed chords.
+    pub(crate) fn expire(&self) -> Option<Vec<KeyboardState>> {
+        let mut pending = self.pending.lock().unwrap();
+        if pending.as_ref()?.deadline > Instant::now() {
+            return None;
+        }
+        pending.take().map(|p| p.swallowed)
+    }
+
+    /// Drops the pending sequence without replaying it.
+    pub(crate) fn cancel(&self) {
+        self.pending.lock().unwrap().take();
+    }
+}
+
+/// Starts the sequences whose first chord is pressed, if any.
+fn start(
+    sequences: &[HotkeySequence],
+    vk_code: u16,
+    state: &KeyboardState,
+    paused: bool,
+) -> Option<(Option<PendingSequence>, KeyAction)> {
+    let matches: Vec<&HotkeySequence> = sequences
+        .iter()
+        .filter(|sequence| !paused || sequence.bypass_pause)
+        .filter(|sequence| {
+            sequence
+                .chords
+                .first()
+                .is_some_and(|chord| chord.is_trigger_state(state))
+        })
+        .collect();
+    if matches.is_empty() {
+        return None;
+    }
+    Some(advance(&matches, 0, vk_code, state, Vec::new()))
+}
+
+/// Aborts the pending sequence, the key that aborted it can start another one.
+fn restart(
+    pending: &mut Option<PendingSequence>,
+    aborted: PendingSequence,
+    sequences: &[HotkeySequence],
+    vk_code: u16,
+    state: &KeyboardState,
+    paused: bool,
+) -> SequenceStep {
+    match start(sequences, vk_code, state, paused) {
+        Some((next, action)) => {
+            *pending = next;
+            SequenceStep::Restarted(aborted.swallowed, action)
+        }
+        None => SequenceStep::Aborted(aborted.swallowed),
+    }
+}
+
+/// Completes the matching sequence or keeps waiting for the next chord.
+fn advance(
+    matches: &[&HotkeySequence],
+    progress: usize,
+    vk_code: u16,
+    state: &KeyboardState,
+    mut swallowed: Vec<KeyboardState>,
+) -> (Option<PendingSequence>, KeyAction) {
+    if let Some(sequence) = matches.iter().find(|s| s.chords.len() == progress + 1) {
+        run_on_executor_thread(sequence.callback.clone());
+        return (None, sequence.behaviour.key_action(state));
+    }
+
+    swallowed.push(state.clone());
+    let timeout = matches.iter().map(|s| s.timeout).max().unwrap_or_default();
+    let pending = PendingSequence {
+        candidates: matches.iter().map(|s| s.id).collect(),
+        progress: progress + 1,
+        deadline: Instant::now() + timeout,
+        swallowed,
+        held: Some(vk_code),
+    };
+    (
+        Some(pending),
+        TriggerBehavior::StopPropagation.key_action(state),
+    )
+}
+
+/// Builds the synthetic events that replay the swallowed chords, pressing
+/// again the modifiers of each chord that are no longer held.
+pub(crate) fn replay_inputs(swallowed: &[KeyboardState], current: &KeyboardState) -> Vec<KeyInput> {
+    let mut inputs = Vec::new();
+    for chord in swallowed {
+        let Some(trigger) = chord.pressing.last().copied() else {
+            continue;
+        };
+        let missing: Vec<VKey> = chord
+            .pressing
+            .iter()
+            .filter(|key| **key != trigger && key.is_modifier_key() && !current.is_down(**key))
+            .copied()
+            .collect();
+        inputs.extend(missing.iter().map(|key| KeyInput::Down(*key)));
+        inputs.push(KeyInput::Down(trigger));
+        inputs.push(KeyInput::Up(trigger));
+        inputs.extend(missing.iter().rev().map(|key| KeyInput::Up(*key)));
+    }
+    inputs
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::backend::testing::ScriptedEngine;
+    use crate::error::WHKError;
+    use crate::{Hotkey, HotkeyManager};
+
+    const WAIT: Duration = Duration::from_secs(1);
+
+    fn ctrl(key: VKey) -> Chord {
+        Chord::new(key, [VKey::Control])
+    }
+
+    #[test]
+    fn test_sequence_triggers() {
+        let engine = ScriptedEngine::start();
+        let (tx, rx) = crossbeam_channel::unbounded();
+        HotkeyManager::current()
+            .register_sequence(HotkeySequence::new(
+                [ctrl(VKey::K), ctrl(VKey::C)],
+                move || {
+                    tx.send(()).unwrap();
+                },
+            ))
+            .unwrap();
+
+        engine.press(VKey::LControl);
+        assert_eq!(engine.press(VKey::K), KeyAction::Block);
+        engine.release(VKey::K);
+        assert!(rx.recv_timeout(Duration::from_millis(50)).is_err());
+        assert_eq!(engine.press(VKey::C), KeyAction::Block);
+        engine.release(VKey::C);
+        engine.release(VKey::LControl);
+
+        assert!(rx.recv_timeout(WAIT).is_ok(), "Callback should be executed");
+        assert!(engine.take_sent().is_empty(), "Nothing should be replayed");
+    }
+
+    #[test]
+    fn test_sequence_swallows_autorepeat() {
+        let engine = ScriptedEngine::start();
+        let (tx, rx) = crossbeam_channel::unbounded();
+        HotkeyManager::current()
+            .register_sequence(HotkeySequence::new(
+                [ctrl(VKey::K), ctrl(VKey::U)],
+                move || {
+                    tx.send(()).unwrap();
+                },
+            ))
+            .unwrap();
+
+        engine.press(VKey::LControl);
+        assert_eq!(engine.press(VKey::K), KeyAction::Block);
+        assert_eq!(engine.press(VKey::K), KeyAction::Block);
+        engine.release(VKey::K);
+        assert_eq!(engine.press(VKey::U), KeyAction::Block);
+        assert!(rx.recv_timeout(WAIT).is_ok(), "Callback should be executed");
+    }
+
+    #[test]
+    fn test_sequence_abort_replays_swallowed_keys() {
+        let engine = ScriptedEngine::start();
+        HotkeyManager::current()
+            .register_sequence(HotkeySequence::new([ctrl(VKey::K), ctrl(VKey::C)], || {}))
+            .unwrap();
+
+        engine.press(VKey::LControl);
+        assert_eq!(engine.press(VKey::K), KeyAction::Block);
+        engine.release(VKey::K);
+        engine.release(VKey::LControl);
+
+        // the aborting key is blocked and sent again after the replayed chord
+        assert_eq!(engine.press(VKey::X), KeyAction::Block);
+        assert_eq!(
+            engine.take_sent(),
+            vec![
+                KeyInput::Down(VKey::LControl),
+                KeyInput::Down(VKey::K),
+                KeyInput::Up(VKey::K),
+                KeyInput::Up(VKey::LControl),
+                KeyInput::Down(VKey::X),
+            ]
+        );
+        engine.release(VKey::X);
+        assert_eq!(engine.press(VKey::X), KeyAction::Allow);
+    }
+
+    #[test]
+    fn test_prefix_sequences_are_rejected() {
+        let manager = HotkeyManager::new();
+        manager
+            .register_sequence(HotkeySequence::new([ctrl(VKey::K), ctrl(VKey::C)], || {}))
+            .unwrap();
+        for chords in [
+            vec![ctrl(VKey::K)],
+            vec![ctrl(VKey::K), ctrl(VKey::C), ctrl(VKey::D)],
+        ] {
+            assert!(matches!(
+                manager.register_sequence(HotkeySequence::new(chords, || {})),
+                Err(WHKError::SequencePrefixConflict)
+            ));
+        }
+        manager
+            .register_sequence(HotkeySequence::new([ctrl(VKey::K), ctrl(VKey::U)], || {}))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_sequence_ids_are_not_reused() {
+        let engine = ScriptedEngine::start();
+        let manager = HotkeyManager::current();
+        let sequence = || HotkeySequence::new([ctrl(VKey::K), ctrl(VKey::C)], || {});
+        let id = manager.register_sequence(sequence()).unwrap();
+        manager.unregister_sequence(id).unwrap();
+        let new_id = manager.register_sequence(sequence()).unwrap();
+        assert_ne!(id, new_id);
+
+        // the previous id no longer unregisters the sequence
+        manager.unregister_sequence(id).unwrap();
+        engine.press(VKey::LControl);
+        assert_eq!(engine.press(VKey::K), KeyAction::Block);
+    }
+
+    #[test]
+    fn test_sequence_abort_runs_hotkey() {
+        let engine = ScriptedEngine::start();
+        let (tx, rx) = crossbeam_channel::unbounded();
+        let manager = HotkeyManager::current();
+        manager
+            .register_sequence(HotkeySequence::new([ctrl(VKey::K), ctrl(VKey::C)], || {}))
+            .unwrap();
+        manager
+            .register_hotkey(Hotkey::new(VKey::S, [VKey::Control], move || {
+                tx.send(()).unwrap();
+            }))
+            .unwrap();
+
+        engine.press(VKey::LControl);
+        assert_eq!(engine.press(VKey::K), KeyAction::Block);
+        engine.release(VKey::K);
+        assert_eq!(engine.press(VKey::S), KeyAction::Block);
+        assert!(rx.recv_timeout(WAIT).is_ok(), "Hotkey should be executed");
+        assert_eq!(
+            engine.take_sent(),
+            vec![KeyInput::Down(VKey::K), KeyInput::Up(VKey::K)]
+        );
+    }
+
+    #[test]
+    fn test_sequence_restarts_on_aborting_key() {
+        let engine = ScriptedEngine::start();
+        let (tx, rx) = crossbeam_channel::unbounded();
+        HotkeyManager::current()
+            .register_sequence(HotkeySequence::new(
+                [ctrl(VKey::K), ctrl(VKey::C)],
+                move || {
+                    tx.send(()).unwrap();
+                },
+            ))
+            .unwrap();
+
+        engine.press(VKey::LControl);
+        assert_eq!(engine.press(VKey::K), KeyAction::Block);
+        engine.release(VKey::K);
+        // aborts the sequence, which starts again with the same key
+        assert_eq!(engine.press(VKey::K), KeyAction::Block);
+        engine.release(VKey::K);
+        assert_eq!(
+            engine.take_sent(),
+            vec![KeyInput::Down(VKey::K), KeyInput::Up(VKey::K)]
+        );
+        assert_eq!(engine.press(VKey::C), KeyAction::Block);
+        assert!(rx.recv_timeout(WAIT).is_ok(), "Callback should be executed");
+    }
+
+    #[test]
+    fn test_sequence_timeout_replays_swallowed_keys() {
+        let engine = ScriptedEngine::start();
+        let (tx, rx) = crossbeam_channel::unbounded();
+        HotkeyManager::current()
+            .register_sequence(
+                HotkeySequence::new(
+                    [Chord::new(VKey::G, []), Chord::new(VKey::G, [])],
+                    move || {
+                        tx.send(()).unwrap();
+                    },
+                )
+                .timeout(Duration::from_millis(50)),
+            )
+            .unwrap();
+
+        assert_eq!(engine.chord([VKey::G]), vec![KeyAction::Block]);
+        assert_eq!(
+            engine.wait_sent(WAIT),
+            vec![KeyInput::Down(VKey::G), KeyInput::Up(VKey::G)]
+        );
+
+        // the sequence can be started again
+        assert_eq!(engine.chord([VKey::G]), vec![KeyAction::Block]);
+        assert_eq!(engine.chord([VKey::G]), vec![KeyAction::Block]);
+        assert!(rx.recv_timeout(WAIT).is_ok(), "Callback should be executed");
+    }
+
+    #[test]
+    fn test_sequences_sharing_prefix() {
+        let engine = ScriptedEngine::start();
+        let (tx, rx) = crossbeam_channel::unbounded();
+        let manager = HotkeyManager::current();
+        let tx_c = tx.clone();
+        manager
+            .register_sequence(HotkeySequence::new(
+                [ctrl(VKey::K), ctrl(VKey::C)],
+                move || {
+                    tx_c.send("comment").unwrap();
+                },
+            ))
+            .unwrap();
+        manager
+            .register_sequence(HotkeySequence::new(
+                [ctrl(VKey::K), ctrl(VKey::U)],
+                move || {
+                    tx.send("uncomment").unwrap();
+                },
+            ))
+            .unwrap();
+
+        assert_eq!(
+            engine.chord([VKey::LControl, VKey::K]),
+            vec![KeyAction::Allow, KeyAction::Block]
+        );
+        assert_eq!(
+            engine.chord([VKey::LControl, VKey::U]),
+            vec![KeyAction::Allow, KeyAction::Block]
+        );
+        assert_eq!(rx.recv_timeout(WAIT).unwrap(), "uncomment");
+    }
+
+    #[test]
+    fn test_replay_inputs() {
+        let mut chord = KeyboardState::new();
+        chord.keydown(VKey::LControl);
+        chord.keydown(VKey::LShift);
+        chord.keydown(VKey::K);
+
+        let mut current = KeyboardState::new();
+        current.keydown(VKey::LShift);
+
+        assert_eq!(
+            replay_inputs(&[chord], &current),
+            vec![
+                KeyInput::Down(VKey::LControl),
+                KeyInput::Down(VKey::K),
+                KeyInput::Up(VKey::K),
+                KeyInput::Up(VKey::LControl),
+            ]
+        );
+    }
+}
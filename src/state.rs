@@ -3,8 +3,7 @@
 
 use std::sync::{Arc, LazyLock, Mutex};
 
-use crate::{log_on_dev, VKey};
-use windows::Win32::UI::Input::KeyboardAndMouse::GetAsyncKeyState;
+use crate::{backend, log_on_dev, VKey};
 
 /// this is an arbitrary number, on local tests it don't need more than 3, but we use 10 just to be sure
 const SYNC_COUNT_NEEDED_TO_BE_CONSIDERATED_SAFE: u8 = 10;
@@ -18,10 +17,15 @@ pub(crate) static KEYBOARD_STATE: LazyLock<Arc<Mutex<KeyboardState>>> = LazyLock
 /// Represents a state of pressed keys on a keyboard.
 /// Can be used to track the current state of the keyboard
 /// or to represent a keyboard state for hotkeys.
+///
+/// With the `serde` feature, only the pressed keys are serialized.
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct KeyboardState {
     pub pressing: Vec<VKey>,
+    #[cfg_attr(feature = "serde", serde(skip))]
     needs_sync: bool,
+    #[cfg_attr(feature = "serde", serde(skip))]
     sync_count: u8,
 }
 
@@ -104,11 +108,10 @@ impl KeyboardState {
         }
     }
 
-    /// Returns whether a key is currently pressed according to the OS.
+    /// Returns whether a key is currently pressed according to the OS
+    /// (or the active [`crate::backend::InputBackend`]).
     pub fn async_is_key_down(key: u16) -> bool {
-        let state: i16 = unsafe { GetAsyncKeyState(key.into()) };
-        // Check if the high-order bit is set (on intergers this bit is set if the value is negative)
-        state < 0
+        backend::async_is_key_down(key)
     }
 }
 
@@ -229,3 +232,31 @@ mod tests {
         );
     }
 }
+
+#[cfg(all(test, feature = "serde"))]
+mod serde_tests {
+    use super::*;
+    use serde_test::{assert_tokens, Token};
+
+    #[test]
+    fn test_serialize_deserialize_state() {
+        let mut state = KeyboardState::new();
+        state.keydown(VKey::LShift);
+        state.keydown(VKey::Tab);
+        assert_tokens(
+            &state,
+            &[
+                Token::Struct {
+                    name: "KeyboardState",
+                    len: 1,
+                },
+                Token::Str("pressing"),
+                Token::Seq { len: Some(2) },
+                Token::Str("LShift"),
+                Token::Str("Tab"),
+                Token::SeqEnd,
+                Token::StructEnd,
+            ],
+        );
+    }
+}
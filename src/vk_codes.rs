@@ -0,0 +1,174 @@
+//! Windows Virtual-Key codes as plain integers, so the key table can be built on
+//! every platform (the `windows` crate is only available when targeting Windows).
+//!
+//! # See Also
+//! - [Microsoft Virtual-Key Codes](https://learn.microsoft.com/en-us/windows/win32/inputdev/virtual-key-codes)
+#![allow(non_upper_case_globals)]
+
+pub(crate) const VK_BACK: u16 = 0x08;
+pub(crate) const VK_TAB: u16 = 0x09;
+pub(crate) const VK_CLEAR: u16 = 0x0C;
+pub(crate) const VK_RETURN: u16 = 0x0D;
+pub(crate) const VK_SHIFT: u16 = 0x10;
+pub(crate) const VK_CONTROL: u16 = 0x11;
+pub(crate) const VK_MENU: u16 = 0x12;
+pub(crate) const VK_PAUSE: u16 = 0x13;
+pub(crate) const VK_CAPITAL: u16 = 0x14;
+pub(crate) const VK_KANA: u16 = 0x15;
+pub(crate) const VK_IME_ON: u16 = 0x16;
+pub(crate) const VK_JUNJA: u16 = 0x17;
+pub(crate) const VK_FINAL: u16 = 0x18;
+pub(crate) const VK_HANJA: u16 = 0x19;
+pub(crate) const VK_IME_OFF: u16 = 0x1A;
+pub(crate) const VK_ESCAPE: u16 = 0x1B;
+pub(crate) const VK_CONVERT: u16 = 0x1C;
+pub(crate) const VK_NONCONVERT: u16 = 0x1D;
+pub(crate) const VK_ACCEPT: u16 = 0x1E;
+pub(crate) const VK_MODECHANGE: u16 = 0x1F;
+pub(crate) const VK_SPACE: u16 = 0x20;
+pub(crate) const VK_PRIOR: u16 = 0x21;
+pub(crate) const VK_NEXT: u16 = 0x22;
+pub(crate) const VK_END: u16 = 0x23;
+pub(crate) const VK_HOME: u16 = 0x24;
+pub(crate) const VK_LEFT: u16 = 0x25;
+pub(crate) const VK_UP: u16 = 0x26;
+pub(crate) const VK_RIGHT: u16 = 0x27;
+pub(crate) const VK_DOWN: u16 = 0x28;
+pub(crate) const VK_SELECT: u16 = 0x29;
+pub(crate) const VK_PRINT: u16 = 0x2A;
+pub(crate) const VK_EXECUTE: u16 = 0x2B;
+pub(crate) const VK_SNAPSHOT: u16 = 0x2C;
+pub(crate) const VK_INSERT: u16 = 0x2D;
+pub(crate) const VK_DELETE: u16 = 0x2E;
+pub(crate) const VK_HELP: u16 = 0x2F;
+pub(crate) const VK_0: u16 = 0x30;
+pub(crate) const VK_1: u16 = 0x31;
+pub(crate) const VK_2: u16 = 0x32;
+pub(crate) const VK_3: u16 = 0x33;
+pub(crate) const VK_4: u16 = 0x34;
+pub(crate) const VK_5: u16 = 0x35;
+pub(crate) const VK_6: u16 = 0x36;
+pub(crate) const VK_7: u16 = 0x37;
+pub(crate) const VK_8: u16 = 0x38;
+pub(crate) const VK_9: u16 = 0x39;
+pub(crate) const VK_A: u16 = 0x41;
+pub(crate) const VK_B: u16 = 0x42;
+pub(crate) const VK_C: u16 = 0x43;
+pub(crate) const VK_D: u16 = 0x44;
+pub(crate) const VK_E: u16 = 0x45;
+pub(crate) const VK_F: u16 = 0x46;
+pub(crate) const VK_G: u16 = 0x47;
+pub(crate) const VK_H: u16 = 0x48;
+pub(crate) const VK_I: u16 = 0x49;
+pub(crate) const VK_J: u16 = 0x4A;
+pub(crate) const VK_K: u16 = 0x4B;
+pub(crate) const VK_L: u16 = 0x4C;
+pub(crate) const VK_M: u16 = 0x4D;
+pub(crate) const VK_N: u16 = 0x4E;
+pub(crate) const VK_O: u16 = 0x4F;
+pub(crate) const VK_P: u16 = 0x50;
+pub(crate) const VK_Q: u16 = 0x51;
+pub(crate) const VK_R: u16 = 0x52;
+pub(crate) const VK_S: u16 = 0x53;
+pub(crate) const VK_T: u16 = 0x54;
+pub(crate) const VK_U: u16 = 0x55;
+pub(crate) const VK_V: u16 = 0x56;
+pub(crate) const VK_W: u16 = 0x57;
+pub(crate) const VK_X: u16 = 0x58;
+pub(crate) const VK_Y: u16 = 0x59;
+pub(crate) const VK_Z: u16 = 0x5A;
+pub(crate) const VK_LWIN: u16 = 0x5B;
+pub(crate) const VK_RWIN: u16 = 0x5C;
+pub(crate) const VK_APPS: u16 = 0x5D;
+pub(crate) const VK_SLEEP: u16 = 0x5F;
+pub(crate) const VK_NUMPAD0: u16 = 0x60;
+pub(crate) const VK_NUMPAD1: u16 = 0x61;
+pub(crate) const VK_NUMPAD2: u16 = 0x62;
+pub(crate) const VK_NUMPAD3: u16 = 0x63;
+pub(crate) const VK_NUMPAD4: u16 = 0x64;
+pub(crate) const VK_NUMPAD5: u16 = 0x65;
+pub(crate) const VK_NUMPAD6: u16 = 0x66;
+pub(crate) const VK_NUMPAD7: u16 = 0x67;
+pub(crate) const VK_NUMPAD8: u16 = 0x68;
+pub(crate) const VK_NUMPAD9: u16 = 0x69;
+pub(crate) const VK_MULTIPLY: u16 = 0x6A;
+pub(crate) const VK_ADD: u16 = 0x6B;
+pub(crate) const VK_SEPARATOR: u16 = 0x6C;
+pub(crate) const VK_SUBTRACT: u16 = 0x6D;
+pub(crate) const VK_DECIMAL: u16 = 0x6E;
+pub(crate) const VK_DIVIDE: u16 = 0x6F;
+pub(crate) const VK_F1: u16 = 0x70;
+pub(crate) const VK_F2: u16 = 0x71;
+pub(crate) const VK_F3: u16 = 0x72;
+pub(crate) const VK_F4: u16 = 0x73;
+pub(crate) const VK_F5: u16 = 0x74;
+pub(crate) const VK_F6: u16 = 0x75;
+pub(crate) const VK_F7: u16 = 0x76;
+pub(crate) const VK_F8: u16 = 0x77;
+pub(crate) const VK_F9: u16 = 0x78;
+pub(crate) const VK_F10: u16 = 0x79;
+pub(crate) const VK_F11: u16 = 0x7A;
+pub(crate) const VK_F12: u16 = 0x7B;
+pub(crate) const VK_F13: u16 = 0x7C;
+pub(crate) const VK_F14: u16 = 0x7D;
+pub(crate) const VK_F15: u16 = 0x7E;
+pub(crate) const VK_F16: u16 = 0x7F;
+pub(crate) const VK_F17: u16 = 0x80;
+pub(crate) const VK_F18: u16 = 0x81;
+pub(crate) const VK_F19: u16 = 0x82;
+pub(crate) const VK_F20: u16 = 0x83;
+pub(crate) const VK_F21: u16 = 0x84;
+pub(crate) const VK_F22: u16 = 0x85;
+pub(crate) const VK_F23: u16 = 0x86;
+pub(crate) const VK_F24: u16 = 0x87;
+pub(crate) const VK_NUMLOCK: u16 = 0x90;
+pub(crate) const VK_SCROLL: u16 = 0x91;
+pub(crate) const VK_LSHIFT: u16 = 0xA0;
+pub(crate) const VK_RSHIFT: u16 = 0xA1;
+pub(crate) const VK_LCONTROL: u16 = 0xA2;
+pub(crate) const VK_RCONTROL: u16 = 0xA3;
+pub(crate) const VK_LMENU: u16 = 0xA4;
+pub(crate) const VK_RMENU: u16 = 0xA5;
+pub(crate) const VK_BROWSER_BACK: u16 = 0xA6;
+pub(crate) const VK_BROWSER_FORWARD: u16 = 0xA7;
+pub(crate) const VK_BROWSER_REFRESH: u16 = 0xA8;
+pub(crate) const VK_BROWSER_STOP: u16 = 0xA9;
+pub(crate) const VK_BROWSER_SEARCH: u16 = 0xAA;
+pub(crate) const VK_BROWSER_FAVORITES: u16 = 0xAB;
+pub(crate) const VK_BROWSER_HOME: u16 = 0xAC;
+pub(crate) const VK_VOLUME_MUTE: u16 = 0xAD;
+pub(crate) const VK_VOLUME_DOWN: u16 = 0xAE;
+pub(crate) const VK_VOLUME_UP: u16 = 0xAF;
+pub(crate) const VK_MEDIA_NEXT_TRACK: u16 = 0xB0;
+pub(crate) const VK_MEDIA_PREV_TRACK: u16 = 0xB1;
+pub(crate) const VK_MEDIA_STOP: u16 = 0xB2;
+pub(crate) const VK_MEDIA_PLAY_PAUSE: u16 = 0xB3;
+pub(crate) const VK_LAUNCH_MAIL: u16 = 0xB4;
+pub(crate) const VK_LAUNCH_MEDIA_SELECT: u16 = 0xB5;
+pub(crate) const VK_LAUNCH_APP1: u16 = 0xB6;
+pub(crate) const VK_LAUNCH_APP2: u16 = 0xB7;
+pub(crate) const VK_OEM_1: u16 = 0xBA;
+pub(crate) const VK_OEM_PLUS: u16 = 0xBB;
+pub(crate) const VK_OEM_COMMA: u16 = 0xBC;
+pub(crate) const VK_OEM_MINUS: u16 = 0xBD;
+pub(crate) const VK_OEM_PERIOD: u16 = 0xBE;
+pub(crate) const VK_OEM_2: u16 = 0xBF;
+pub(crate) const VK_OEM_3: u16 = 0xC0;
+pub(crate) const VK_OEM_4: u16 = 0xDB;
+pub(crate) const VK_OEM_5: u16 = 0xDC;
+pub(crate) const VK_OEM_6: u16 = 0xDD;
+pub(crate) const VK_OEM_7: u16 = 0xDE;
+pub(crate) const VK_OEM_8: u16 = 0xDF;
+pub(crate) const VK_OEM_102: u16 = 0xE2;
+pub(crate) const VK_PROCESSKEY: u16 = 0xE5;
+pub(crate) const VK_PACKET: u16 = 0xE7;
+pub(crate) const VK_ATTN: u16 = 0xF6;
+pub(crate) const VK_CRSEL: u16 = 0xF7;
+pub(crate) const VK_EXSEL: u16 = 0xF8;
+pub(crate) const VK_EREOF: u16 = 0xF9;
+pub(crate) const VK_PLAY: u16 = 0xFA;
+pub(crate) const VK_ZOOM: u16 = 0xFB;
+pub(crate) const VK_NONAME: u16 = 0xFC;
+pub(crate) const VK_PA1: u16 = 0xFD;
+pub(crate) const VK_OEM_CLEAR: u16 = 0xFE;
+pub(crate) const VK__none_: u16 = 0xFF;